@@ -0,0 +1,470 @@
+// src-tauri/src/commands/budget_commands.rs
+// Budgets: a spending limit per month or year for a category or a payment
+// method, the budget-vs-actual for a period, and the alert fired when a new
+// transaction pushes a budget past 80% or 100%.
+//
+// Spending counts the expense types (`expense_types_condition()`), in the
+// default currency every `transactions.amount` is recorded in; each row is
+// scaled by its own currency before summing, so a row left in another currency
+// is not read as minor units of the budget's. A category
+// budget covers the category's subcategories too. Periods are Gregorian
+// calendar months and years.
+
+use chrono::{Datelike, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Runtime, State};
+use tauri_plugin_notification::NotificationExt;
+use uuid::Uuid;
+
+use super::category_commands::category_tree_ctes;
+use super::db_commands::default_currency_or_ils;
+use crate::error::{Ten10Error, Ten10Result};
+use crate::models::{Budget, Transaction};
+use crate::money::{to_micro_sql, Money};
+use crate::scheduler::notification_language;
+use crate::transaction_types::{expense_types_condition, EXPENSE_TYPES};
+use crate::DbState;
+
+/// Percentages of a budget at which an alert fires, lowest first.
+const ALERT_THRESHOLDS: [f64; 2] = [80.0, 100.0];
+
+#[derive(Deserialize, Debug)]
+pub struct BudgetPayload {
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub payment_method: Option<String>,
+    pub period: String,
+    pub amount: Money,
+    /// Must be the default currency, which spending is measured in; defaults to it
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub rollover: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BudgetProgress {
+    pub budget: Budget,
+    pub period_start: String,
+    pub period_end: String,
+    pub spent: Money,
+    /// Left over from (negative: overspent in) the earlier periods, with rollover
+    pub carried_over: Money,
+    /// `amount` plus `carried_over`
+    pub available: Money,
+    pub remaining: Money,
+    /// None when nothing is available (earlier overspending used it all up)
+    pub percent_used: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BudgetAlert {
+    pub budget: Budget,
+    /// The highest threshold the transaction crossed
+    pub threshold: f64,
+    /// Infinite when nothing was available
+    pub percent_used: f64,
+}
+
+/// First and last day of the `period` ("monthly" or "yearly") containing `date`.
+fn period_bounds(period: &str, date: NaiveDate) -> Ten10Result<(NaiveDate, NaiveDate)> {
+    let (start, next) = match period {
+        "monthly" => {
+            let start = NaiveDate::from_ymd_opt(date.year(), date.month(), 1);
+            (start, start.and_then(|d| d.checked_add_months(chrono::Months::new(1))))
+        }
+        "yearly" => (
+            NaiveDate::from_ymd_opt(date.year(), 1, 1),
+            NaiveDate::from_ymd_opt(date.year() + 1, 1, 1),
+        ),
+        other => return Err(Ten10Error::validation(format!("Invalid budget period: {}", other))),
+    };
+    match (start, next.and_then(|d| d.pred_opt())) {
+        (Some(start), Some(end)) => Ok((start, end)),
+        _ => Err(Ten10Error::validation(format!("Date out of range: {}", date))),
+    }
+}
+
+/// Number of whole periods from the one starting at `first` to the one starting at `current`.
+fn periods_between(period: &str, first: NaiveDate, current: NaiveDate) -> i64 {
+    let months = (current.year() - first.year()) as i64 * 12 + current.month() as i64 - first.month() as i64;
+    if period == "yearly" {
+        months / 12
+    } else {
+        months
+    }
+}
+
+fn parse_date(value: &str) -> Ten10Result<NaiveDate> {
+    value
+        .get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .ok_or_else(|| Ten10Error::validation(format!("Invalid date: {}", value)))
+}
+
+/// Expenses counted toward `budget` from `start` to `end`, in minor units of its currency.
+fn spent_minor(conn: &Connection, budget: &Budget, start: NaiveDate, end: NaiveDate) -> RusqliteResult<i64> {
+    let (scope, value) = match (&budget.category, &budget.payment_method) {
+        (Some(category), _) => (
+            "(category = ?3 OR category IN (SELECT key FROM category_lineage WHERE ancestor = ?3))",
+            category,
+        ),
+        (None, Some(method)) => ("payment_method = ?3", method),
+        (None, None) => return Ok(0),
+    };
+    let micro: i64 = conn.query_row(
+        &format!(
+            "WITH RECURSIVE {}
+             SELECT COALESCE(SUM({}), 0) FROM transactions
+             WHERE {} AND date >= ?1 AND date <= ?2 AND {}",
+            category_tree_ctes(),
+            to_micro_sql("amount", "currency"),
+            expense_types_condition(),
+            scope
+        ),
+        params![start.to_string(), end.to_string(), value],
+        |row| row.get(0),
+    )?;
+    Ok(Money::from_micro(micro).to_minor(&budget.currency))
+}
+
+fn percent_of(spent: i64, available: i64) -> f64 {
+    if available > 0 {
+        spent as f64 * 100.0 / available as f64
+    } else if spent > 0 {
+        f64::INFINITY
+    } else {
+        0.0
+    }
+}
+
+/// Budget vs actual for the period of `budget` containing `date`.
+pub(crate) fn budget_progress(conn: &Connection, budget: &Budget, date: NaiveDate) -> Ten10Result<BudgetProgress> {
+    let (start, end) = period_bounds(&budget.period, date)?;
+    let amount = budget.amount.to_minor(&budget.currency);
+    let spent = spent_minor(conn, budget, start, end)?;
+
+    let mut carried_over = 0;
+    if budget.rollover {
+        let (first_start, _) = period_bounds(&budget.period, parse_date(&budget.created_at)?)?;
+        let earlier_periods = periods_between(&budget.period, first_start, start);
+        if earlier_periods > 0 {
+            let earlier_end = start.pred_opt().unwrap_or(start);
+            carried_over = amount * earlier_periods - spent_minor(conn, budget, first_start, earlier_end)?;
+        }
+    }
+    let available = amount + carried_over;
+
+    Ok(BudgetProgress {
+        period_start: start.to_string(),
+        period_end: end.to_string(),
+        spent: Money::from_minor(spent, &budget.currency),
+        carried_over: Money::from_minor(carried_over, &budget.currency),
+        available: Money::from_minor(available, &budget.currency),
+        remaining: Money::from_minor(available - spent, &budget.currency),
+        percent_used: (available > 0).then(|| percent_of(spent, available)),
+        budget: budget.clone(),
+    })
+}
+
+fn load_budgets(conn: &Connection) -> RusqliteResult<Vec<Budget>> {
+    conn.prepare("SELECT * FROM budgets ORDER BY period, category, payment_method")?
+        .query_map([], Budget::from_row)?
+        .collect()
+}
+
+fn load_budget(conn: &Connection, id: &str) -> Ten10Result<Budget> {
+    conn.query_row("SELECT * FROM budgets WHERE id = ?1", params![id], Budget::from_row)
+        .optional()?
+        .ok_or_else(|| Ten10Error::not_found("Budget", id))
+}
+
+/// The budgets `transaction` (just saved) pushed past a threshold, i.e. that
+/// were below it without the transaction and are at or above it with it.
+pub(crate) fn budget_alerts_for(conn: &Connection, transaction: &Transaction) -> Ten10Result<Vec<BudgetAlert>> {
+    if !EXPENSE_TYPES.contains(&transaction.transaction_type.as_str()) {
+        return Ok(Vec::new());
+    }
+    let date = parse_date(&transaction.date)?;
+    let budgets: Vec<Budget> = conn
+        .prepare(&format!(
+            "WITH RECURSIVE {}
+             SELECT * FROM budgets
+             WHERE category = ?1
+                OR category IN (SELECT ancestor FROM category_lineage WHERE key = ?1)
+                OR payment_method = ?2",
+            category_tree_ctes()
+        ))?
+        .query_map(params![transaction.category, transaction.payment_method], Budget::from_row)?
+        .collect::<RusqliteResult<_>>()?;
+
+    let mut alerts = Vec::new();
+    for budget in budgets {
+        let progress = budget_progress(conn, &budget, date)?;
+        let available = progress.available.to_minor(&budget.currency);
+        let spent = progress.spent.to_minor(&budget.currency);
+        let before = percent_of(spent - transaction.amount.to_minor(&budget.currency), available);
+        let after = percent_of(spent, available);
+        let crossed = ALERT_THRESHOLDS.iter().rev().find(|&&threshold| before < threshold && after >= threshold);
+        if let Some(&threshold) = crossed {
+            alerts.push(BudgetAlert { budget, threshold, percent_used: after });
+        }
+    }
+    Ok(alerts)
+}
+
+fn alert_text(alert: &BudgetAlert, language: &str) -> (String, String) {
+    let subject = alert.budget.category.as_deref().or(alert.budget.payment_method.as_deref()).unwrap_or_default();
+    let over = alert.threshold >= 100.0;
+    if language == "en" {
+        let period = if alert.budget.period == "yearly" { "yearly" } else { "monthly" };
+        let body = if over {
+            format!("{}: the {} budget has been exceeded.", subject, period)
+        } else {
+            format!("{}: {:.0}% of the {} budget has been used.", subject, alert.percent_used, period)
+        };
+        ("Ten10 - budget".to_string(), body)
+    } else {
+        let period = if alert.budget.period == "yearly" { "השנתי" } else { "החודשי" };
+        let body = if over {
+            format!("{}: חרגת מהתקציב {}.", subject, period)
+        } else {
+            format!("{}: נוצלו {:.0}% מהתקציב {}.", subject, alert.percent_used, period)
+        };
+        ("Ten10 - תקציב".to_string(), body)
+    }
+}
+
+/// Shows a system notification per alert.
+pub(crate) fn notify_budget_alerts<R: Runtime>(app: &AppHandle<R>, alerts: &[BudgetAlert]) {
+    if alerts.is_empty() {
+        return;
+    }
+    let language = notification_language(app);
+    for alert in alerts {
+        let (title, body) = alert_text(alert, &language);
+        if let Err(e) = app.notification().builder().title(title).body(body).show() {
+            eprintln!("[Budgets] Failed to show notification: {}", e);
+        }
+    }
+}
+
+/// Checks and trims the payload; the currency must be the default currency.
+fn validate(conn: &Connection, payload: BudgetPayload) -> Ten10Result<BudgetPayload> {
+    let blank_to_none = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let category = blank_to_none(payload.category);
+    let payment_method = blank_to_none(payload.payment_method);
+    if category.is_some() == payment_method.is_some() {
+        return Err(Ten10Error::validation("A budget is for either a category or a payment method."));
+    }
+    period_bounds(&payload.period, Utc::now().date_naive())?;
+    if payload.amount.to_major() <= 0.0 {
+        return Err(Ten10Error::validation("Budget amount must be positive."));
+    }
+    let default_currency = default_currency_or_ils(conn)?;
+    let currency = blank_to_none(payload.currency).unwrap_or_else(|| default_currency.clone());
+    if currency != default_currency {
+        return Err(Ten10Error::validation(format!(
+            "Budgets are in the default currency ({}), not {}.",
+            default_currency, currency
+        )));
+    }
+    Ok(BudgetPayload { category, payment_method, currency: Some(currency), ..payload })
+}
+
+/// All budgets.
+#[tauri::command]
+pub fn get_budgets(db_state: State<'_, DbState>) -> Result<Vec<Budget>, Ten10Error> {
+    let conn = db_state.read()?;
+    Ok(load_budgets(&conn)?)
+}
+
+#[tauri::command]
+pub fn add_budget(db_state: State<'_, DbState>, budget: BudgetPayload) -> Result<Budget, Ten10Error> {
+    let conn = db_state.0.lock()?;
+    let payload = validate(&conn, budget)?;
+    let currency = payload.currency.unwrap_or_default();
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO budgets (id, category, payment_method, period, amount, currency, rollover, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+        params![
+            id,
+            payload.category,
+            payload.payment_method,
+            payload.period,
+            payload.amount.to_minor(&currency),
+            currency,
+            payload.rollover,
+            Utc::now().to_rfc3339(),
+        ],
+    )?;
+    load_budget(&conn, &id)
+}
+
+/// Replaces every field of budget `id`; rollover keeps counting from its creation.
+#[tauri::command]
+pub fn update_budget(db_state: State<'_, DbState>, id: String, budget: BudgetPayload) -> Result<Budget, Ten10Error> {
+    let conn = db_state.0.lock()?;
+    let payload = validate(&conn, budget)?;
+    let currency = payload.currency.unwrap_or_default();
+    let updated = conn.execute(
+        "UPDATE budgets SET category = ?2, payment_method = ?3, period = ?4, amount = ?5, currency = ?6,
+             rollover = ?7, updated_at = ?8
+         WHERE id = ?1",
+        params![
+            id,
+            payload.category,
+            payload.payment_method,
+            payload.period,
+            payload.amount.to_minor(&currency),
+            currency,
+            payload.rollover,
+            Utc::now().to_rfc3339(),
+        ],
+    )?;
+    if updated == 0 {
+        return Err(Ten10Error::not_found("Budget", id));
+    }
+    load_budget(&conn, &id)
+}
+
+#[tauri::command]
+pub fn delete_budget(db_state: State<'_, DbState>, id: String) -> Result<(), Ten10Error> {
+    let conn = db_state.0.lock()?;
+    match conn.execute("DELETE FROM budgets WHERE id = ?1", params![id])? {
+        0 => Err(Ten10Error::not_found("Budget", id)),
+        _ => Ok(()),
+    }
+}
+
+/// Budget vs actual of every budget, for the periods containing `date` (today by default).
+#[tauri::command]
+pub fn get_budget_progress(
+    db_state: State<'_, DbState>,
+    date: Option<String>,
+) -> Result<Vec<BudgetProgress>, Ten10Error> {
+    let date = match date {
+        Some(date) => parse_date(&date)?,
+        None => chrono::Local::now().date_naive(),
+    };
+    let conn = db_state.read()?;
+    load_budgets(&conn)?.iter().map(|budget| budget_progress(&conn, budget, date)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO categories (key, parent_key, sort_order, created_at, updated_at) VALUES
+                ('rent', 'housing', 0, '0', '0');
+            INSERT INTO budgets (id, category, payment_method, period, amount, currency, rollover, created_at, updated_at) VALUES
+                ('housing', 'housing', NULL, 'monthly', 400000, 'ILS', 1, '2024-01-15T00:00:00Z', '0'),
+                ('visa', NULL, 'visa', 'yearly', 100000, 'ILS', 0, '2024-01-01T00:00:00Z', '0');
+            INSERT INTO transactions (id, date, amount, currency, type, category, payment_method) VALUES
+                ('t1', '2024-01-05', 300000, 'ILS', 'expense', 'rent', 'visa'),
+                ('t2', '2024-02-05', 350000, 'ILS', 'recognized-expense', 'housing', NULL),
+                ('t3', '2024-02-10', 20000, 'ILS', 'expense', 'food', 'visa'),
+                ('t4', '2024-02-11', 99999, 'ILS', 'income', 'housing', 'visa');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn transaction(date: &str, amount: f64, category: &str) -> Transaction {
+        serde_json::from_value(serde_json::json!({
+            "id": "new", "date": date, "amount": amount, "currency": "ILS", "type": "expense", "category": category
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn progress_covers_subcategories_and_rolls_over() {
+        let conn = test_db();
+        let date = NaiveDate::from_ymd_opt(2024, 2, 20).unwrap();
+        let progress = budget_progress(&conn, &load_budget(&conn, "housing").unwrap(), date).unwrap();
+        assert_eq!((progress.period_start.as_str(), progress.period_end.as_str()), ("2024-02-01", "2024-02-29"));
+        assert_eq!(progress.spent.to_major(), 3500.0);
+        assert_eq!(progress.carried_over.to_major(), 1000.0);
+        assert_eq!(progress.available.to_major(), 5000.0);
+        assert_eq!(progress.remaining.to_major(), 1500.0);
+        assert_eq!(progress.percent_used, Some(70.0));
+
+        let visa = budget_progress(&conn, &load_budget(&conn, "visa").unwrap(), date).unwrap();
+        assert_eq!((visa.spent.to_major(), visa.carried_over.to_major()), (3200.0, 0.0));
+        assert_eq!(visa.percent_used, Some(320.0));
+    }
+
+    #[test]
+    fn alerts_fire_only_when_a_threshold_is_crossed() {
+        let conn = test_db();
+        let thresholds = |t: &Transaction| -> Vec<(String, f64)> {
+            budget_alerts_for(&conn, t).unwrap().into_iter().map(|a| (a.budget.id, a.threshold)).collect()
+        };
+
+        // 3500 -> 4200 of 5000 crosses 80%.
+        let t5 = transaction("2024-02-21", 700.0, "rent");
+        conn.execute(
+            "INSERT INTO transactions (id, date, amount, currency, type, category) VALUES ('t5', ?1, 70000, 'ILS', 'expense', 'rent')",
+            params![t5.date],
+        )
+        .unwrap();
+        assert_eq!(thresholds(&t5), vec![("housing".to_string(), 80.0)]);
+
+        // 4200 -> 4300 stays between the thresholds.
+        conn.execute(
+            "INSERT INTO transactions (id, date, amount, currency, type, category) VALUES ('t6', '2024-02-22', 10000, 'ILS', 'expense', 'housing')",
+            [],
+        )
+        .unwrap();
+        assert!(thresholds(&transaction("2024-02-22", 100.0, "housing")).is_empty());
+
+        let income = Transaction { transaction_type: "income".to_string(), ..transaction("2024-02-22", 1.0, "housing") };
+        assert!(thresholds(&income).is_empty());
+        assert_eq!(periods_between("yearly", NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(), NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()), 1);
+    }
+
+    #[test]
+    fn spending_scales_each_row_by_its_own_currency() {
+        let conn = test_db();
+        conn.execute(
+            "INSERT INTO transactions (id, date, amount, currency, type, category) VALUES ('t7', '2024-03-01', 4500, 'JPY', 'expense', 'rent')",
+            [],
+        )
+        .unwrap();
+        let housing = load_budget(&conn, "housing").unwrap();
+        let progress = budget_progress(&conn, &housing, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()).unwrap();
+        assert_eq!((progress.spent.to_major(), progress.available.to_major()), (4500.0, 5500.0));
+
+        // 0 -> 4500 of 5500 crosses 80%.
+        let t7 = Transaction { currency: "JPY".to_string(), ..transaction("2024-03-01", 4500.0, "rent") };
+        let alerts = budget_alerts_for(&conn, &t7).unwrap();
+        assert_eq!(alerts.iter().map(|a| (a.budget.id.as_str(), a.threshold)).collect::<Vec<_>>(), vec![("housing", 80.0)]);
+    }
+
+    #[test]
+    fn budgets_follow_the_default_currency_in_client_preferences() {
+        let conn = test_db();
+        conn.execute(
+            "INSERT INTO app_settings (key, value) VALUES ('client_preferences', '{\"defaultCurrency\":\"USD\"}')",
+            [],
+        )
+        .unwrap();
+        let payload = |currency: Option<&str>| -> BudgetPayload {
+            serde_json::from_value(serde_json::json!({
+                "category": "food", "period": "monthly", "amount": 250.0, "currency": currency
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(validate(&conn, payload(Some("USD"))).unwrap().currency.as_deref(), Some("USD"));
+        assert_eq!(validate(&conn, payload(None)).unwrap().currency.as_deref(), Some("USD"));
+        assert!(matches!(validate(&conn, payload(Some("ILS"))), Err(Ten10Error::Validation(_))));
+    }
+}
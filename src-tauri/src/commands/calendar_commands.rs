@@ -0,0 +1,139 @@
+use chrono::{Datelike, Local, NaiveDate};
+use serde::Serialize;
+
+use crate::hebrew_calendar;
+use crate::error::Ten10Error;
+
+/// Gregorian bounds of a named period, ready to pass to any range command.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CalendarDateRange {
+    pub start_date: String,
+    pub end_date: String,
+    /// "2024", "2024-05", "5785" or "5785-07" (Hebrew: biblical month number)
+    pub label: String,
+}
+
+fn range(start: NaiveDate, end: NaiveDate, label: String) -> CalendarDateRange {
+    CalendarDateRange {
+        start_date: start.format("%Y-%m-%d").to_string(),
+        end_date: end.format("%Y-%m-%d").to_string(),
+        label,
+    }
+}
+
+/// Presets: "this_month", "this_year", "year_to_date" (since 1 January or since
+/// Rosh Hashana, through `reference`) and "previous_year".
+pub(crate) fn calendar_range(
+    calendar: &str,
+    preset: &str,
+    reference: NaiveDate,
+) -> Result<CalendarDateRange, Ten10Error> {
+    let invalid = || Ten10Error::validation(format!("Date out of range: {}", reference));
+    match calendar {
+        "gregorian" => {
+            let year = reference.year();
+            let year_bounds = |y: i32| {
+                NaiveDate::from_ymd_opt(y, 1, 1)
+                    .zip(NaiveDate::from_ymd_opt(y, 12, 31))
+                    .ok_or_else(invalid)
+            };
+            match preset {
+                "this_month" => {
+                    let start = reference.with_day(1).ok_or_else(invalid)?;
+                    let end = start
+                        .checked_add_months(chrono::Months::new(1))
+                        .and_then(|d| d.pred_opt())
+                        .ok_or_else(invalid)?;
+                    Ok(range(start, end, reference.format("%Y-%m").to_string()))
+                }
+                "this_year" => {
+                    let (start, end) = year_bounds(year)?;
+                    Ok(range(start, end, year.to_string()))
+                }
+                "year_to_date" => {
+                    let (start, _) = year_bounds(year)?;
+                    Ok(range(start, reference, year.to_string()))
+                }
+                "previous_year" => {
+                    let (start, end) = year_bounds(year - 1)?;
+                    Ok(range(start, end, (year - 1).to_string()))
+                }
+                other => Err(Ten10Error::validation(format!("Invalid range preset: {}", other))),
+            }
+        }
+        "hebrew" => {
+            let today = hebrew_calendar::from_gregorian(reference);
+            match preset {
+                "this_month" => {
+                    let (start, end) =
+                        hebrew_calendar::month_bounds(today.year, today.month).ok_or_else(invalid)?;
+                    Ok(range(start, end, format!("{}-{:02}", today.year, today.month)))
+                }
+                "this_year" => {
+                    let (start, end) = hebrew_calendar::year_bounds(today.year).ok_or_else(invalid)?;
+                    Ok(range(start, end, today.year.to_string()))
+                }
+                "year_to_date" => {
+                    let start = hebrew_calendar::rosh_hashana(today.year).ok_or_else(invalid)?;
+                    Ok(range(start, reference, today.year.to_string()))
+                }
+                "previous_year" => {
+                    let (start, end) =
+                        hebrew_calendar::year_bounds(today.year - 1).ok_or_else(invalid)?;
+                    Ok(range(start, end, (today.year - 1).to_string()))
+                }
+                other => Err(Ten10Error::validation(format!("Invalid range preset: {}", other))),
+            }
+        }
+        other => Err(Ten10Error::validation(format!("Invalid calendar: {}", other))),
+    }
+}
+
+/// Resolves a named period ("this_month", "this_year", "year_to_date",
+/// "previous_year") in the Gregorian or Hebrew calendar around `reference_date`
+/// (today when omitted).
+#[tauri::command]
+pub fn get_calendar_date_range(
+    calendar: String,
+    preset: String,
+    reference_date: Option<String>,
+) -> Result<CalendarDateRange, Ten10Error> {
+    let reference = match reference_date {
+        Some(s) => NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+            .map_err(|e| Ten10Error::validation(format!("Error parsing reference_date '{}': {}", s, e)))?,
+        None => Local::now().date_naive(),
+    };
+    calendar_range(&calendar, &preset, reference)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(calendar: &str, preset: &str, reference: &str) -> (String, String, String) {
+        let reference = NaiveDate::parse_from_str(reference, "%Y-%m-%d").unwrap();
+        let r = calendar_range(calendar, preset, reference).unwrap();
+        (r.start_date, r.end_date, r.label)
+    }
+
+    fn expect(start: &str, end: &str, label: &str) -> (String, String, String) {
+        (start.to_string(), end.to_string(), label.to_string())
+    }
+
+    #[test]
+    fn hebrew_presets_follow_rosh_hashana() {
+        assert_eq!(resolve("hebrew", "this_year", "2025-01-15"), expect("2024-10-03", "2025-09-22", "5785"));
+        assert_eq!(resolve("hebrew", "year_to_date", "2025-01-15"), expect("2024-10-03", "2025-01-15", "5785"));
+        assert_eq!(resolve("hebrew", "previous_year", "2025-01-15"), expect("2023-09-16", "2024-10-02", "5784"));
+        // 2024-03-24 is 14 Adar II 5784
+        assert_eq!(resolve("hebrew", "this_month", "2024-03-24"), expect("2024-03-11", "2024-04-08", "5784-13"));
+    }
+
+    #[test]
+    fn gregorian_presets() {
+        assert_eq!(resolve("gregorian", "this_month", "2024-02-10"), expect("2024-02-01", "2024-02-29", "2024-02"));
+        assert_eq!(resolve("gregorian", "year_to_date", "2024-02-10"), expect("2024-01-01", "2024-02-10", "2024"));
+        assert_eq!(resolve("gregorian", "previous_year", "2024-02-10"), expect("2023-01-01", "2023-12-31", "2023"));
+        assert!(calendar_range("gregorian", "fortnight", NaiveDate::MIN).is_err());
+    }
+}
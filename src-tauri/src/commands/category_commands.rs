@@ -0,0 +1,281 @@
+// src-tauri/src/commands/category_commands.rs
+// The category tree: categories with an optional parent (housing > rent), the
+// base types they apply to, icon, color and ordering.
+//
+// Transactions keep storing the category value itself; a `categories` row with
+// that `key` places it in the tree. Values without a row (typed before they were
+// added to the tree) behave as top-level categories.
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
+use serde::Deserialize;
+use tauri::State;
+
+use crate::error::{Ten10Error, Ten10Result};
+use crate::models::Category;
+use crate::DbState;
+
+/// Base types a category can apply to.
+const CATEGORY_TYPES: [&str; 3] = ["income", "expense", "donation"];
+
+/// Deepest level the tree queries follow; guards against a cycle slipping in.
+const MAX_DEPTH: i64 = 32;
+
+/// Recursive CTEs (to follow `WITH RECURSIVE`) over the tree:
+/// `category_lineage(key, ancestor, distance)` pairs every category with itself
+/// and each of its ancestors, `category_depth(key, depth)` gives its level (0 at
+/// the top).
+pub(crate) fn category_tree_ctes() -> String {
+    format!(
+        "category_lineage(key, ancestor, distance) AS (
+            SELECT key, key, 0 FROM categories
+            UNION
+            SELECT l.key, c.parent_key, l.distance + 1
+            FROM category_lineage l JOIN categories c ON c.key = l.ancestor
+            WHERE c.parent_key IS NOT NULL AND l.distance < {}
+        ),
+        category_depth(key, depth) AS (
+            SELECT key, MAX(distance) FROM category_lineage GROUP BY key
+        )",
+        MAX_DEPTH
+    )
+}
+
+/// Level of `key` in the tree, None when it has no `categories` row.
+pub(crate) fn category_depth(conn: &Connection, key: &str) -> RusqliteResult<Option<i64>> {
+    conn.query_row(
+        &format!("WITH RECURSIVE {} SELECT depth FROM category_depth WHERE key = ?1", category_tree_ctes()),
+        params![key],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct CategoryPayload {
+    pub key: String,
+    #[serde(default)]
+    pub parent_key: Option<String>,
+    #[serde(default)]
+    pub transaction_types: Vec<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Placed last among its siblings when missing
+    #[serde(default)]
+    pub sort_order: Option<i64>,
+}
+
+fn load_category(conn: &Connection, key: &str) -> Ten10Result<Category> {
+    conn.query_row("SELECT * FROM categories WHERE key = ?1", params![key], Category::from_row)
+        .optional()?
+        .ok_or_else(|| Ten10Error::not_found("Category", key))
+}
+
+/// Whether `candidate` is `key` or one of its descendants.
+fn is_in_subtree(conn: &Connection, key: &str, candidate: &str) -> RusqliteResult<bool> {
+    conn.query_row(
+        &format!(
+            "WITH RECURSIVE {} SELECT EXISTS(SELECT 1 FROM category_lineage WHERE key = ?2 AND ancestor = ?1)",
+            category_tree_ctes()
+        ),
+        params![key, candidate],
+        |row| row.get(0),
+    )
+}
+
+/// Trims and checks the payload against the tree: the parent must exist and
+/// must not be the category itself or one of its descendants.
+fn validate(conn: &Connection, payload: CategoryPayload) -> Ten10Result<CategoryPayload> {
+    let key = payload.key.trim().to_string();
+    if key.is_empty() {
+        return Err(Ten10Error::validation("Category name must not be empty."));
+    }
+    let blank_to_none = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let parent_key = blank_to_none(payload.parent_key);
+    if let Some(parent) = &parent_key {
+        load_category(conn, parent)?;
+        if is_in_subtree(conn, &key, parent)? {
+            return Err(Ten10Error::validation(format!(
+                "'{}' cannot be placed under itself or one of its subcategories.",
+                key
+            )));
+        }
+    }
+    let mut transaction_types: Vec<String> = Vec::new();
+    for t in payload.transaction_types.iter().map(|t| t.trim()) {
+        if !CATEGORY_TYPES.contains(&t) {
+            return Err(Ten10Error::validation(format!("Invalid transaction type: {}", t)));
+        }
+        if !transaction_types.iter().any(|existing| existing == t) {
+            transaction_types.push(t.to_string());
+        }
+    }
+    Ok(CategoryPayload {
+        key,
+        parent_key,
+        transaction_types,
+        icon: blank_to_none(payload.icon),
+        color: blank_to_none(payload.color),
+        sort_order: payload.sort_order,
+    })
+}
+
+fn save_category_row(conn: &Connection, payload: &CategoryPayload) -> Ten10Result<()> {
+    let sort_order = match payload.sort_order {
+        Some(order) => order,
+        None => conn.query_row(
+            "SELECT COALESCE(MAX(sort_order) + 1, 0) FROM categories WHERE parent_key IS ?1 AND key != ?2",
+            params![payload.parent_key, payload.key],
+            |row| row.get(0),
+        )?,
+    };
+    conn.execute(
+        "INSERT INTO categories (key, parent_key, transaction_types, icon, color, sort_order, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+         ON CONFLICT(key) DO UPDATE SET
+             parent_key = excluded.parent_key,
+             transaction_types = excluded.transaction_types,
+             icon = excluded.icon,
+             color = excluded.color,
+             sort_order = excluded.sort_order,
+             updated_at = excluded.updated_at",
+        params![
+            payload.key,
+            payload.parent_key,
+            payload.transaction_types.join(","),
+            payload.icon,
+            payload.color,
+            sort_order,
+            Utc::now().to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Carries the tree over when the category value `from` is renamed to `to` on
+/// the transactions: its row is renamed, or, when `to` already has one, its
+/// subcategories move under `to` and the row is dropped.
+pub(crate) fn rename_category_key(conn: &Connection, from: &str, to: &str) -> Ten10Result<()> {
+    let now = Utc::now().to_rfc3339();
+    let target_exists: bool =
+        conn.query_row("SELECT EXISTS(SELECT 1 FROM categories WHERE key = ?1)", params![to], |row| row.get(0))?;
+    if target_exists {
+        conn.execute(
+            "UPDATE categories SET parent_key = CASE WHEN key = ?2 THEN NULL ELSE ?2 END, updated_at = ?3
+             WHERE parent_key = ?1",
+            params![from, to, now],
+        )?;
+        conn.execute("DELETE FROM categories WHERE key = ?1", params![from])?;
+    } else {
+        // `parent_key` follows through ON UPDATE CASCADE.
+        conn.execute("UPDATE categories SET key = ?2, updated_at = ?3 WHERE key = ?1", params![from, to, now])?;
+    }
+    Ok(())
+}
+
+/// The categories applying to `transaction_type` (a base type), or all of them,
+/// ordered for display; the frontend builds the tree from `parent_key`.
+#[tauri::command]
+pub fn get_categories(
+    db_state: State<'_, DbState>,
+    transaction_type: Option<String>,
+) -> Result<Vec<Category>, Ten10Error> {
+    let conn = db_state.read()?;
+    let mut stmt = conn.prepare(
+        "SELECT * FROM categories
+         WHERE ?1 IS NULL OR transaction_types = '' OR ',' || transaction_types || ',' LIKE '%,' || ?1 || ',%'
+         ORDER BY sort_order, key",
+    )?;
+    let categories = stmt
+        .query_map(params![transaction_type.filter(|t| !t.is_empty())], Category::from_row)?
+        .collect::<RusqliteResult<Vec<_>>>()?;
+    Ok(categories)
+}
+
+/// Adds a category or replaces the attributes of an existing one (by key).
+#[tauri::command]
+pub fn save_category(db_state: State<'_, DbState>, category: CategoryPayload) -> Result<Category, Ten10Error> {
+    let mut conn = db_state.0.lock()?;
+    let tx = conn.transaction()?;
+    let payload = validate(&tx, category)?;
+    save_category_row(&tx, &payload)?;
+    let saved = load_category(&tx, &payload.key)?;
+    tx.commit()?;
+    Ok(saved)
+}
+
+/// Removes a category from the tree; its subcategories move up to its parent.
+/// Transactions keep the value and show it as a top-level category.
+#[tauri::command]
+pub fn delete_category(db_state: State<'_, DbState>, key: String) -> Result<(), Ten10Error> {
+    let mut conn = db_state.0.lock()?;
+    let tx = conn.transaction()?;
+    let category = load_category(&tx, &key)?;
+    tx.execute(
+        "UPDATE categories SET parent_key = ?2, updated_at = ?3 WHERE parent_key = ?1",
+        params![key, category.parent_key, Utc::now().to_rfc3339()],
+    )?;
+    tx.execute("DELETE FROM categories WHERE key = ?1", params![key])?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn
+    }
+
+    fn add(conn: &Connection, key: &str, parent: Option<&str>) -> Ten10Result<()> {
+        let payload = validate(
+            conn,
+            CategoryPayload {
+                key: key.to_string(),
+                parent_key: parent.map(str::to_string),
+                transaction_types: vec!["expense".to_string()],
+                ..Default::default()
+            },
+        )?;
+        save_category_row(conn, &payload)
+    }
+
+    #[test]
+    fn builds_a_tree_without_cycles() {
+        let conn = test_db();
+        add(&conn, "rent", Some("housing")).unwrap();
+        add(&conn, "arnona", Some("housing")).unwrap();
+        add(&conn, "arnona north", Some("arnona")).unwrap();
+
+        assert_eq!(category_depth(&conn, "housing").unwrap(), Some(0));
+        assert_eq!(category_depth(&conn, "arnona north").unwrap(), Some(2));
+        assert_eq!(category_depth(&conn, "unknown").unwrap(), None);
+        let order: i64 = conn
+            .query_row("SELECT sort_order FROM categories WHERE key = 'arnona'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(order, 1);
+
+        assert!(matches!(add(&conn, "housing", Some("arnona north")), Err(Ten10Error::Validation(_))));
+        assert!(matches!(add(&conn, "housing", Some("housing")), Err(Ten10Error::Validation(_))));
+        assert!(matches!(add(&conn, "x", Some("missing")), Err(Ten10Error::NotFound { .. })));
+    }
+
+    #[test]
+    fn renaming_a_key_keeps_its_subtree() {
+        let conn = test_db();
+        add(&conn, "Rent", None).unwrap();
+        add(&conn, "Rent Tel Aviv", Some("Rent")).unwrap();
+
+        rename_category_key(&conn, "Rent", "rent").unwrap();
+        assert_eq!(load_category(&conn, "Rent Tel Aviv").unwrap().parent_key.as_deref(), Some("rent"));
+
+        rename_category_key(&conn, "rent", "housing").unwrap();
+        assert!(load_category(&conn, "rent").is_err());
+        assert_eq!(load_category(&conn, "Rent Tel Aviv").unwrap().parent_key.as_deref(), Some("housing"));
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use chrono::{Datelike, Months, NaiveDate};
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
@@ -5,16 +7,106 @@ use tauri::State;
 // use log::{info, error, warn}; // No longer using log crate macros
 
 use crate::DbState; // Assuming DbState is defined in main.rs or lib.rs
-use crate::transaction_types::{
-    expense_types_case_condition, income_types_case_condition, donation_types_case_condition,
-};
+use crate::hebrew_calendar;
+use crate::transaction_types::{DONATION_TYPES, EXPENSE_TYPES, INCOME_TYPES};
+use crate::error::Ten10Error;
+use crate::money::{to_micro_sql, Money};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DesktopMonthlyDataPoint {
-    month_label: String, // YYYY-MM
-    income: f64,
-    donations: f64,
-    expenses: f64,
+    pub(crate) month_label: String, // YYYY-MM (Hebrew calendar: Hebrew year and biblical month number, e.g. 5784-13)
+    pub(crate) month_name: Option<String>, // Hebrew calendar only, e.g. "Adar II"
+    pub(crate) income: f64,
+    pub(crate) donations: f64,
+    pub(crate) expenses: f64,
+    /// Totals from the first month returned up to and including this one
+    #[serde(default)]
+    pub(crate) cumulative: MonthlyTotals,
+    /// Amount per transaction type; only when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) by_type: Option<BTreeMap<String, f64>>,
+    /// Totals per currency paid in; only when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) by_currency: Option<BTreeMap<String, MonthlyTotals>>,
+    /// Totals of chomesh-flagged rows and of the rest; only when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) by_chomesh: Option<ChomeshTotals>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct MonthlyTotals {
+    pub(crate) income: f64,
+    pub(crate) donations: f64,
+    pub(crate) expenses: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChomeshTotals {
+    pub(crate) chomesh: MonthlyTotals,
+    pub(crate) regular: MonthlyTotals,
+}
+
+/// Most months one monthly summary covers.
+pub(crate) const MAX_SUMMARY_MONTHS: i32 = 120;
+
+struct MonthPeriod {
+    label: String,
+    name: Option<String>,
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+/// The `num_months` Gregorian months ending with the month of `end_date`, oldest first.
+fn gregorian_month_periods(end_date: NaiveDate, num_months: i32) -> Result<Vec<MonthPeriod>, Ten10Error> {
+    let end_month_start_date = end_date
+        .with_day(1)
+        .ok_or_else(|| Ten10Error::validation(format!("Invalid date '{}': cannot normalize to first of month", end_date)))?;
+    let start_date_actual = end_month_start_date
+        .checked_sub_months(Months::new((num_months - 1) as u32))
+        .ok_or_else(|| Ten10Error::validation(format!("Date range out of bounds: {} months before {}", num_months, end_month_start_date)))?;
+
+    let mut periods = Vec::new();
+    let mut current_month_iter = start_date_actual;
+    while current_month_iter <= end_month_start_date {
+        let next_month_start = current_month_iter
+            .checked_add_months(Months::new(1))
+            .ok_or_else(|| Ten10Error::validation(format!("Date overflow iterating past {}", current_month_iter)))?;
+        let month_end = next_month_start
+            .checked_sub_days(chrono::Days::new(1))
+            .ok_or_else(|| Ten10Error::validation(format!("Date underflow computing end of month {}", current_month_iter)))?;
+        periods.push(MonthPeriod {
+            label: current_month_iter.format("%Y-%m").to_string(),
+            name: None,
+            start: current_month_iter,
+            end: month_end,
+        });
+        current_month_iter = next_month_start;
+    }
+    Ok(periods)
+}
+
+/// The `num_months` Hebrew months ending with the Hebrew month of `end_date`, oldest
+/// first. Leap years yield both Adar I (12) and Adar II (13).
+fn hebrew_month_periods(end_date: NaiveDate, num_months: i32) -> Result<Vec<MonthPeriod>, Ten10Error> {
+    let end = hebrew_calendar::from_gregorian(end_date);
+    let (mut year, mut month) = (end.year, end.month);
+    for _ in 1..num_months {
+        (year, month) = hebrew_calendar::prev_month(year, month);
+    }
+
+    let mut periods = Vec::new();
+    for _ in 0..num_months {
+        let (start, end) = hebrew_calendar::month_bounds(year, month)
+            .ok_or_else(|| Ten10Error::validation(format!("Invalid Hebrew month {}-{}", year, month)))?;
+        periods.push(MonthPeriod {
+            label: format!("{}-{:02}", year, month),
+            name: Some(hebrew_calendar::month_name(year, month).to_string()),
+            start,
+            end,
+        });
+        (year, month) = hebrew_calendar::next_month(year, month);
+    }
+    Ok(periods)
 }
 
 #[tauri::command]
@@ -22,17 +114,19 @@ pub fn get_desktop_monthly_financial_summary(
     db_state: State<'_, DbState>,
     end_date_str: String, // Expected ISO format 'YYYY-MM-DD'
     num_months: i32,
-) -> Result<Vec<DesktopMonthlyDataPoint>, String> {
+    calendar: Option<String>, // "gregorian" (default) | "hebrew"
+    breakdowns: Option<MonthlySummaryBreakdowns>,
+) -> Result<Vec<DesktopMonthlyDataPoint>, Ten10Error> {
     println!(
-        "[Rust Chart] get_desktop_monthly_financial_summary called with endDateStr: {}, num_months: {}",
+        "[Rust Chart] get_desktop_monthly_financial_summary called with endDateStr: {}, num_months: {}, calendar: {:?}",
         end_date_str,
-        num_months
+        num_months,
+        calendar
     );
 
-    let conn_guard = db_state.0.lock().map_err(|e| {
-        eprintln!("[Rust Chart] Error locking DB: {}", e.to_string());
-        e.to_string()
-    })?;
+    let conn_guard = db_state
+        .read()
+        .inspect_err(|e| eprintln!("[Rust Chart] Error locking DB: {}", e))?;
     let conn: &Connection = &*conn_guard;
 
     let end_date_actual = NaiveDate::parse_from_str(&end_date_str, "%Y-%m-%d").map_err(|e| {
@@ -40,108 +134,185 @@ pub fn get_desktop_monthly_financial_summary(
             "[Rust Chart] Error parsing end_date_str '{}': {}",
             end_date_str, e
         );
-        format!("Error parsing end_date '{}': {}", end_date_str, e)
+        Ten10Error::validation(format!("Error parsing end_date '{}': {}", end_date_str, e))
     })?;
     println!("[Rust Chart] Parsed end_date_actual: {}", end_date_actual);
 
-    let end_month_start_date = end_date_actual
-        .with_day(1)
-        .ok_or_else(|| format!("Invalid date '{}': cannot normalize to first of month", end_date_actual))?;
-    let start_date_actual = end_month_start_date
-        .checked_sub_months(Months::new((num_months - 1) as u32))
-        .ok_or_else(|| format!("Date range out of bounds: {} months before {}", num_months, end_month_start_date))?;
+    query_monthly_summary(
+        conn,
+        end_date_actual,
+        num_months,
+        calendar.as_deref().unwrap_or("gregorian"),
+        breakdowns.unwrap_or_default(),
+    )
+}
 
-    println!(
-        "[Rust Chart] Calculated start_date_actual (first day of period): {}",
-        start_date_actual
-    );
-    println!(
-        "[Rust Chart] Calculated end_month_start_date (first day of last month in period): {}",
-        end_month_start_date
-    );
+/// Which breakdowns `query_monthly_summary` adds to each month.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MonthlySummaryBreakdowns {
+    /// Per transaction type, e.g. "income" and "exempt-income" separately
+    pub by_type: bool,
+    /// Per currency paid in (`original_currency` for converted rows), amounts in the default currency
+    pub by_currency: bool,
+    /// Chomesh-flagged rows apart from the rest
+    pub by_chomesh: bool,
+}
 
-    let mut results: Vec<DesktopMonthlyDataPoint> = Vec::new();
-    let mut current_month_iter = start_date_actual;
+/// Micro-unit sums of one month, or of one slice of it.
+#[derive(Debug, Clone, Copy, Default)]
+struct MicroTotals {
+    income: i64,
+    donations: i64,
+    expenses: i64,
+}
 
-    while current_month_iter <= end_month_start_date {
-        let month_start_str = current_month_iter.format("%Y-%m-01").to_string();
-        let next_month_start = current_month_iter
-            .checked_add_months(Months::new(1))
-            .ok_or_else(|| format!("Date overflow iterating past {}", current_month_iter))?;
-        let month_end_str = next_month_start
-            .checked_sub_days(chrono::Days::new(1))
-            .ok_or_else(|| format!("Date underflow computing end of month {}", current_month_iter))?
-            .format("%Y-%m-%d")
-            .to_string();
-        let month_label_str = current_month_iter.format("%Y-%m").to_string();
-
-        println!(
-            "[Rust Chart] Querying for month: {}, range: {} to {}",
-            month_label_str, month_start_str, month_end_str
-        );
+impl MicroTotals {
+    /// Adds `micro` of a `transaction_type` row; types outside the three groups are ignored.
+    fn add(&mut self, transaction_type: &str, micro: i64) {
+        if INCOME_TYPES.contains(&transaction_type) {
+            self.income += micro;
+        } else if DONATION_TYPES.contains(&transaction_type) {
+            self.donations += micro;
+        } else if EXPENSE_TYPES.contains(&transaction_type) {
+            self.expenses += micro;
+        }
+    }
 
-        let sql_query = format!(
-            "SELECT 
-                COALESCE(SUM({}), 0) as income, 
-                COALESCE(SUM({}), 0) as donations, 
-                COALESCE(SUM({}), 0) as expenses 
-             FROM transactions 
-             WHERE date >= ?1 AND date <= ?2",
-            income_types_case_condition(),
-            donation_types_case_condition(),
-            expense_types_case_condition()
-        );
+    fn accumulate(&mut self, other: MicroTotals) {
+        self.income += other.income;
+        self.donations += other.donations;
+        self.expenses += other.expenses;
+    }
+
+    fn to_major(self) -> MonthlyTotals {
+        let major = |micro| Money::from_micro(micro).to_major();
+        MonthlyTotals { income: major(self.income), donations: major(self.donations), expenses: major(self.expenses) }
+    }
+}
+
+/// The month a grouped `bucket` (`YYYY-MM`, or `YYYY-MM-DD` for the Hebrew
+/// calendar) falls in, by index into `periods`.
+fn period_index(periods: &[MonthPeriod], bucket: &str) -> Option<usize> {
+    match NaiveDate::parse_from_str(bucket, "%Y-%m-%d") {
+        Ok(day) => periods.iter().position(|p| p.start <= day && day <= p.end),
+        Err(_) => periods.iter().position(|p| p.label == bucket),
+    }
+}
+
+/// Per-month accumulation of the grouped rows.
+#[derive(Default)]
+struct MonthAccumulator {
+    totals: MicroTotals,
+    by_type: BTreeMap<String, i64>,
+    by_currency: BTreeMap<String, MicroTotals>,
+    chomesh: MicroTotals,
+    regular: MicroTotals,
+}
+
+/// Income, donations and expenses of the `num_months` months ending with the month of
+/// `end_date`, oldest first, with running totals and the requested `breakdowns`.
+/// Months without transactions are included with zeros. Also feeds the summary sheet
+/// of the XLSX export.
+///
+/// Everything comes from one query grouped by month (by day for the Hebrew calendar,
+/// whose months do not follow `substr(date, 1, 7)`), then folded here in micro-units.
+/// Each row is scaled by its own currency before summing, so a stray row in another
+/// currency is not read as minor units of the default one.
+pub(crate) fn query_monthly_summary(
+    conn: &Connection,
+    end_date_actual: NaiveDate,
+    num_months: i32,
+    calendar: &str,
+    breakdowns: MonthlySummaryBreakdowns,
+) -> Result<Vec<DesktopMonthlyDataPoint>, Ten10Error> {
+    if !(1..=MAX_SUMMARY_MONTHS).contains(&num_months) {
+        return Err(Ten10Error::validation(format!("Invalid number of months: {}", num_months)));
+    }
+    let (periods, bucket) = match calendar {
+        "gregorian" => (gregorian_month_periods(end_date_actual, num_months)?, "substr(date, 1, 7)"),
+        "hebrew" => (hebrew_month_periods(end_date_actual, num_months)?, "substr(date, 1, 10)"),
+        other => return Err(Ten10Error::validation(format!("Invalid calendar: {}", other))),
+    };
+    let (Some(first), Some(last)) = (periods.first(), periods.last()) else {
+        return Ok(Vec::new());
+    };
+    let range_start = first.start.format("%Y-%m-%d").to_string();
+    let range_end = last.end.format("%Y-%m-%d").to_string();
 
-        let mut stmt = conn.prepare(&sql_query)
-            .map_err(|e| {
-                eprintln!("[Rust Chart] Error preparing SQL statement for month {}: {}", month_label_str, e);
-                e.to_string()
-            })?;
-
-        let mut rows = stmt
-            .query(params![month_start_str, month_end_str])
-            .map_err(|e| {
-                eprintln!(
-                    "[Rust Chart] Error executing SQL query for month {}: {}",
-                    month_label_str, e
-                );
-                e.to_string()
-            })?;
-
-        if let Some(row) = rows.next().map_err(|e| {
-            eprintln!(
-                "[Rust Chart] Error fetching row for month {}: {}",
-                month_label_str, e
-            );
-            e.to_string()
-        })? {
-            let income: f64 = row.get(0).unwrap_or(0.0);
-            let donations: f64 = row.get(1).unwrap_or(0.0);
-            let expenses: f64 = row.get(2).unwrap_or(0.0);
-            println!(
-                "[Rust Chart] Month {}: Income={}, Donations={}, Expenses={}",
-                month_label_str, income, donations, expenses
-            );
-            results.push(DesktopMonthlyDataPoint {
-                month_label: month_label_str.clone(),
-                income,
-                donations,
-                expenses,
-            });
+    let (currency_key, chomesh_key) = (
+        if breakdowns.by_currency { "COALESCE(original_currency, currency)" } else { "NULL" },
+        if breakdowns.by_chomesh { "COALESCE(is_chomesh, 0)" } else { "0" },
+    );
+    let amount_micro = to_micro_sql("amount", "currency");
+    let sql_query = format!(
+        "SELECT {bucket} AS bucket, type, {currency_key} AS paid_currency, {chomesh_key} AS chomesh,
+                COALESCE(SUM({amount_micro}), 0)
+         FROM transactions
+         WHERE date >= ?1 AND date <= ?2
+         GROUP BY bucket, type, paid_currency, chomesh"
+    );
+    println!(
+        "[Rust Chart] Querying {} {} months, range: {} to {}",
+        periods.len(),
+        calendar,
+        range_start,
+        range_end
+    );
+
+    let mut months: Vec<MonthAccumulator> = periods.iter().map(|_| MonthAccumulator::default()).collect();
+    let mut stmt = conn.prepare(&sql_query).inspect_err(|e| {
+        eprintln!("[Rust Chart] Error preparing monthly summary query: {}", e);
+    })?;
+    let mut rows = stmt.query(params![range_start, range_end])?;
+    while let Some(row) = rows.next()? {
+        let bucket: String = row.get(0)?;
+        let transaction_type: String = row.get(1)?;
+        let paid_currency: Option<String> = row.get(2)?;
+        let chomesh: bool = row.get::<_, i64>(3)? != 0;
+        let micro: i64 = row.get(4)?;
+        let Some(index) = period_index(&periods, &bucket) else {
+            continue;
+        };
+        let month = &mut months[index];
+        month.totals.add(&transaction_type, micro);
+        if breakdowns.by_type {
+            *month.by_type.entry(transaction_type.clone()).or_default() += micro;
+        }
+        if let Some(currency) = paid_currency {
+            month.by_currency.entry(currency).or_default().add(&transaction_type, micro);
+        }
+        if chomesh {
+            month.chomesh.add(&transaction_type, micro);
         } else {
-            eprintln!(
-                "[Rust Chart] No row returned from query for month {}, adding zeroed entry.",
-                month_label_str
-            );
-            results.push(DesktopMonthlyDataPoint {
-                month_label: month_label_str.clone(),
-                income: 0.0,
-                donations: 0.0,
-                expenses: 0.0,
-            });
+            month.regular.add(&transaction_type, micro);
         }
+    }
 
-        current_month_iter = next_month_start;
+    let major = |micro: i64| Money::from_micro(micro).to_major();
+    let mut running = MicroTotals::default();
+    let mut results: Vec<DesktopMonthlyDataPoint> = Vec::with_capacity(periods.len());
+    for (period, month) in periods.into_iter().zip(months) {
+        running.accumulate(month.totals);
+        let totals = month.totals.to_major();
+        results.push(DesktopMonthlyDataPoint {
+            month_label: period.label,
+            month_name: period.name,
+            income: totals.income,
+            donations: totals.donations,
+            expenses: totals.expenses,
+            cumulative: running.to_major(),
+            by_type: breakdowns.by_type.then(|| {
+                month.by_type.into_iter().map(|(t, micro)| (t, major(micro))).collect()
+            }),
+            by_currency: breakdowns.by_currency.then(|| {
+                month.by_currency.into_iter().map(|(c, totals)| (c, totals.to_major())).collect()
+            }),
+            by_chomesh: breakdowns.by_chomesh.then(|| ChomeshTotals {
+                chomesh: month.chomesh.to_major(),
+                regular: month.regular.to_major(),
+            }),
+        });
     }
 
     println!(
@@ -151,3 +322,100 @@ pub fn get_desktop_monthly_financial_summary(
     );
     Ok(results)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn hebrew_periods_include_both_adars_in_a_leap_year() {
+        // 2024-04-23 is 15 Nisan 5784
+        let periods = hebrew_month_periods(date("2024-04-23"), 4).unwrap();
+        let labels: Vec<_> = periods.iter().map(|p| (p.label.as_str(), p.name.as_deref().unwrap())).collect();
+        assert_eq!(
+            labels,
+            vec![("5784-11", "Shevat"), ("5784-12", "Adar I"), ("5784-13", "Adar II"), ("5784-01", "Nisan")]
+        );
+        assert_eq!((periods[2].start, periods[2].end), (date("2024-03-11"), date("2024-04-08")));
+    }
+
+    #[test]
+    fn gregorian_periods_end_with_the_month_of_end_date() {
+        let periods = gregorian_month_periods(date("2024-03-15"), 3).unwrap();
+        let labels: Vec<_> = periods.iter().map(|p| p.label.as_str()).collect();
+        assert_eq!(labels, vec!["2024-01", "2024-02", "2024-03"]);
+        assert_eq!(periods[1].end, date("2024-02-29"));
+    }
+
+    fn summary_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO transactions (id, date, amount, currency, type, is_chomesh, original_amount, original_currency) VALUES
+                ('t1', '2024-01-05', 100000, 'ILS', 'income', 1, NULL, NULL),
+                ('t2', '2024-01-20', 20010, 'ILS', 'exempt-income', NULL, NULL, NULL),
+                ('t3', '2024-03-10', 37000, 'ILS', 'donation', 0, 10000, 'USD'),
+                ('t4', '2024-03-12', 5000, 'ILS', 'expense', 0, NULL, NULL),
+                ('t5', '2024-04-01', 999, 'ILS', 'expense', 0, NULL, NULL),
+                ('t6', '2024-02-15', 1500, 'JPY', 'expense', 0, NULL, NULL);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn totals(income: f64, donations: f64, expenses: f64) -> MonthlyTotals {
+        MonthlyTotals { income, donations, expenses }
+    }
+
+    #[test]
+    fn summarizes_months_in_one_pass_with_breakdowns() {
+        let conn = summary_db();
+        let all = MonthlySummaryBreakdowns { by_type: true, by_currency: true, by_chomesh: true };
+        let months = query_monthly_summary(&conn, date("2024-03-31"), 3, "gregorian", all).unwrap();
+
+        let labels: Vec<_> = months.iter().map(|m| m.month_label.as_str()).collect();
+        assert_eq!(labels, vec!["2024-01", "2024-02", "2024-03"]);
+        assert_eq!((months[0].income, months[1].income, months[2].donations), (1200.1, 0.0, 370.0));
+        assert_eq!(months[1].cumulative, totals(1200.1, 0.0, 1500.0));
+        assert_eq!(months[2].cumulative, totals(1200.1, 370.0, 1550.0));
+
+        let by_type = months[0].by_type.as_ref().unwrap();
+        assert_eq!((by_type["income"], by_type["exempt-income"]), (1000.0, 200.1));
+        assert_eq!(months[1].by_type.as_ref().unwrap()["expense"], 1500.0);
+        assert_eq!(months[1].by_currency.as_ref().unwrap()["JPY"], totals(0.0, 0.0, 1500.0));
+        let by_currency = months[2].by_currency.as_ref().unwrap();
+        assert_eq!((by_currency["USD"], by_currency["ILS"]), (totals(0.0, 370.0, 0.0), totals(0.0, 0.0, 50.0)));
+        let by_chomesh = months[0].by_chomesh.unwrap();
+        assert_eq!((by_chomesh.chomesh, by_chomesh.regular), (totals(1000.0, 0.0, 0.0), totals(200.1, 0.0, 0.0)));
+
+        let plain = query_monthly_summary(&conn, date("2024-03-31"), 3, "gregorian", Default::default()).unwrap();
+        assert!(plain.iter().all(|m| m.by_type.is_none() && m.by_currency.is_none() && m.by_chomesh.is_none()));
+        assert_eq!(plain[2].cumulative, months[2].cumulative);
+    }
+
+    #[test]
+    fn hebrew_summary_splits_gregorian_months() {
+        // Adar II 5784 starts on 2024-03-11
+        let conn = summary_db();
+        let months = query_monthly_summary(&conn, date("2024-03-31"), 2, "hebrew", Default::default()).unwrap();
+        let per_month: Vec<_> = months.iter().map(|m| (m.month_label.as_str(), m.donations, m.expenses)).collect();
+        assert_eq!(per_month, vec![("5784-12", 370.0, 1500.0), ("5784-13", 0.0, 59.99)]);
+    }
+
+    #[test]
+    fn month_counts_and_hebrew_years_are_bounded() {
+        let conn = summary_db();
+        for months in [0, -1, MAX_SUMMARY_MONTHS + 1, i32::MAX] {
+            let result = query_monthly_summary(&conn, date("2024-03-31"), months, "gregorian", Default::default());
+            assert!(matches!(result, Err(Ten10Error::Validation(_))), "{}", months);
+        }
+        // Before 1 Tishrei 3762 there is no supported Hebrew month.
+        let result = query_monthly_summary(&conn, date("0001-01-01"), 1, "hebrew", Default::default());
+        assert!(matches!(result, Err(Ten10Error::Validation(_))));
+        assert_eq!(query_monthly_summary(&conn, date("2024-03-31"), MAX_SUMMARY_MONTHS, "hebrew", Default::default()).unwrap().len(), 120);
+    }
+}
@@ -1,278 +1,43 @@
+use crate::migrations::run_migrations;
 use crate::DbState;
-use rusqlite::{Connection, Result};
+use crate::error::{Ten10Error, Ten10Result};
+use rusqlite::{Connection, OptionalExtension};
 use tauri::State;
 
 #[tauri::command]
-pub async fn init_db(db: State<'_, DbState>) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+pub async fn init_db(db: State<'_, DbState>) -> Result<(), Ten10Error> {
+    let mut conn = db.0.lock()?;
 
-    // Create transactions table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS transactions (
-            id TEXT PRIMARY KEY,
-            user_id TEXT,
-            date TEXT NOT NULL,
-            amount REAL NOT NULL,
-            currency TEXT NOT NULL,
-            description TEXT,
-            type TEXT NOT NULL,
-            category TEXT,
-            is_chomesh INTEGER,
-            recipient TEXT,
-            payment_method TEXT,
-            created_at TEXT,
-            updated_at TEXT,
-            original_amount REAL,
-            original_currency TEXT,
-            conversion_rate REAL,
-            conversion_date TEXT,
-            rate_source TEXT
-        )",
-        [],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Create recurring_transactions table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS recurring_transactions (
-            id TEXT PRIMARY KEY,
-            user_id TEXT,
-            status TEXT NOT NULL DEFAULT 'active',
-            start_date TEXT NOT NULL,
-            next_due_date TEXT NOT NULL,
-            frequency TEXT NOT NULL DEFAULT 'monthly',
-            day_of_month INTEGER NOT NULL,
-            total_occurrences INTEGER,
-            execution_count INTEGER NOT NULL DEFAULT 0,
-            description TEXT,
-            amount REAL NOT NULL,
-            currency TEXT NOT NULL,
-            type TEXT NOT NULL,
-            category TEXT,
-            is_chomesh INTEGER,
-            recipient TEXT,
-            payment_method TEXT,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL,
-            original_amount REAL,
-            original_currency TEXT,
-            conversion_rate REAL,
-            conversion_date TEXT,
-            rate_source TEXT
-        )",
-        [],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // --- Add new columns to recurring_transactions if they don't exist ---
-    
-    if !column_exists(&conn, "recurring_transactions", "original_amount").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE recurring_transactions ADD COLUMN original_amount REAL",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
+    // Schema changes live in migrations.rs; each one is applied exactly once.
+    let applied = run_migrations(&mut conn)?;
+    if !applied.is_empty() {
+        println!("[DB Migration] Database upgraded, applied versions: {:?}", applied);
     }
 
-    if !column_exists(&conn, "recurring_transactions", "original_currency").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE recurring_transactions ADD COLUMN original_currency TEXT",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    if !column_exists(&conn, "recurring_transactions", "conversion_rate").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE recurring_transactions ADD COLUMN conversion_rate REAL",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    if !column_exists(&conn, "recurring_transactions", "conversion_date").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE recurring_transactions ADD COLUMN conversion_date TEXT",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    if !column_exists(&conn, "recurring_transactions", "rate_source").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE recurring_transactions ADD COLUMN rate_source TEXT",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    if !column_exists(&conn, "recurring_transactions", "payment_method").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE recurring_transactions ADD COLUMN payment_method TEXT",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    // Add source_recurring_id to transactions table if it doesn't exist
-    // Use a helper function to check for column existence to avoid errors on re-runs
-    if !column_exists(&conn, "transactions", "source_recurring_id").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE transactions ADD COLUMN source_recurring_id TEXT",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    // Add occurrence_number to transactions table if it doesn't exist
-    if !column_exists(&conn, "transactions", "occurrence_number").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE transactions ADD COLUMN occurrence_number INTEGER",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    // --- Currency Conversion Columns ---
-    
-    if !column_exists(&conn, "transactions", "original_amount").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE transactions ADD COLUMN original_amount REAL",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    if !column_exists(&conn, "transactions", "original_currency").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE transactions ADD COLUMN original_currency TEXT",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    if !column_exists(&conn, "transactions", "conversion_rate").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE transactions ADD COLUMN conversion_rate REAL",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    if !column_exists(&conn, "transactions", "conversion_date").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE transactions ADD COLUMN conversion_date TEXT",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    if !column_exists(&conn, "transactions", "rate_source").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE transactions ADD COLUMN rate_source TEXT",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    if !column_exists(&conn, "transactions", "payment_method").map_err(|e| e.to_string())? {
-        conn.execute(
-            "ALTER TABLE transactions ADD COLUMN payment_method TEXT",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    // --- Normalize predefined category labels to stable keys ---
-    // Maps known Hebrew and English localized labels to canonical keys
-    // (e.g. "מזון" or "Food" → "food"). Unknown values are left unchanged
-    // so custom categories survive untouched.
-    // Donation transactions do not support categories (they use recipient).
-    // Only touch legacy localized labels; stable keys and custom text skip the UPDATE (cheaper on large DBs).
-    let normalize_category_sql =
-        "UPDATE {} SET category = CASE \
-          WHEN category IN ('Salary', 'משכורת')          THEN 'salary' \
-          WHEN category IN ('Business', 'עסק')             THEN 'business' \
-          WHEN category IN ('Freelance', 'עבודה עצמאית')  THEN 'freelance' \
-          WHEN category IN ('Investment', 'השקעות')        THEN 'investment' \
-          WHEN category IN ('Allowance', 'קצבאות')         THEN 'allowance' \
-          WHEN category IN ('Gift', 'מתנה')               THEN 'gift' \
-          WHEN category IN ('Food', 'מזון')               THEN 'food' \
-          WHEN category IN ('Transportation', 'תחבורה')   THEN 'transportation' \
-          WHEN category IN ('Housing', 'דיור')            THEN 'housing' \
-          WHEN category IN ('Utilities', 'שירותים')       THEN 'utilities' \
-          WHEN category IN ('Healthcare', 'בריאות')       THEN 'healthcare' \
-          WHEN category IN ('Education', 'חינוך')         THEN 'education' \
-          WHEN category IN ('Leisure', 'פנאי')            THEN 'leisure' \
-          WHEN category IN ('Shopping', 'קניות')          THEN 'shopping' \
-          WHEN category IN ('Other', 'אחר')               THEN 'other' \
-          ELSE category \
-        END \
-        WHERE category IN ( \
-          'Salary', 'משכורת', 'Business', 'עסק', 'Freelance', 'עבודה עצמאית', \
-          'Investment', 'השקעות', 'Allowance', 'קצבאות', 'Gift', 'מתנה', \
-          'Food', 'מזון', 'Transportation', 'תחבורה', 'Housing', 'דיור', \
-          'Utilities', 'שירותים', 'Healthcare', 'בריאות', 'Education', 'חינוך', \
-          'Leisure', 'פנאי', 'Shopping', 'קניות', 'Other', 'אחר' \
-        )";
-    conn.execute(&normalize_category_sql.replace("{}", "transactions"), [])
-        .map_err(|e| e.to_string())?;
-    conn.execute(&normalize_category_sql.replace("{}", "recurring_transactions"), [])
-        .map_err(|e| e.to_string())?;
-
-    // --- Cleanup: Drop old recurring columns if they exist ---
-    let columns_to_drop = vec!["is_recurring", "recurring_day_of_month", "recurring_total_count"];
-    for col in columns_to_drop {
-        if column_exists(&conn, "transactions", col).map_err(|e| e.to_string())? {
-            // Note: DROP COLUMN might not be supported in older SQLite versions,
-            // but it's available in recent tauri builds.
-            conn.execute(&format!("ALTER TABLE transactions DROP COLUMN {}", col), [])
-                .map_err(|e| e.to_string())?;
-            println!("[DB Migration] Dropped deprecated column: {}", col);
-        }
-    }
-
-    // --- App settings table (for default_currency etc.) - survives WebView cache wipe on update ---
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS app_settings (
-            key TEXT PRIMARY KEY,
-            value TEXT NOT NULL
-        )",
-        [],
-    )
-    .map_err(|e| e.to_string())?;
-
     Ok(())
 }
 
-// Helper function to check if a column exists in a table
-fn column_exists(conn: &Connection, table_name: &str, column_name: &str) -> Result<bool> {
-    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table_name))?;
-    let mut rows = stmt.query([])?;
-    while let Some(row) = rows.next()? {
-        let name: String = row.get(1)?;
-        if name.eq_ignore_ascii_case(column_name) {
-            return Ok(true);
-        }
-    }
-    Ok(false)
-}
-
 #[tauri::command]
-pub async fn clear_all_data(db: State<'_, DbState>) -> Result<(), String> {
-    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+pub async fn clear_all_data(db: State<'_, DbState>) -> Result<(), Ten10Error> {
+    let mut conn = db.0.lock()?;
     
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let tx = conn.transaction()?;
 
-    tx.execute("DELETE FROM recurring_transactions", [])
-        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM pledge_payments", [])?;
+    tx.execute("DELETE FROM pledges", [])?;
+    println!("Cleared all pledges from the database.");
+
+    tx.execute("DELETE FROM recurring_transactions", [])?;
     println!("Cleared all recurring_transactions from the database.");
 
-    tx.execute("DELETE FROM transactions", [])
-        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM transactions", [])?;
     println!("Cleared all transactions from the database.");
 
-    tx.commit().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM recipient_aliases", [])?;
+    tx.execute("DELETE FROM recipients", [])?;
+    println!("Cleared all recipients from the database.");
+
+    tx.commit()?;
     
     Ok(())
 }
@@ -294,32 +59,52 @@ pub fn get_app_version() -> String {
  * Returns None if not set.
  */
 #[tauri::command]
-pub fn get_default_currency(db: State<'_, DbState>) -> Result<Option<String>, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn
-        .prepare("SELECT value FROM app_settings WHERE key = 'default_currency'")
-        .map_err(|e| e.to_string())?;
-    let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
-    if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-        let value: String = row.get(0).map_err(|e| e.to_string())?;
+pub fn get_default_currency(db: State<'_, DbState>) -> Result<Option<String>, Ten10Error> {
+    let conn = db.read()?;
+    let mut stmt = conn.prepare("SELECT value FROM app_settings WHERE key = 'default_currency'")?;
+    let mut rows = stmt.query([])?;
+    if let Some(row) = rows.next()? {
+        let value: String = row.get(0)?;
         Ok(Some(value))
     } else {
         Ok(None)
     }
 }
 
+/// The currency every `transactions.amount` is recorded in, as the frontend saved it:
+/// `defaultCurrency` in the `client_preferences` JSON, else the legacy `default_currency`
+/// key (deleted once the frontend has migrated to `client_preferences`).
+pub(crate) fn stored_default_currency(conn: &Connection) -> rusqlite::Result<Option<String>> {
+    let setting = |key: &str| {
+        conn.query_row("SELECT value FROM app_settings WHERE key = ?1", [key], |row| row.get::<_, String>(0))
+            .optional()
+    };
+    let from_preferences = setting("client_preferences")?
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+        .and_then(|preferences| preferences.get("defaultCurrency")?.as_str().map(str::to_string));
+    match from_preferences {
+        Some(currency) => Ok(Some(currency)),
+        None => setting("default_currency"),
+    }
+}
+
+/// `stored_default_currency`, or ILS until one is set.
+pub(crate) fn default_currency_or_ils(conn: &Connection) -> Ten10Result<String> {
+    Ok(stored_default_currency(conn)?.unwrap_or_else(|| "ILS".to_string()))
+}
+
 /**
  * Set the default currency in app_settings (SQLite).
  * Used on desktop to persist currency so it survives WebView cache wipe.
+ * Records only the setting; `change_default_currency` also re-converts existing data.
  */
 #[tauri::command]
-pub fn set_default_currency(db: State<'_, DbState>, currency: String) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+pub fn set_default_currency(db: State<'_, DbState>, currency: String) -> Result<(), Ten10Error> {
+    let conn = db.0.lock()?;
     conn.execute(
         "INSERT INTO app_settings (key, value) VALUES ('default_currency', ?1) ON CONFLICT(key) DO UPDATE SET value = ?1",
         [&currency],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(())
 }
 
@@ -327,14 +112,12 @@ pub fn set_default_currency(db: State<'_, DbState>, currency: String) -> Result<
  * Generic get/set for app_settings. Used for language, theme, autoLockTimeoutMinutes.
  */
 #[tauri::command]
-pub fn get_app_setting(db: State<'_, DbState>, key: String) -> Result<Option<String>, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn
-        .prepare("SELECT value FROM app_settings WHERE key = ?1")
-        .map_err(|e| e.to_string())?;
-    let mut rows = stmt.query([&key]).map_err(|e| e.to_string())?;
-    if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-        let value: String = row.get(0).map_err(|e| e.to_string())?;
+pub fn get_app_setting(db: State<'_, DbState>, key: String) -> Result<Option<String>, Ten10Error> {
+    let conn = db.read()?;
+    let mut stmt = conn.prepare("SELECT value FROM app_settings WHERE key = ?1")?;
+    let mut rows = stmt.query([&key])?;
+    if let Some(row) = rows.next()? {
+        let value: String = row.get(0)?;
         Ok(Some(value))
     } else {
         Ok(None)
@@ -342,24 +125,22 @@ pub fn get_app_setting(db: State<'_, DbState>, key: String) -> Result<Option<Str
 }
 
 #[tauri::command]
-pub fn set_app_setting(db: State<'_, DbState>, key: String, value: String) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+pub fn set_app_setting(db: State<'_, DbState>, key: String, value: String) -> Result<(), Ten10Error> {
+    let conn = db.0.lock()?;
     conn.execute(
         "INSERT INTO app_settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = ?2",
         rusqlite::params![&key, &value],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn delete_app_setting(db: State<'_, DbState>, key: String) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+pub fn delete_app_setting(db: State<'_, DbState>, key: String) -> Result<(), Ten10Error> {
+    let conn = db.0.lock()?;
     conn.execute(
         "DELETE FROM app_settings WHERE key = ?1",
         rusqlite::params![&key],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(())
 }
 
@@ -369,8 +150,8 @@ pub fn delete_app_setting(db: State<'_, DbState>, key: String) -> Result<(), Str
  * Returns None if no transactions.
  */
 #[tauri::command]
-pub fn infer_default_currency_from_transactions(db: State<'_, DbState>) -> Result<Option<String>, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+pub fn infer_default_currency_from_transactions(db: State<'_, DbState>) -> Result<Option<String>, Ten10Error> {
+    let conn = db.read()?;
     // COALESCE(original_currency, currency) - for legacy tx without original_currency, use currency
     let mut stmt = conn
         .prepare(
@@ -380,13 +161,38 @@ pub fn infer_default_currency_from_transactions(db: State<'_, DbState>) -> Resul
              GROUP BY c
              ORDER BY cnt DESC
              LIMIT 1",
-        )
-        .map_err(|e| e.to_string())?;
-    let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
-    if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-        let value: String = row.get(0).map_err(|e| e.to_string())?;
+        )?;
+    let mut rows = stmt.query([])?;
+    if let Some(row) = rows.next()? {
+        let value: String = row.get(0)?;
         Ok(Some(value))
     } else {
         Ok(None)
     }
 } 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_currency_comes_from_client_preferences() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(default_currency_or_ils(&conn).unwrap(), "ILS");
+
+        conn.execute("INSERT INTO app_settings (key, value) VALUES ('default_currency', 'EUR')", []).unwrap();
+        assert_eq!(default_currency_or_ils(&conn).unwrap(), "EUR");
+
+        conn.execute(
+            "INSERT INTO app_settings (key, value) VALUES ('client_preferences', '{\"language\":\"en\",\"defaultCurrency\":\"USD\"}')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(default_currency_or_ils(&conn).unwrap(), "USD");
+
+        // Preferences saved before the currency was chosen fall back to the legacy key.
+        conn.execute("UPDATE app_settings SET value = '{\"language\":\"en\"}' WHERE key = 'client_preferences'", []).unwrap();
+        assert_eq!(default_currency_or_ils(&conn).unwrap(), "EUR");
+    }
+}
@@ -0,0 +1,428 @@
+// src-tauri/src/commands/default_currency_commands.rs
+// Changing the default currency of a database that already has data. Every
+// `transactions.amount` (and recurring template, and budget) is recorded in the
+// default currency, so switching it means re-converting them all into the new
+// one; `set_default_currency` alone would leave totals mixing currencies.
+//
+// A row is re-converted from what was actually paid (`original_amount` in
+// `original_currency`, or the amount itself for an unconverted row) at the
+// stored rate for its `conversion_date` (its date when it has none; today for
+// recurring templates and budgets), see exchange_rate_commands.rs. What was paid
+// stays as it is: a row paid in the new currency simply drops its conversion.
+// The amounts and conversion every row had before are kept in
+// `conversion_history`. Pledges are kept in their own currency and need no change.
+//
+// The change is previewed first, then applied in a single transaction, and only
+// when a rate was found for every row.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, Connection, Result as RusqliteResult};
+use serde::Serialize;
+use tauri::State;
+
+use super::db_commands::default_currency_or_ils;
+use super::exchange_rate_commands::{resolve_rate, RateLookupOptions, ResolvedRate};
+use crate::error::{Ten10Error, Ten10Result};
+use crate::money::Money;
+use crate::DbState;
+
+/// Tables whose rows carry a conversion into the default currency.
+const CONVERTED_TABLES: [&str; 2] = ["transactions", "recurring_transactions"];
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MissingRate {
+    pub base: String,
+    pub quote: String,
+    pub date: String,
+    /// Rows needing this rate
+    pub rows: usize,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PaidCurrencyCount {
+    pub currency: String,
+    pub rows: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DefaultCurrencyChange {
+    pub from: String,
+    pub to: String,
+    pub transactions: usize,
+    pub recurring_transactions: usize,
+    pub budgets: usize,
+    /// Re-converted rows per currency they were paid in
+    pub paid_currencies: Vec<PaidCurrencyCount>,
+    /// Rates not found; the change cannot be applied until they are stored
+    pub missing_rates: Vec<MissingRate>,
+}
+
+/// A row's amounts as stored.
+struct StoredAmounts {
+    id: String,
+    amount: i64,
+    currency: String,
+    original_amount: Option<i64>,
+    original_currency: Option<String>,
+    /// The date its rate is looked up for
+    rate_date: NaiveDate,
+}
+
+/// The new amounts of a row, in minor units.
+struct Conversion {
+    table: &'static str,
+    id: String,
+    amount: i64,
+    /// None for a row paid in the new currency
+    rate: Option<ResolvedRate>,
+    original_amount: i64,
+    original_currency: String,
+}
+
+struct Plan {
+    change: DefaultCurrencyChange,
+    rows: Vec<Conversion>,
+    budgets: Vec<(String, i64)>,
+}
+
+fn date_prefix(value: &str) -> Option<NaiveDate> {
+    value.get(..10).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+}
+
+fn stored_amounts(conn: &Connection, table: &str, to: &str, today: NaiveDate) -> RusqliteResult<Vec<StoredAmounts>> {
+    let date_column = if table == "transactions" { "date" } else { "NULL" };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, amount, currency, original_amount, original_currency, conversion_date, {date_column}
+         FROM {table} WHERE currency <> ?1"
+    ))?;
+    let rows = stmt.query_map(params![to], |row| {
+        let conversion_date: Option<String> = row.get(5)?;
+        let date: Option<String> = row.get(6)?;
+        let rate_date = conversion_date.or(date).as_deref().and_then(date_prefix).unwrap_or(today);
+        Ok(StoredAmounts {
+            id: row.get(0)?,
+            amount: row.get(1)?,
+            currency: row.get(2)?,
+            original_amount: row.get(3)?,
+            original_currency: row.get(4)?,
+            rate_date,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Works out every conversion into `to` without changing anything.
+fn plan(conn: &Connection, to: &str, options: &RateLookupOptions, today: NaiveDate) -> Ten10Result<Plan> {
+    let from = default_currency_or_ils(conn)?;
+    let mut change = DefaultCurrencyChange { from, to: to.to_string(), ..Default::default() };
+    let mut rates: HashMap<(String, NaiveDate), Option<ResolvedRate>> = HashMap::new();
+    let mut missing: BTreeMap<(String, NaiveDate), usize> = BTreeMap::new();
+    let mut paid_currencies: BTreeMap<String, usize> = BTreeMap::new();
+    let mut rate_for = |currency: &str, date: NaiveDate| -> RusqliteResult<Option<ResolvedRate>> {
+        let key = (currency.to_string(), date);
+        if !rates.contains_key(&key) {
+            rates.insert(key.clone(), resolve_rate(conn, currency, to, date, options)?);
+        }
+        Ok(rates[&key].clone())
+    };
+
+    let mut rows = Vec::new();
+    for table in CONVERTED_TABLES {
+        for stored in stored_amounts(conn, table, to, today)? {
+            let (paid, paid_currency) = match (stored.original_amount, stored.original_currency) {
+                (Some(amount), Some(currency)) => (amount, currency),
+                _ => (stored.amount, stored.currency),
+            };
+            *paid_currencies.entry(paid_currency.clone()).or_default() += 1;
+            match table {
+                "transactions" => change.transactions += 1,
+                _ => change.recurring_transactions += 1,
+            }
+            let rate = if paid_currency == to {
+                None
+            } else {
+                match rate_for(&paid_currency, stored.rate_date)? {
+                    Some(rate) => Some(rate),
+                    None => {
+                        *missing.entry((paid_currency, stored.rate_date)).or_default() += 1;
+                        continue;
+                    }
+                }
+            };
+            let amount = match &rate {
+                Some(rate) => Money::from_minor(paid, &paid_currency).convert(rate.rate, to)?.to_minor(to),
+                None => paid,
+            };
+            rows.push(Conversion {
+                table,
+                id: stored.id,
+                amount,
+                rate,
+                original_amount: paid,
+                original_currency: paid_currency,
+            });
+        }
+    }
+
+    let mut budgets = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, amount, currency FROM budgets WHERE currency <> ?1")?;
+    let stored_budgets = stmt
+        .query_map(params![to], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?)))?
+        .collect::<RusqliteResult<Vec<_>>>()?;
+    for (id, amount, currency) in stored_budgets {
+        change.budgets += 1;
+        match rate_for(&currency, today)? {
+            Some(rate) => budgets.push((id, Money::from_minor(amount, &currency).convert(rate.rate, to)?.to_minor(to))),
+            None => *missing.entry((currency, today)).or_default() += 1,
+        }
+    }
+
+    change.paid_currencies =
+        paid_currencies.into_iter().map(|(currency, rows)| PaidCurrencyCount { currency, rows }).collect();
+    change.missing_rates = missing
+        .into_iter()
+        .map(|((base, date), rows)| MissingRate {
+            base,
+            quote: to.to_string(),
+            date: date.format("%Y-%m-%d").to_string(),
+            rows,
+        })
+        .collect();
+    Ok(Plan { change, rows, budgets })
+}
+
+/// Copies the amounts and conversion of row `id` of `table` into `conversion_history`.
+fn archive(conn: &Connection, table: &str, id: &str, now: &str) -> RusqliteResult<usize> {
+    let conversion = if table == "budgets" {
+        "NULL, NULL, NULL, NULL, NULL"
+    } else {
+        "original_amount, original_currency, conversion_rate, conversion_date, rate_source"
+    };
+    conn.execute(
+        &format!(
+            "INSERT INTO conversion_history
+                (source_table, row_id, amount, currency, original_amount, original_currency,
+                 conversion_rate, conversion_date, rate_source, replaced_at)
+             SELECT ?1, id, amount, currency, {conversion}, ?3 FROM {table} WHERE id = ?2"
+        ),
+        params![table, id, now],
+    )
+}
+
+fn apply(conn: &Connection, plan: &Plan) -> Ten10Result<()> {
+    if let Some(first) = plan.change.missing_rates.first() {
+        return Err(Ten10Error::validation(format!(
+            "No exchange rate {} -> {} for {} ({} missing rates); store them before changing the default currency.",
+            first.base,
+            first.quote,
+            first.date,
+            plan.change.missing_rates.len()
+        )));
+    }
+    let now = Utc::now().to_rfc3339();
+    let to = &plan.change.to;
+    for row in &plan.rows {
+        archive(conn, row.table, &row.id, &now)?;
+        match &row.rate {
+            Some(rate) => conn.execute(
+                &format!(
+                    "UPDATE {} SET amount = ?2, currency = ?3, original_amount = ?4, original_currency = ?5,
+                         conversion_rate = ?6, conversion_date = ?7, rate_source = ?8, updated_at = ?9
+                     WHERE id = ?1",
+                    row.table
+                ),
+                params![row.id, row.amount, to, row.original_amount, row.original_currency, rate.rate, rate.date, rate.source, now],
+            )?,
+            None => conn.execute(
+                &format!(
+                    "UPDATE {} SET amount = ?2, currency = ?3, original_amount = NULL, original_currency = NULL,
+                         conversion_rate = NULL, conversion_date = NULL, rate_source = NULL, updated_at = ?4
+                     WHERE id = ?1",
+                    row.table
+                ),
+                params![row.id, row.amount, to, now],
+            )?,
+        };
+    }
+    for (id, amount) in &plan.budgets {
+        archive(conn, "budgets", id, &now)?;
+        conn.execute(
+            "UPDATE budgets SET amount = ?2, currency = ?3, updated_at = ?4 WHERE id = ?1",
+            params![id, amount, to, now],
+        )?;
+    }
+    conn.execute(
+        "INSERT INTO app_settings (key, value) VALUES ('default_currency', ?1) ON CONFLICT(key) DO UPDATE SET value = ?1",
+        params![to],
+    )?;
+    // The frontend's saved settings, which the default currency is resolved from first.
+    conn.execute(
+        "UPDATE app_settings SET value = json_set(value, '$.defaultCurrency', ?1)
+         WHERE key = 'client_preferences' AND json_valid(value)",
+        params![to],
+    )?;
+    Ok(())
+}
+
+fn validate_currency(currency: &str) -> Ten10Result<String> {
+    let code = currency.trim().to_ascii_uppercase();
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(code)
+    } else {
+        Err(Ten10Error::validation(format!("Invalid currency code: {}", currency)))
+    }
+}
+
+/// The rows changing the default currency to `currency` would re-convert, and
+/// the rates it is missing.
+#[tauri::command]
+pub fn preview_default_currency_change(
+    db_state: State<'_, DbState>,
+    currency: String,
+    options: Option<RateLookupOptions>,
+) -> Result<DefaultCurrencyChange, Ten10Error> {
+    let currency = validate_currency(&currency)?;
+    let conn = db_state.read()?;
+    let today = chrono::Local::now().date_naive();
+    Ok(plan(&conn, &currency, &options.unwrap_or_default(), today)?.change)
+}
+
+/// Makes `currency` the default currency and re-converts every transaction,
+/// recurring template and budget into it, all or nothing.
+#[tauri::command]
+pub fn change_default_currency(
+    db_state: State<'_, DbState>,
+    currency: String,
+    options: Option<RateLookupOptions>,
+) -> Result<DefaultCurrencyChange, Ten10Error> {
+    let currency = validate_currency(&currency)?;
+    let mut conn = db_state.0.lock()?;
+    let today = chrono::Local::now().date_naive();
+    let tx = conn.transaction()?;
+    let plan = plan(&tx, &currency, &options.unwrap_or_default(), today)?;
+    apply(&tx, &plan)?;
+    tx.commit()?;
+    println!(
+        "[Rust DEBUG] change_default_currency: {} -> {}, {} transactions, {} recurring, {} budgets",
+        plan.change.from, plan.change.to, plan.change.transactions, plan.change.recurring_transactions, plan.change.budgets
+    );
+    Ok(plan.change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    /// ILS database with an ILS salary, a USD donation converted at 3.7, a JPY
+    /// expense, a USD recurring template and an ILS budget.
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO app_settings (key, value) VALUES ('default_currency', 'ILS');
+             INSERT INTO transactions (id, date, amount, currency, type, original_amount, original_currency,
+                                       conversion_rate, conversion_date, rate_source) VALUES
+                ('t1', '2024-01-10', 1000000, 'ILS', 'income', NULL, NULL, NULL, NULL, NULL),
+                ('t2', '2024-02-15', 37000, 'ILS', 'donation', 10000, 'USD', 3.7, '2024-02-14', 'auto'),
+                ('t3', '2024-03-01', 2500, 'ILS', 'expense', 1000, 'JPY', 0.025, '2024-03-01', 'manual');
+             INSERT INTO recurring_transactions (id, start_date, next_due_date, day_of_month, amount, currency, type,
+                                                 original_amount, original_currency, conversion_rate, created_at, updated_at) VALUES
+                ('r1', '2024-01-01', '2024-05-01', 1, 18500, 'ILS', 'donation', 5000, 'USD', 3.7, '0', '0');
+             INSERT INTO budgets (id, category, period, amount, currency, created_at, updated_at) VALUES
+                ('b1', 'food', 'monthly', 200000, 'ILS', '0', '0');
+             INSERT INTO exchange_rates (date, base, quote, rate, source, updated_at) VALUES
+                ('2024-01-09', 'USD', 'ILS', 3.6, 'boi', '0');",
+        )
+        .unwrap();
+        conn
+    }
+
+    /// id, amount, currency, original amount and currency, rate source
+    type Amounts = (String, i64, String, Option<i64>, Option<String>, Option<String>);
+
+    fn amounts(conn: &Connection, table: &str) -> Vec<Amounts> {
+        conn.prepare(&format!(
+            "SELECT id, amount, currency, original_amount, original_currency, rate_source FROM {table} ORDER BY id"
+        ))
+        .unwrap()
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)))
+        .unwrap()
+        .collect::<RusqliteResult<_>>()
+        .unwrap()
+    }
+
+    #[test]
+    fn previews_and_refuses_while_rates_are_missing() {
+        let conn = test_db();
+        let plan = plan(&conn, "USD", &RateLookupOptions::default(), date("2024-06-01")).unwrap();
+        assert_eq!((plan.change.from.as_str(), plan.change.transactions, plan.change.recurring_transactions, plan.change.budgets), ("ILS", 3, 1, 1));
+        assert_eq!(
+            plan.change.paid_currencies,
+            vec![
+                PaidCurrencyCount { currency: "ILS".into(), rows: 1 },
+                PaidCurrencyCount { currency: "JPY".into(), rows: 1 },
+                PaidCurrencyCount { currency: "USD".into(), rows: 2 },
+            ]
+        );
+        assert_eq!(
+            plan.change.missing_rates,
+            vec![MissingRate { base: "JPY".into(), quote: "USD".into(), date: "2024-03-01".into(), rows: 1 }]
+        );
+
+        let before = amounts(&conn, "transactions");
+        assert!(apply(&conn, &plan).is_err());
+        assert_eq!(amounts(&conn, "transactions"), before);
+        assert_eq!(default_currency_or_ils(&conn).unwrap(), "ILS");
+    }
+
+    #[test]
+    fn reconverts_everything_at_historical_rates_keeping_what_was_paid() {
+        let mut conn = test_db();
+        conn.execute_batch(
+            "INSERT INTO exchange_rates (date, base, quote, rate, source, updated_at) VALUES ('2024-02-28', 'USD', 'JPY', 150, 'ecb', '0');
+             INSERT INTO app_settings (key, value) VALUES ('client_preferences', '{\"defaultCurrency\":\"ILS\",\"language\":\"he\"}');",
+        )
+        .unwrap();
+        let tx = conn.transaction().unwrap();
+        let plan = plan(&tx, "USD", &RateLookupOptions::default(), date("2024-06-01")).unwrap();
+        assert!(plan.change.missing_rates.is_empty());
+        apply(&tx, &plan).unwrap();
+        tx.commit().unwrap();
+
+        // t1: 10,000 ILS on 2024-01-10 at 1/3.6; t2 was paid in USD; t3 1,000 JPY at 1/150
+        assert_eq!(
+            amounts(&conn, "transactions"),
+            vec![
+                ("t1".into(), 277778, "USD".into(), Some(1000000), Some("ILS".into()), Some("boi".into())),
+                ("t2".into(), 10000, "USD".into(), None, None, None),
+                ("t3".into(), 667, "USD".into(), Some(1000), Some("JPY".into()), Some("ecb".into())),
+            ]
+        );
+        assert_eq!(amounts(&conn, "recurring_transactions"), vec![("r1".into(), 5000, "USD".into(), None, None, None)]);
+        // 2,000 ILS at the latest rate, 1/3.6
+        let budget: (i64, String) = conn.query_row("SELECT amount, currency FROM budgets", [], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+        assert_eq!(budget, (55556, "USD".into()));
+        assert_eq!(default_currency_or_ils(&conn).unwrap(), "USD");
+        let preferences: String = conn
+            .query_row("SELECT value FROM app_settings WHERE key = 'client_preferences'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(preferences, r#"{"defaultCurrency":"USD","language":"he"}"#);
+
+        // The conversion t2 drops is kept, with every other replaced row.
+        let history: (i64, i64, String, Option<f64>, Option<String>) = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM conversion_history), amount, currency, conversion_rate, conversion_date
+                 FROM conversion_history WHERE source_table = 'transactions' AND row_id = 't2'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
+            )
+            .unwrap();
+        assert_eq!(history, (5, 37000, "ILS".into(), Some(3.7), Some("2024-02-14".into())));
+    }
+}
@@ -1,10 +1,15 @@
+use chrono::NaiveDate;
 use rusqlite::{params, Connection};
 use serde::Serialize;
 use tauri::State;
 
+use crate::hebrew_calendar;
 use crate::DbState;
 use crate::models::TitheBalanceBreakdown;
+use crate::tithe_rules::{chomesh_delta_case, maaser_delta_case, rules_join};
 use crate::transaction_types::{donation_types_case_condition, donation_types_condition};
+use crate::error::Ten10Error;
+use crate::money::{major_sum_sql, micro_to_major_sql, to_major_sql, to_micro_sql};
 
 // New struct for returning detailed donation data
 #[derive(Serialize, Debug)]
@@ -18,18 +23,19 @@ pub async fn get_desktop_total_donations_in_range(
     db_state: State<'_, DbState>,
     start_date: String,
     end_date: String,
-) -> Result<DesktopDonationData, String> {
+) -> Result<DesktopDonationData, Ten10Error> {
     let query_sql = format!(
         "SELECT
-            COALESCE(SUM({}), 0) AS total_donations_amount,
-            COALESCE(SUM(CASE WHEN type = 'non_tithe_donation' THEN amount ELSE 0 END), 0) AS non_tithe_donation_amount
+            {} AS total_donations_amount,
+            {} AS non_tithe_donation_amount
         FROM
             transactions
         WHERE
             {} AND
             date >= ?1 AND
             date <= ?2;",
-        donation_types_case_condition(),
+        major_sum_sql(&donation_types_case_condition()),
+        major_sum_sql("CASE WHEN type = 'non_tithe_donation' THEN amount ELSE 0 END"),
         donation_types_condition()
     );
 
@@ -38,7 +44,7 @@ pub async fn get_desktop_total_donations_in_range(
         start_date, end_date
     );
 
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
+    let conn_guard = db_state.read()?;
     let conn: &Connection = &*conn_guard;
 
     match conn.query_row(query_sql.as_str(), params![start_date, end_date], |row| {
@@ -56,94 +62,411 @@ pub async fn get_desktop_total_donations_in_range(
         }
         Err(e) => {
             eprintln!("Desktop Query Error (donation_commands.rs): {}", e);
-            Err(format!(
-                "Failed to fetch detailed donations with rusqlite: {}",
-                e
-            ))
+            Err(e.into())
         }
     }
 }
 
+/// Tithe balance from all transactions dated on or before `as_of` (every
+/// transaction when `as_of` is None). `as_of` must be a YYYY-MM-DD date.
+pub(crate) fn compute_tithe_balance(
+    conn: &Connection,
+    as_of: Option<&str>,
+) -> Result<TitheBalanceBreakdown, Ten10Error> {
+    // Dates are compared as text, so only the zero-padded form is accepted.
+    if let Some(value) = as_of {
+        let canonical = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .is_ok_and(|d| d.format("%Y-%m-%d").to_string() == value);
+        if !canonical {
+            return Err(Ten10Error::validation(format!("Invalid date: {}", value)));
+        }
+    }
+    // BALANCE SPLIT LOGIC lives in tithe_rules.rs: each transaction is evaluated
+    // with the tithe rules version in force on its date.
+    // To change this split logic, also update: SQL migration file.
+    // Pots are rounded to whole micro-units before the total, so total = maaser + chomesh exactly.
+    let query_sql = format!(
+        "SELECT {}, {}, {}
+        FROM (
+            SELECT
+                ROUND(COALESCE(SUM({}), 0)) AS maaser,
+                ROUND(COALESCE(SUM({}), 0)) AS chomesh
+            FROM transactions
+            {}
+            WHERE ?1 IS NULL OR date <= ?1
+        )",
+        micro_to_major_sql("maaser + chomesh"),
+        micro_to_major_sql("maaser"),
+        micro_to_major_sql("chomesh"),
+        to_micro_sql(&maaser_delta_case(), "transactions.currency"),
+        to_micro_sql(&chomesh_delta_case(), "transactions.currency"),
+        rules_join("transactions")
+    );
+
+    conn.query_row(&query_sql, params![as_of], |row| {
+        Ok(TitheBalanceBreakdown {
+            total_balance: row.get(0)?,
+            maaser_balance: row.get(1)?,
+            chomesh_balance: row.get(2)?,
+        })
+    })
+    .map_err(Ten10Error::from)
+}
+
 #[tauri::command]
 pub async fn get_desktop_overall_tithe_balance(
     db_state: State<'_, DbState>,
-) -> Result<TitheBalanceBreakdown, String> {
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
-    let conn: &Connection = &*conn_guard;
+    as_of: Option<String>,
+) -> Result<TitheBalanceBreakdown, Ten10Error> {
+    let conn_guard = db_state.read()?;
+    let balance = compute_tithe_balance(&conn_guard, as_of.as_deref())?;
 
-    let mut stmt = match conn.prepare("SELECT type, amount, is_chomesh FROM transactions") {
-        Ok(s) => s,
-        Err(e) => return Err(format!("Failed to prepare statement: {}", e)),
-    };
+    println!(
+        "Desktop Query Result (donation_commands.rs): tithe_balance = {} (maaser: {}, chomesh: {}, as of: {:?})",
+        balance.total_balance, balance.maaser_balance, balance.chomesh_balance, as_of
+    );
+    Ok(balance)
+}
 
-    let rows = match stmt.query_map([], |row| {
-        Ok((
-            row.get::<usize, String>(0)?,      // type
-            row.get::<usize, f64>(1)?,         // amount
-            row.get::<usize, Option<i32>>(2)?, // is_chomesh (INTEGER in SQLite, can be NULL)
-        ))
-    }) {
-        Ok(r) => r,
-        Err(e) => return Err(format!("Failed to query rows: {}", e)),
+// ─── Year boundaries ──────────────────────────────────────────────────────────
+
+/// Opening and closing tithe balance of one Gregorian or Hebrew year.
+#[derive(Serialize, Debug, Clone)]
+pub struct TitheYearBalance {
+    /// Gregorian year (2024) or Hebrew year (5785)
+    pub year: i32,
+    pub start_date: String,
+    pub end_date: String,
+    /// Balance carried in: everything dated before `start_date`
+    pub opening: TitheBalanceBreakdown,
+    /// Balance at the end of `end_date`
+    pub closing: TitheBalanceBreakdown,
+}
+
+/// Most years one `query_tithe_year_balances` call covers.
+const MAX_YEAR_SPAN: i32 = 200;
+
+/// Years `query_tithe_year_balances` accepts per calendar; 5660 is Tishrei 1899.
+fn supported_years(calendar: &str) -> Result<std::ops::RangeInclusive<i32>, Ten10Error> {
+    match calendar {
+        "gregorian" => Ok(1900..=9999),
+        "hebrew" => Ok(5660..=hebrew_calendar::MAX_YEAR),
+        other => Err(Ten10Error::validation(format!("Invalid calendar: {}", other))),
+    }
+}
+
+/// First and last day of `year` in the given calendar ("gregorian" or "hebrew").
+fn year_bounds(calendar: &str, year: i32) -> Result<(NaiveDate, NaiveDate), Ten10Error> {
+    let bounds = match calendar {
+        "gregorian" => NaiveDate::from_ymd_opt(year, 1, 1).zip(NaiveDate::from_ymd_opt(year, 12, 31)),
+        "hebrew" => hebrew_calendar::year_bounds(year),
+        other => return Err(Ten10Error::validation(format!("Invalid calendar: {}", other))),
     };
+    bounds.ok_or_else(|| Ten10Error::validation(format!("Invalid {} year: {}", calendar, year)))
+}
+
+pub(crate) fn query_tithe_year_balances(
+    conn: &Connection,
+    calendar: &str,
+    from_year: i32,
+    to_year: i32,
+) -> Result<Vec<TitheYearBalance>, Ten10Error> {
+    let supported = supported_years(calendar)?;
+    if from_year > to_year || !supported.contains(&from_year) || !supported.contains(&to_year) {
+        return Err(Ten10Error::validation(format!("Invalid year range: {} - {}", from_year, to_year)));
+    }
+    if to_year - from_year >= MAX_YEAR_SPAN {
+        return Err(Ten10Error::validation(format!("At most {} years at a time", MAX_YEAR_SPAN)));
+    }
 
-    let mut maaser_balance = 0.0;
-    let mut chomesh_balance = 0.0;
-
-    for row_result in rows {
-        match row_result {
-            Ok((type_str, amount, is_chomesh_opt)) => {
-                let is_chomesh = is_chomesh_opt.map_or(false, |v| v == 1);
-                match type_str.as_str() {
-                    "income" => {
-                        // BALANCE SPLIT LOGIC: maaser gets 10% of ALL income (base obligation).
-                        // Chomesh pot gets only the EXTRA 10% from chomesh-marked income (delta).
-                        // So income of 1000 with chomesh: maaser +100, chomesh +100, total +200.
-                        // To change this split logic, also update: SQL migration file.
-                        maaser_balance += amount * 0.1;
-                        if is_chomesh {
-                            chomesh_balance += amount * 0.1;
-                        }
-                    }
-                    "donation" => {
-                        // Donation reduces the pot indicated by is_chomesh
-                        if is_chomesh {
-                            chomesh_balance -= amount;
-                        } else {
-                            maaser_balance -= amount;
-                        }
-                    }
-                    "recognized-expense" => {
-                        // All recognized-expenses reduce maaser by 10%
-                        maaser_balance -= amount * 0.1;
-                        // Chomesh recognized-expenses also reduce chomesh by an extra 10%
-                        if is_chomesh {
-                            chomesh_balance -= amount * 0.1;
-                        }
-                    }
-                    "initial_balance" => {
-                        // Initial balance goes to the pot indicated by is_chomesh
-                        if is_chomesh {
-                            chomesh_balance += amount;
-                        } else {
-                            maaser_balance += amount;
-                        }
-                    }
-                    _ => {} // Other types do not affect the balance
-                }
+    let mut years = Vec::new();
+    let mut opening: Option<TitheBalanceBreakdown> = None;
+    for year in from_year..=to_year {
+        let (start, end) = year_bounds(calendar, year)?;
+        // Years are contiguous, so each opening balance is the previous closing one.
+        let opening_balance = match opening.take() {
+            Some(balance) => balance,
+            None => {
+                let day_before = start
+                    .pred_opt()
+                    .ok_or_else(|| Ten10Error::validation("Year starts before the supported range"))?;
+                compute_tithe_balance(conn, Some(&day_before.format("%Y-%m-%d").to_string()))?
             }
-            Err(e) => return Err(format!("Error processing row: {}", e)),
-        }
+        };
+        let closing = compute_tithe_balance(conn, Some(&end.format("%Y-%m-%d").to_string()))?;
+        opening = Some(closing.clone());
+        years.push(TitheYearBalance {
+            year,
+            start_date: start.format("%Y-%m-%d").to_string(),
+            end_date: end.format("%Y-%m-%d").to_string(),
+            opening: opening_balance,
+            closing,
+        });
     }
+    Ok(years)
+}
 
-    let total_balance = maaser_balance + chomesh_balance;
-    println!(
-        "Desktop Query Result (donation_commands.rs): tithe_balance = {} (maaser: {}, chomesh: {})",
-        total_balance, maaser_balance, chomesh_balance
+/// Opening and closing tithe balance for each year in [from_year, to_year].
+/// `calendar` is "gregorian" (January - December) or "hebrew" (Tishrei - Elul,
+/// years given as e.g. 5785).
+#[tauri::command]
+pub fn get_desktop_tithe_year_balances(
+    db_state: State<'_, DbState>,
+    calendar: String,
+    from_year: i32,
+    to_year: i32,
+) -> Result<Vec<TitheYearBalance>, Ten10Error> {
+    let conn_guard = db_state.read()?;
+    query_tithe_year_balances(&conn_guard, &calendar, from_year, to_year)
+}
+
+// ─── Running tithe ledger ─────────────────────────────────────────────────────
+
+/// One point of the running tithe balance. For `granularity = "transaction"` every
+/// entry is a single transaction that moved the balance; otherwise it is a
+/// day/week/month bucket and the balances are as of the end of that bucket.
+#[derive(Serialize, Debug, Clone)]
+pub struct TitheLedgerEntry {
+    /// Transaction date, day, week start (Sunday, YYYY-MM-DD) or month (YYYY-MM)
+    pub period: String,
+    pub transaction_id: Option<String>,
+    pub transaction_type: Option<String>,
+    pub description: Option<String>,
+    pub amount: Option<f64>,
+    pub transaction_count: i64,
+    pub maaser_change: f64,
+    pub chomesh_change: f64,
+    pub maaser_balance: f64,
+    pub chomesh_balance: f64,
+    pub total_balance: f64,
+}
+
+pub(crate) fn query_tithe_ledger(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    granularity: &str,
+) -> Result<Vec<TitheLedgerEntry>, Ten10Error> {
+    let bucket = match granularity {
+        "transaction" | "day" => "date",
+        // Israeli weeks start on Sunday: the Sunday on or before the date
+        "week" => "date(date, '-6 days', 'weekday 0')",
+        "month" => "substr(date, 1, 7)",
+        other => return Err(Ten10Error::validation(format!("Invalid granularity: {}", other))),
+    };
+
+    // Running sums are computed over ALL history so the first entry in range
+    // already carries the opening balance.
+    let running_sql = format!(
+        "WITH deltas AS (
+            SELECT transactions.id, date, transactions.created_at, type, amount, currency, description,
+                   {maaser} AS maaser_delta,
+                   {chomesh} AS chomesh_delta
+            FROM transactions
+            {join}
+        ),
+        running AS (
+            SELECT *,
+                   {bucket} AS period,
+                   ROW_NUMBER() OVER w AS seq,
+                   SUM(maaser_delta) OVER w AS maaser_balance,
+                   SUM(chomesh_delta) OVER w AS chomesh_balance
+            FROM deltas
+            WINDOW w AS (ORDER BY date, created_at, id ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW)
+        )",
+        maaser = to_micro_sql(&maaser_delta_case(), "transactions.currency"),
+        chomesh = to_micro_sql(&chomesh_delta_case(), "transactions.currency"),
+        join = rules_join("transactions"),
+        bucket = bucket,
     );
-    Ok(TitheBalanceBreakdown {
-        total_balance,
-        maaser_balance,
-        chomesh_balance,
-    })
+
+    // Deltas are in micro-units; balances are rounded to whole micro-units before
+    // being added into the total.
+    let balances = format!(
+        "{}, {}, {}",
+        micro_to_major_sql("ROUND(maaser_balance)"),
+        micro_to_major_sql("ROUND(chomesh_balance)"),
+        micro_to_major_sql("ROUND(maaser_balance) + ROUND(chomesh_balance)"),
+    );
+
+    let sql = if granularity == "transaction" {
+        format!(
+            "{}
+            SELECT period, id, type, description, {}, 1,
+                   {}, {}, {}
+            FROM running
+            WHERE date >= ?1 AND date <= ?2 AND (maaser_delta != 0 OR chomesh_delta != 0)
+            ORDER BY seq",
+            running_sql,
+            to_major_sql("amount", "currency"),
+            micro_to_major_sql("ROUND(maaser_delta)"),
+            micro_to_major_sql("ROUND(chomesh_delta)"),
+            balances
+        )
+    } else {
+        // SQLite returns the bare balance columns from the row holding MAX(seq),
+        // i.e. the last transaction of each bucket.
+        format!(
+            "{}
+            SELECT period, NULL, NULL, NULL, NULL, COUNT(*),
+                   {}, {}, {}, MAX(seq)
+            FROM running
+            WHERE date >= ?1 AND date <= ?2
+            GROUP BY period
+            ORDER BY period",
+            running_sql,
+            micro_to_major_sql("ROUND(COALESCE(SUM(maaser_delta), 0))"),
+            micro_to_major_sql("ROUND(COALESCE(SUM(chomesh_delta), 0))"),
+            balances
+        )
+    };
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt
+        .query_map(params![start_date, end_date], |row| {
+            Ok(TitheLedgerEntry {
+                period: row.get(0)?,
+                transaction_id: row.get(1)?,
+                transaction_type: row.get(2)?,
+                description: row.get(3)?,
+                amount: row.get(4)?,
+                transaction_count: row.get(5)?,
+                maaser_change: row.get(6)?,
+                chomesh_change: row.get(7)?,
+                maaser_balance: row.get(8)?,
+                chomesh_balance: row.get(9)?,
+                total_balance: row.get(10)?,
+            })
+        })?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(Ten10Error::from)
+}
+
+/// Tithe balance over time within [start_date, end_date]: after each transaction
+/// (`granularity = "transaction"`) or at the end of each "day" / "week" / "month".
+#[tauri::command]
+pub fn get_desktop_tithe_balance_ledger(
+    db_state: State<'_, DbState>,
+    start_date: String,
+    end_date: String,
+    granularity: String,
+) -> Result<Vec<TitheLedgerEntry>, Ten10Error> {
+    let conn_guard = db_state.read()?;
+    query_tithe_ledger(&conn_guard, &start_date, &end_date, &granularity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO transactions (id, date, amount, currency, type, is_chomesh, created_at) VALUES
+                ('b1', '2023-12-31', 4000, 'ILS', 'initial_balance', 0, '1'),
+                ('i1', '2024-01-02', 100000, 'ILS', 'income', 1, '2'),
+                ('p1', '2024-01-03', 7000, 'ILS', 'expense', 0, '3'),
+                ('d1', '2024-01-03', 5000, 'ILS', 'donation', 0, '4'),
+                ('d2', '2024-01-10', 3000, 'ILS', 'donation', 1, '5'),
+                ('i2', '2024-02-01', 50000, 'ILS', 'income', 0, '6');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn balances(entries: &[TitheLedgerEntry]) -> Vec<(String, f64, f64)> {
+        entries
+            .iter()
+            .map(|e| (e.period.clone(), e.maaser_balance, e.chomesh_balance))
+            .collect()
+    }
+
+    #[test]
+    fn per_transaction_ledger_carries_opening_balance() {
+        let conn = test_db();
+        let ledger = query_tithe_ledger(&conn, "2024-01-01", "2024-12-31", "transaction").unwrap();
+
+        let ids: Vec<_> = ledger.iter().map(|e| e.transaction_id.clone().unwrap()).collect();
+        assert_eq!(ids, vec!["i1", "d1", "d2", "i2"]); // the plain expense does not move the balance
+        assert_eq!(
+            balances(&ledger),
+            vec![
+                ("2024-01-02".to_string(), 140.0, 100.0),
+                ("2024-01-03".to_string(), 90.0, 100.0),
+                ("2024-01-10".to_string(), 90.0, 70.0),
+                ("2024-02-01".to_string(), 140.0, 70.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn bucketed_ledger_reports_end_of_period_balances() {
+        let conn = test_db();
+        let months = query_tithe_ledger(&conn, "2024-01-01", "2024-12-31", "month").unwrap();
+        assert_eq!(
+            balances(&months),
+            vec![("2024-01".to_string(), 90.0, 70.0), ("2024-02".to_string(), 140.0, 70.0)]
+        );
+        assert_eq!(months[0].transaction_count, 4);
+        assert_eq!((months[0].maaser_change, months[0].chomesh_change), (50.0, 70.0));
+
+        // 2024-01-02 is a Tuesday; its week starts on Sunday 2023-12-31.
+        let weeks = query_tithe_ledger(&conn, "2024-01-01", "2024-01-31", "week").unwrap();
+        assert_eq!(
+            balances(&weeks),
+            vec![("2023-12-31".to_string(), 90.0, 100.0), ("2024-01-07".to_string(), 90.0, 70.0)]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_granularity() {
+        let conn = test_db();
+        assert!(query_tithe_ledger(&conn, "2024-01-01", "2024-12-31", "hour").is_err());
+    }
+
+    #[test]
+    fn balance_as_of_ignores_later_transactions() {
+        let conn = test_db();
+        let all = compute_tithe_balance(&conn, None).unwrap();
+        assert_eq!((all.maaser_balance, all.chomesh_balance), (140.0, 70.0));
+
+        // Inclusive of transactions on the as-of date itself.
+        let early = compute_tithe_balance(&conn, Some("2024-01-03")).unwrap();
+        assert_eq!((early.maaser_balance, early.chomesh_balance), (90.0, 100.0));
+        assert_eq!(early.total_balance, 190.0);
+
+        let before_history = compute_tithe_balance(&conn, Some("2000-01-01")).unwrap();
+        assert_eq!(before_history.total_balance, 0.0);
+
+        for bad in ["2024-1-3", "2024-02-30", "today"] {
+            assert!(matches!(compute_tithe_balance(&conn, Some(bad)), Err(Ten10Error::Validation(_))));
+        }
+    }
+
+    #[test]
+    fn year_balances_carry_closing_into_next_opening() {
+        let conn = test_db();
+        let years = query_tithe_year_balances(&conn, "gregorian", 2023, 2024).unwrap();
+        assert_eq!(years.len(), 2);
+        assert_eq!((years[0].start_date.as_str(), years[0].end_date.as_str()), ("2023-01-01", "2023-12-31"));
+        assert_eq!(years[0].opening.total_balance, 0.0);
+        assert_eq!(years[0].closing.maaser_balance, 40.0);
+        assert_eq!(years[1].opening.maaser_balance, 40.0);
+        assert_eq!((years[1].closing.maaser_balance, years[1].closing.chomesh_balance), (140.0, 70.0));
+
+        // 5784 runs 2023-09-16 .. 2024-10-02, so it holds everything in the fixture.
+        let hebrew = query_tithe_year_balances(&conn, "hebrew", 5784, 5785).unwrap();
+        assert_eq!(hebrew[0].start_date, "2023-09-16");
+        assert_eq!(hebrew[0].opening.total_balance, 0.0);
+        assert_eq!(hebrew[0].closing.total_balance, 210.0);
+        assert_eq!(hebrew[1].start_date, "2024-10-03");
+        assert_eq!(hebrew[1].opening.total_balance, 210.0);
+
+        assert!(query_tithe_year_balances(&conn, "julian", 2024, 2024).is_err());
+        assert!(query_tithe_year_balances(&conn, "gregorian", 2025, 2024).is_err());
+        assert!(matches!(query_tithe_year_balances(&conn, "gregorian", 1, i32::MAX), Err(Ten10Error::Validation(_))));
+        assert!(matches!(query_tithe_year_balances(&conn, "hebrew", 2024, 2024), Err(Ten10Error::Validation(_))));
+        assert!(matches!(query_tithe_year_balances(&conn, "gregorian", 1900, 9999), Err(Ten10Error::Validation(_))));
+    }
 }
@@ -0,0 +1,529 @@
+// src-tauri/src/commands/exchange_rate_commands.rs
+// Local exchange-rate store: the rates the frontend fetched, typed in or imported
+// from a Bank of Israel CSV / ECB XML history file, kept per pair and day in
+// `exchange_rates` (migration 0014) so conversion keeps working offline.
+//
+// A lookup takes the nearest rate on or before a date: the pair as stored, else
+// its inverse, else a cross rate through a currency both sides are quoted
+// against (ECB files quote everything against EUR, BOI files against ILS), else
+// the rate of the latest converted transaction. The rate's `source` goes into
+// the `rate_source` of the transactions converted with it.
+
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::error::{Ten10Error, Ten10Result};
+use crate::models::ExchangeRate;
+use crate::DbState;
+
+/// The currency BOI rates are quoted in.
+const BOI_QUOTE: &str = "ILS";
+/// The currency ECB reference rates are quoted against.
+const ECB_BASE: &str = "EUR";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExchangeRatePayload {
+    pub date: String,
+    pub base: String,
+    pub quote: String,
+    pub rate: f64,
+    /// Defaults to "manual"
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RateFileFormat {
+    /// Bank of Israel: the series download (`SERIES_CODE`/`TIME_PERIOD`/`OBS_VALUE`
+    /// columns) or a table with a date column and one column per currency, in ILS
+    BoiCsv,
+    /// ECB reference rates (`eurofxref-hist.xml`), against EUR
+    EcbXml,
+}
+
+impl RateFileFormat {
+    fn default_source(self) -> &'static str {
+        match self {
+            RateFileFormat::BoiCsv => "boi",
+            RateFileFormat::EcbXml => "ecb",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateImportReport {
+    pub imported: usize,
+    /// Entries without a valid date, currency or positive rate
+    pub skipped: usize,
+    pub first_date: Option<String>,
+    pub last_date: Option<String>,
+}
+
+/// What a lookup may fall back to when there is no rate for the date itself.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RateLookupOptions {
+    /// How many days before (or, with `allow_later`, after) the date a rate may
+    /// be; any when None
+    pub max_age_days: Option<i64>,
+    /// With no rate on or before the date, take the first one after it
+    pub allow_later: bool,
+    /// As a last resort, take the rate of the latest transaction converted between
+    /// the two, in either direction
+    pub use_transactions: bool,
+}
+
+impl Default for RateLookupOptions {
+    fn default() -> Self {
+        RateLookupOptions { max_age_days: None, allow_later: false, use_transactions: true }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RateMethod {
+    Direct,
+    Inverse,
+    Cross,
+    Transaction,
+}
+
+/// A rate found for `base` → `quote`, with the date and source it came from.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ResolvedRate {
+    pub base: String,
+    pub quote: String,
+    pub rate: f64,
+    /// The date of the rate (of its older leg, for a cross rate)
+    pub date: String,
+    /// The stored rate's source ("boi+ecb" for a cross rate over both), or
+    /// "transaction"
+    pub source: String,
+    pub method: RateMethod,
+}
+
+/// A stored rate for one ordered pair, possibly read off its inverse.
+struct Leg {
+    date: NaiveDate,
+    rate: f64,
+    source: String,
+    inverse: bool,
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    ["%Y-%m-%d", "%d/%m/%Y", "%d-%m-%Y", "%d.%m.%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value.get(..10).unwrap_or(value), format).ok())
+}
+
+fn currency_code(value: &str) -> Option<String> {
+    let code = value.trim().to_ascii_uppercase();
+    (code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())).then_some(code)
+}
+
+fn validate_code(value: &str) -> Ten10Result<String> {
+    currency_code(value).ok_or_else(|| Ten10Error::validation(format!("Invalid currency code: {}", value)))
+}
+
+fn validate_date(value: &str) -> Ten10Result<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| Ten10Error::validation(format!("Invalid date: {}", value)))
+}
+
+/// A rate row ready to store, or None when the entry is unusable.
+fn rate_entry(date: &str, base: &str, quote: &str, rate: f64) -> Option<(NaiveDate, String, String, f64)> {
+    let (date, base, quote) = (parse_date(date)?, currency_code(base)?, currency_code(quote)?);
+    (base != quote && rate.is_finite() && rate > 0.0).then_some((date, base, quote, rate))
+}
+
+/// The comma-separated fields of a CSV line, unquoted.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+/// Parses either BOI layout; returns the entries and the number of unusable ones.
+fn parse_boi_csv(content: &str) -> (Vec<(NaiveDate, String, String, f64)>, usize) {
+    let mut lines = content.lines().map(|l| l.trim_start_matches('\u{feff}')).filter(|l| !l.trim().is_empty());
+    let Some(header) = lines.next().map(csv_fields) else {
+        return (Vec::new(), 0);
+    };
+    let column = |name: &str| header.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let (mut entries, mut skipped) = (Vec::new(), 0);
+    let rate = |value: Option<&String>| value.and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+
+    if let (Some(date_col), Some(value_col)) = (column("TIME_PERIOD"), column("OBS_VALUE")) {
+        // Series download: "RER_USD_ILS" is the representative rate of USD in ILS
+        let (series_col, base_col, quote_col) = (column("SERIES_CODE"), column("BASE_CURRENCY"), column("COUNTER_CURRENCY"));
+        for fields in lines.map(csv_fields) {
+            let series: Vec<&str> = series_col.and_then(|c| fields.get(c)).map_or(Vec::new(), |s| s.split('_').collect());
+            let base = base_col.and_then(|c| fields.get(c)).map(String::as_str).or(series.get(1).copied());
+            let quote = quote_col.and_then(|c| fields.get(c)).map(String::as_str).or(series.get(2).copied());
+            let date = fields.get(date_col).map_or("", String::as_str);
+            match rate_entry(date, base.unwrap_or(""), quote.unwrap_or(BOI_QUOTE), rate(fields.get(value_col))) {
+                Some(entry) => entries.push(entry),
+                None => skipped += 1,
+            }
+        }
+    } else {
+        // Table: a date column, then one column of ILS rates per currency
+        let currencies: Vec<Option<String>> = header.iter().map(|h| currency_code(h)).collect();
+        for fields in lines.map(csv_fields) {
+            let date = fields.first().map_or("", String::as_str);
+            for (col, currency) in currencies.iter().enumerate().skip(1) {
+                let Some(currency) = currency else { continue };
+                match rate_entry(date, currency, BOI_QUOTE, rate(fields.get(col))) {
+                    Some(entry) => entries.push(entry),
+                    None => skipped += 1,
+                }
+            }
+        }
+    }
+    (entries, skipped)
+}
+
+/// The value of attribute `name` in an XML start tag.
+fn xml_attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!(" {}=", name))? + name.len() + 2;
+    let quote = tag[start..].chars().next().filter(|q| *q == '"' || *q == '\'')?;
+    let value = &tag[start + 1..];
+    value.find(quote).map(|end| &value[..end])
+}
+
+/// Parses ECB reference rates: `<Cube time="…">` days of `<Cube currency="…" rate="…"/>`.
+fn parse_ecb_xml(content: &str) -> (Vec<(NaiveDate, String, String, f64)>, usize) {
+    let (mut entries, mut skipped) = (Vec::new(), 0);
+    let mut day: Option<String> = None;
+    for tag in content.split('<').skip(1).filter(|t| t.starts_with("Cube")) {
+        let tag = tag.split('>').next().unwrap_or_default();
+        if let Some(time) = xml_attr(tag, "time") {
+            day = Some(time.to_string());
+        }
+        if let (Some(currency), Some(rate)) = (xml_attr(tag, "currency"), xml_attr(tag, "rate")) {
+            let rate = rate.parse::<f64>().unwrap_or(0.0);
+            match day.as_deref().and_then(|d| rate_entry(d, ECB_BASE, currency, rate)) {
+                Some(entry) => entries.push(entry),
+                None => skipped += 1,
+            }
+        }
+    }
+    (entries, skipped)
+}
+
+/// Inserts or replaces the rate of a pair and day.
+fn upsert_rate(conn: &Connection, date: NaiveDate, base: &str, quote: &str, rate: f64, source: &str) -> RusqliteResult<()> {
+    conn.execute(
+        "INSERT INTO exchange_rates (date, base, quote, rate, source, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (base, quote, date) DO UPDATE SET rate = excluded.rate, source = excluded.source,
+             updated_at = excluded.updated_at",
+        params![date.format("%Y-%m-%d").to_string(), base, quote, rate, source, Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+pub(crate) fn import_rates(conn: &Connection, format: RateFileFormat, content: &str, source: &str) -> Ten10Result<RateImportReport> {
+    let (entries, skipped) = match format {
+        RateFileFormat::BoiCsv => parse_boi_csv(content),
+        RateFileFormat::EcbXml => parse_ecb_xml(content),
+    };
+    if entries.is_empty() {
+        return Err(Ten10Error::validation(format!("No exchange rates found in the {:?} file", format)));
+    }
+    for (date, base, quote, rate) in &entries {
+        upsert_rate(conn, *date, base, quote, *rate, source)?;
+    }
+    let date = |d: Option<&NaiveDate>| d.map(|d| d.format("%Y-%m-%d").to_string());
+    Ok(RateImportReport {
+        imported: entries.len(),
+        skipped,
+        first_date: date(entries.iter().map(|e| &e.0).min()),
+        last_date: date(entries.iter().map(|e| &e.0).max()),
+    })
+}
+
+/// The stored rate of `base` → `quote` (or of its inverse) nearest to `date`
+/// from below or, when `later`, from above.
+fn nearest_leg(conn: &Connection, base: &str, quote: &str, date: NaiveDate, later: bool) -> RusqliteResult<Option<Leg>> {
+    let (comparison, order) = if later { (">", "ASC") } else { ("<=", "DESC") };
+    let sql = format!(
+        "SELECT date, rate, source FROM exchange_rates WHERE base = ?1 AND quote = ?2 AND date {comparison} ?3
+         ORDER BY date {order} LIMIT 1"
+    );
+    let date_str = date.format("%Y-%m-%d").to_string();
+    let read = |base: &str, quote: &str, inverse: bool| -> RusqliteResult<Option<Leg>> {
+        conn.query_row(&sql, params![base, quote, date_str], |row| {
+            let (date, rate, source): (String, f64, String) = (row.get(0)?, row.get(1)?, row.get(2)?);
+            Ok(parse_date(&date).map(|date| Leg { date, rate: if inverse { 1.0 / rate } else { rate }, source, inverse }))
+        })
+        .optional()
+        .map(Option::flatten)
+    };
+    let (direct, inverse) = (read(base, quote, false)?, read(quote, base, true)?);
+    // The closest of the two, the pair as stored on a tie
+    Ok(match (direct, inverse) {
+        (Some(d), Some(i)) if (i.date > d.date) != later && i.date != d.date => Some(i),
+        (Some(d), _) => Some(d),
+        (None, i) => i,
+    })
+}
+
+/// `nearest_leg` on or before `date`, else after it when allowed, within the maximum age.
+fn leg(conn: &Connection, base: &str, quote: &str, date: NaiveDate, options: &RateLookupOptions) -> RusqliteResult<Option<Leg>> {
+    let within = |leg: &Leg| options.max_age_days.is_none_or(|days| (date - leg.date).num_days().abs() <= days);
+    if let Some(leg) = nearest_leg(conn, base, quote, date, false)?.filter(within) {
+        return Ok(Some(leg));
+    }
+    if options.allow_later {
+        return Ok(nearest_leg(conn, base, quote, date, true)?.filter(within));
+    }
+    Ok(None)
+}
+
+/// The rate of `base` → `quote` for `date` per `options`, or None when there is none.
+pub(crate) fn resolve_rate(
+    conn: &Connection,
+    base: &str,
+    quote: &str,
+    date: NaiveDate,
+    options: &RateLookupOptions,
+) -> RusqliteResult<Option<ResolvedRate>> {
+    let resolved = |rate: f64, date: NaiveDate, source: String, method: RateMethod| ResolvedRate {
+        base: base.to_string(),
+        quote: quote.to_string(),
+        rate,
+        date: date.format("%Y-%m-%d").to_string(),
+        source,
+        method,
+    };
+    if base == quote {
+        return Ok(Some(resolved(1.0, date, "identity".to_string(), RateMethod::Direct)));
+    }
+    if let Some(leg) = leg(conn, base, quote, date, options)? {
+        let method = if leg.inverse { RateMethod::Inverse } else { RateMethod::Direct };
+        return Ok(Some(resolved(leg.rate, leg.date, leg.source, method)));
+    }
+
+    // Cross rate through a currency both are quoted against; the freshest wins
+    let mut stmt = conn.prepare(
+        "SELECT quote FROM exchange_rates WHERE base IN (?1, ?2)
+         UNION SELECT base FROM exchange_rates WHERE quote IN (?1, ?2)",
+    )?;
+    let pivots: Vec<String> = stmt.query_map(params![base, quote], |row| row.get(0))?.collect::<RusqliteResult<_>>()?;
+    let mut best: Option<(Leg, Leg)> = None;
+    for pivot in pivots.iter().filter(|p| *p != base && *p != quote) {
+        if let (Some(from), Some(to)) = (leg(conn, base, pivot, date, options)?, leg(conn, quote, pivot, date, options)?) {
+            let age = |a: &Leg, b: &Leg| (date - a.date).num_days().abs().max((date - b.date).num_days().abs());
+            if best.as_ref().is_none_or(|(f, t)| age(&from, &to) < age(f, t)) {
+                best = Some((from, to));
+            }
+        }
+    }
+    if let Some((from, to)) = best {
+        let source = if from.source == to.source { from.source } else { format!("{}+{}", from.source, to.source) };
+        return Ok(Some(resolved(from.rate / to.rate, from.date.min(to.date), source, RateMethod::Cross)));
+    }
+
+    // The latest conversion between the two, inverted when it went quote → base
+    if options.use_transactions {
+        let last: Option<(f64, String)> = conn
+            .query_row(
+                "SELECT CASE WHEN original_currency = ?1 THEN conversion_rate ELSE 1.0 / conversion_rate END, date
+                 FROM transactions
+                 WHERE ((original_currency = ?1 AND currency = ?2) OR (original_currency = ?2 AND currency = ?1))
+                   AND conversion_rate > 0 AND date <= ?3
+                 ORDER BY date DESC, original_currency = ?1 DESC, created_at DESC
+                 LIMIT 1",
+                params![base, quote, date.format("%Y-%m-%d").to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        if let Some((rate, day)) = last {
+            let day = parse_date(&day).unwrap_or(date);
+            return Ok(Some(resolved(rate, day, "transaction".to_string(), RateMethod::Transaction)));
+        }
+    }
+    Ok(None)
+}
+
+/// Stores rates, replacing any of the same pair and day. Returns how many were stored.
+#[tauri::command]
+pub fn upsert_exchange_rates(db_state: State<'_, DbState>, rates: Vec<ExchangeRatePayload>) -> Result<usize, Ten10Error> {
+    let mut conn = db_state.0.lock()?;
+    let tx = conn.transaction()?;
+    for rate in &rates {
+        let (date, base, quote) = (validate_date(&rate.date)?, validate_code(&rate.base)?, validate_code(&rate.quote)?);
+        if base == quote || !rate.rate.is_finite() || rate.rate <= 0.0 {
+            return Err(Ten10Error::validation(format!("Invalid rate {} for {} -> {}", rate.rate, base, quote)));
+        }
+        let source = rate.source.as_deref().map(str::trim).filter(|s| !s.is_empty()).unwrap_or("manual");
+        upsert_rate(&tx, date, &base, &quote, rate.rate, source)?;
+    }
+    tx.commit()?;
+    Ok(rates.len())
+}
+
+/// Imports a rate history file; `source` defaults to "boi" or "ecb".
+#[tauri::command]
+pub fn import_exchange_rates(
+    db_state: State<'_, DbState>,
+    path: String,
+    format: RateFileFormat,
+    source: Option<String>,
+) -> Result<RateImportReport, Ten10Error> {
+    let content = std::fs::read_to_string(&path)?;
+    let source = source.filter(|s| !s.trim().is_empty()).unwrap_or_else(|| format.default_source().to_string());
+    let mut conn = db_state.0.lock()?;
+    let tx = conn.transaction()?;
+    let report = import_rates(&tx, format, &content, &source)?;
+    tx.commit()?;
+    println!(
+        "[Rust DEBUG] import_exchange_rates: {} rates from {} ({} skipped)",
+        report.imported, path, report.skipped
+    );
+    Ok(report)
+}
+
+/// The rate of `base` → `quote` for `date` (today by default), falling back per `options`.
+#[tauri::command]
+pub fn lookup_exchange_rate(
+    db_state: State<'_, DbState>,
+    base: String,
+    quote: String,
+    date: Option<String>,
+    options: Option<RateLookupOptions>,
+) -> Result<Option<ResolvedRate>, Ten10Error> {
+    let (base, quote) = (validate_code(&base)?, validate_code(&quote)?);
+    let date = match date {
+        Some(date) => validate_date(&date)?,
+        None => chrono::Local::now().date_naive(),
+    };
+    let conn = db_state.read()?;
+    Ok(resolve_rate(&conn, &base, &quote, date, &options.unwrap_or_default())?)
+}
+
+/// The stored rates of `base` → `quote`, newest first.
+#[tauri::command]
+pub fn get_exchange_rates(
+    db_state: State<'_, DbState>,
+    base: String,
+    quote: String,
+) -> Result<Vec<ExchangeRate>, Ten10Error> {
+    let (base, quote) = (validate_code(&base)?, validate_code(&quote)?);
+    let conn = db_state.read()?;
+    let mut stmt = conn.prepare(
+        "SELECT date, base, quote, rate, source, updated_at FROM exchange_rates
+         WHERE base = ?1 AND quote = ?2 ORDER BY date DESC",
+    )?;
+    let rates = stmt.query_map(params![base, quote], ExchangeRate::from_row)?.collect::<RusqliteResult<_>>()?;
+    Ok(rates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn
+    }
+
+    fn lookup(conn: &Connection, base: &str, quote: &str, day: &str, options: RateLookupOptions) -> Option<(f64, String, String, RateMethod)> {
+        resolve_rate(conn, base, quote, date(day), &options).unwrap().map(|r| (r.rate, r.date, r.source, r.method))
+    }
+
+    #[test]
+    fn imports_boi_and_ecb_files() {
+        let conn = test_db();
+        let series = "\u{feff}SERIES_CODE,FREQ,TIME_PERIOD,OBS_VALUE\n\
+                      RER_USD_ILS,D,2024-01-02,3.621\n\
+                      RER_USD_ILS,D,2024-01-03,\"3.640\"\n\
+                      RER_USD_ILS,D,2024-01-04,\n";
+        let report = import_rates(&conn, RateFileFormat::BoiCsv, series, "boi").unwrap();
+        assert_eq!((report.imported, report.skipped), (2, 1));
+        assert_eq!((report.first_date.as_deref(), report.last_date.as_deref()), (Some("2024-01-02"), Some("2024-01-03")));
+
+        let table = "Date,USD,GBP,Notes\n05/01/2024,3.70,4.68,x\n";
+        let report = import_rates(&conn, RateFileFormat::BoiCsv, table, "boi").unwrap();
+        assert_eq!((report.imported, report.skipped), (2, 0));
+
+        let ecb = r#"<gesmes:Envelope><Cube>
+            <Cube time="2024-01-05"><Cube currency="USD" rate="1.0921"/><Cube currency="ILS" rate="3.9789"/></Cube>
+            <Cube time="2024-01-04"><Cube currency="USD" rate="bad"/></Cube>
+        </Cube></gesmes:Envelope>"#;
+        let report = import_rates(&conn, RateFileFormat::EcbXml, ecb, "ecb").unwrap();
+        assert_eq!((report.imported, report.skipped), (2, 1));
+        assert!(import_rates(&conn, RateFileFormat::EcbXml, "<xml/>", "ecb").is_err());
+
+        let stored: Vec<(String, String, String, f64)> = conn
+            .prepare("SELECT date, base, quote, rate FROM exchange_rates ORDER BY date, base, quote")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
+            .unwrap()
+            .collect::<RusqliteResult<_>>()
+            .unwrap();
+        assert_eq!(stored[1], ("2024-01-03".to_string(), "USD".to_string(), "ILS".to_string(), 3.64));
+        assert_eq!(stored.len(), 6);
+    }
+
+    #[test]
+    fn looks_up_direct_inverse_cross_and_transaction_rates() {
+        let conn = test_db();
+        for (day, base, quote, rate, source) in [
+            ("2024-01-02", "USD", "ILS", 3.6, "boi"),
+            ("2024-01-10", "USD", "ILS", 3.7, "boi"),
+            ("2024-01-08", "EUR", "USD", 1.1, "ecb"),
+            ("2024-01-08", "EUR", "GBP", 0.8, "ecb"),
+        ] {
+            upsert_rate(&conn, date(day), base, quote, rate, source).unwrap();
+        }
+        let options = RateLookupOptions::default();
+
+        assert_eq!(lookup(&conn, "USD", "ILS", "2024-01-09", options), Some((3.6, "2024-01-02".into(), "boi".into(), RateMethod::Direct)));
+        assert_eq!(lookup(&conn, "ILS", "USD", "2024-01-10", options).map(|r| (r.0, r.3)), Some((1.0 / 3.7, RateMethod::Inverse)));
+        let cross = lookup(&conn, "GBP", "USD", "2024-01-08", options).unwrap();
+        assert_eq!((format!("{:.4}", cross.0), cross.2, cross.3), ("1.3750".to_string(), "ecb".to_string(), RateMethod::Cross));
+        let mixed = lookup(&conn, "EUR", "ILS", "2024-01-31", options).unwrap();
+        assert_eq!((format!("{:.2}", mixed.0), mixed.1, mixed.2), ("4.07".to_string(), "2024-01-08".to_string(), "ecb+boi".to_string()));
+
+        // Fallbacks
+        assert_eq!(lookup(&conn, "USD", "ILS", "2024-01-01", options), None);
+        let later = RateLookupOptions { allow_later: true, ..options };
+        assert_eq!(lookup(&conn, "USD", "ILS", "2024-01-01", later).map(|r| r.0), Some(3.6));
+        let fresh_only = RateLookupOptions { max_age_days: Some(3), ..options };
+        assert_eq!(lookup(&conn, "USD", "ILS", "2024-01-09", fresh_only), None);
+
+        conn.execute_batch(
+            "INSERT INTO transactions (id, date, amount, currency, type, original_amount, original_currency, conversion_rate)
+                VALUES ('t1', '2023-12-01', 400, 'ILS', 'donation', 100, 'CHF', 4.0);",
+        )
+        .unwrap();
+        assert_eq!(lookup(&conn, "CHF", "ILS", "2024-01-01", options), Some((4.0, "2023-12-01".into(), "transaction".into(), RateMethod::Transaction)));
+        assert_eq!(lookup(&conn, "ILS", "CHF", "2024-01-01", options).map(|r| (r.0, r.3)), Some((0.25, RateMethod::Transaction)));
+        let store_only = RateLookupOptions { use_transactions: false, ..options };
+        assert_eq!(lookup(&conn, "CHF", "ILS", "2024-01-01", store_only), None);
+    }
+}
@@ -3,23 +3,26 @@ use tauri::State;
 
 use crate::DbState;
 use crate::transaction_types::expense_types_condition;
+use crate::error::Ten10Error;
+use crate::money::major_sum_sql;
 
 #[tauri::command]
 pub async fn get_desktop_total_expenses_in_range(
     db_state: State<'_, DbState>,
     start_date: String,
     end_date: String,
-) -> Result<f64, String> {
+) -> Result<f64, Ten10Error> {
     // SQL query directly embedded
     let query_sql = format!(
         "SELECT
-            COALESCE(SUM(amount), 0)
+            {}
         FROM
             transactions
         WHERE
             {} AND
             date >= ?1 AND
             date <= ?2;",
+        major_sum_sql("amount"),
         expense_types_condition()
     );
 
@@ -28,7 +31,7 @@ pub async fn get_desktop_total_expenses_in_range(
         start_date, end_date
     );
 
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
+    let conn_guard = db_state.read()?;
     let conn: &Connection = &*conn_guard;
 
     match conn.query_row::<f64, _, _>(query_sql.as_str(), params![start_date, end_date], |row| row.get(0)) {
@@ -41,10 +44,7 @@ pub async fn get_desktop_total_expenses_in_range(
         }
         Err(e) => {
             eprintln!("Desktop Query Error (expense_commands.rs): {}", e);
-            Err(format!(
-                "Failed to fetch total expenses with rusqlite: {}",
-                e
-            ))
+            Err(e.into())
         }
     }
 }
@@ -0,0 +1,748 @@
+// src-tauri/src/commands/export_commands.rs
+// File exports written directly by Rust, so transactions never cross IPC.
+// CSV is streamed from the SQLite cursor to disk; XLSX builds the workbook
+// (a sheet per transaction group plus a summary) and writes it in one go.
+//
+// Localization stays in the frontend: it sends the header text per column and
+// `labels` for stored keys (transaction types, categories, payment methods...).
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDate};
+use rusqlite::{Connection, ToSql};
+use rust_xlsxwriter::{ColNum, ExcelDateTime, Format, FormatBorder, RowNum, Workbook, Worksheet};
+use serde::Deserialize;
+use tauri::State;
+
+use super::chart_commands::{query_monthly_summary, MonthlySummaryBreakdowns, MAX_SUMMARY_MONTHS};
+use super::db_commands::default_currency_or_ils;
+use super::donation_commands::compute_tithe_balance;
+use super::transaction_commands::{build_export_query, export_row, TransactionFiltersPayload};
+use crate::error::{Ten10Error, Ten10Result};
+use crate::models::TransactionForTable;
+use crate::money::minor_unit_exponent;
+use crate::transaction_types::{DONATION_TYPES, EXPENSE_TYPES, INCOME_TYPES};
+use crate::DbState;
+
+/// Every column a transaction export can contain, in the default order.
+pub const EXPORT_COLUMNS: &[&str] = &[
+    "date",
+    "type",
+    "description",
+    "category",
+    "recipient",
+    "payment_method",
+    "amount",
+    "currency",
+    "is_chomesh",
+    "movement_type",
+    "recurring_frequency",
+    "recurring_progress",
+    "id",
+    "user_id",
+    "created_at",
+    "updated_at",
+    "source_recurring_id",
+    "original_amount",
+    "original_currency",
+    "conversion_rate",
+];
+
+/// Columns exported when the caller does not choose (everything but the conversion details).
+const DEFAULT_COLUMN_COUNT: usize = 17;
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvExportOptions {
+    /// Column keys from `EXPORT_COLUMNS`, in output order
+    #[serde(default)]
+    pub columns: Option<Vec<String>>,
+    /// Header text per column key; the key itself when missing
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Display text for stored values, keyed "type.income", "category.expense.food",
+    /// "payment_method.cash", "frequency.monthly", "boolean.yes", "movement.recurring"...
+    /// Values without a label are written as stored.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// "," (default), ";" or "\t"
+    #[serde(default)]
+    pub delimiter: Option<String>,
+}
+
+/// Column keys from `options`, validated; the default set when none are given.
+fn resolve_columns(columns: &Option<Vec<String>>) -> Ten10Result<Vec<&'static str>> {
+    match columns {
+        Some(keys) if !keys.is_empty() => keys
+            .iter()
+            .map(|key| {
+                EXPORT_COLUMNS
+                    .iter()
+                    .find(|c| **c == key)
+                    .copied()
+                    .ok_or_else(|| Ten10Error::validation(format!("Unknown export column: {}", key)))
+            })
+            .collect(),
+        _ => Ok(EXPORT_COLUMNS[..DEFAULT_COLUMN_COUNT].to_vec()),
+    }
+}
+
+/// Display text of transaction cells, using the frontend's `labels`.
+struct Labels<'a>(&'a HashMap<String, String>);
+
+impl Labels<'_> {
+    fn label(&self, key: String, stored: &str) -> String {
+        self.0.get(&key).cloned().unwrap_or_else(|| stored.to_string())
+    }
+
+    fn cell(&self, column: &str, row: &TransactionForTable) -> String {
+        let t = &row.transaction;
+        let opt = |v: &Option<String>| v.clone().unwrap_or_default();
+        match column {
+            "date" => t.date.clone(),
+            "type" => self.label(format!("type.{}", t.transaction_type), &t.transaction_type),
+            "description" => opt(&t.description),
+            "category" => match &t.category {
+                Some(category) => {
+                    let base = match t.transaction_type.as_str() {
+                        "income" | "exempt-income" => "income",
+                        "expense" | "recognized-expense" => "expense",
+                        _ => "other",
+                    };
+                    self.label(format!("category.{}.{}", base, category), category)
+                }
+                None => String::new(),
+            },
+            "recipient" => opt(&t.recipient),
+            "payment_method" => match &t.payment_method {
+                Some(method) => self.label(format!("payment_method.{}", method), method),
+                None => String::new(),
+            },
+            "amount" => t.amount.to_string(),
+            "currency" => t.currency.clone(),
+            "is_chomesh" => match t.is_chomesh {
+                Some(true) => self.label("boolean.yes".to_string(), "yes"),
+                Some(false) => self.label("boolean.no".to_string(), "no"),
+                None => String::new(),
+            },
+            "movement_type" => {
+                if t.source_recurring_id.is_some() {
+                    self.label("movement.recurring".to_string(), "recurring")
+                } else {
+                    self.label("movement.regular".to_string(), "regular")
+                }
+            }
+            "recurring_frequency" => match &row.recurring_info {
+                Some(info) => self.label(format!("frequency.{}", info.frequency), &info.frequency),
+                None => String::new(),
+            },
+            "recurring_progress" => {
+                let total = row.recurring_info.as_ref().and_then(|info| info.total_occurrences);
+                match (t.occurrence_number, total) {
+                    (Some(n), Some(total)) => format!("{}/{}", n, total),
+                    (Some(n), None) => format!("{}/∞", n),
+                    _ => String::new(),
+                }
+            }
+            "id" => t.id.clone(),
+            "user_id" => opt(&t.user_id),
+            "created_at" => opt(&t.created_at),
+            "updated_at" => opt(&t.updated_at),
+            "source_recurring_id" => opt(&t.source_recurring_id),
+            "original_amount" => t.original_amount.map(|m| m.to_string()).unwrap_or_default(),
+            "original_currency" => opt(&t.original_currency),
+            "conversion_rate" => t.conversion_rate.map(|r| r.to_string()).unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+}
+
+struct CsvFormat<'a> {
+    columns: Vec<&'static str>,
+    labels: Labels<'a>,
+    delimiter: char,
+}
+
+impl<'a> CsvFormat<'a> {
+    fn new(options: &'a CsvExportOptions) -> Ten10Result<Self> {
+        let delimiter = match options.delimiter.as_deref().unwrap_or(",") {
+            "," => ',',
+            ";" => ';',
+            "\t" | "tab" => '\t',
+            other => return Err(Ten10Error::validation(format!("Unsupported CSV delimiter: {:?}", other))),
+        };
+        Ok(CsvFormat { columns: resolve_columns(&options.columns)?, labels: Labels(&options.labels), delimiter })
+    }
+
+    /// Quotes a field containing the delimiter, a quote or a line break (RFC 4180).
+    fn escape(&self, value: &str) -> String {
+        if value.contains(self.delimiter) || value.contains(['"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    fn write_line<W: Write>(&self, out: &mut W, cells: impl Iterator<Item = String>) -> std::io::Result<()> {
+        let line: Vec<String> = cells.map(|c| self.escape(&c)).collect();
+        out.write_all(line.join(&self.delimiter.to_string()).as_bytes())?;
+        out.write_all(b"\r\n")
+    }
+}
+
+/// Writes the transactions matching `filters` as CSV to `out`. Returns the number of rows.
+pub(crate) fn write_transactions_csv<W: Write>(
+    conn: &Connection,
+    filters: &TransactionFiltersPayload,
+    options: &CsvExportOptions,
+    out: &mut W,
+) -> Ten10Result<usize> {
+    let format = CsvFormat::new(options)?;
+
+    // UTF-8 BOM so Excel opens Hebrew text correctly
+    out.write_all("\u{FEFF}".as_bytes())?;
+    format.write_line(
+        out,
+        format
+            .columns
+            .iter()
+            .map(|c| options.headers.get(*c).cloned().unwrap_or_else(|| c.to_string())),
+    )?;
+
+    let (query, params) = build_export_query(filters);
+    let params: Vec<&dyn ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    let mut stmt = conn.prepare(&query)?;
+    let mut rows = stmt.query(params.as_slice())?;
+
+    let mut count = 0;
+    while let Some(row) = rows.next()? {
+        let row = export_row(row)?;
+        format.write_line(out, format.columns.iter().map(|c| format.labels.cell(c, &row)))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Writes a file at `path` (chosen with the dialog plugin) through `write`. The file is
+/// written next to the target and moved into place at the end, so a failed export
+/// never leaves a truncated file behind.
+fn write_atomically(
+    path: &str,
+    write: impl FnOnce(&mut BufWriter<File>) -> Ten10Result<usize>,
+) -> Ten10Result<usize> {
+    let partial = PathBuf::from(format!("{}.part", path));
+    let result = File::create(&partial).map_err(Ten10Error::from).and_then(|file| {
+        let mut out = BufWriter::new(file);
+        let count = write(&mut out)?;
+        out.flush()?;
+        Ok(count)
+    });
+
+    match result {
+        Ok(count) => {
+            fs::rename(&partial, Path::new(path))?;
+            Ok(count)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            Err(e)
+        }
+    }
+}
+
+/// Streams the transactions matching `filters` into a CSV file at `path`.
+/// Returns the number of rows written.
+#[tauri::command]
+pub fn export_transactions_csv(
+    db_state: State<'_, DbState>,
+    filters: TransactionFiltersPayload,
+    path: String,
+    options: Option<CsvExportOptions>,
+) -> Result<usize, Ten10Error> {
+    let options = options.unwrap_or_default();
+    let conn = db_state.read()?;
+    let count = write_atomically(&path, |out| write_transactions_csv(&conn, &filters, &options, out))?;
+    println!("[Rust] export_transactions_csv: {} rows written to {}", count, path);
+    Ok(count)
+}
+
+// ─── XLSX ─────────────────────────────────────────────────────────────────────
+
+/// Transaction sheets of the workbook, in order, with the types each one holds.
+/// Types outside every group (initial_balance) go to an "other" sheet when present.
+const SHEET_GROUPS: [(&str, &[&str]); 3] =
+    [("income", INCOME_TYPES), ("expenses", EXPENSE_TYPES), ("donations", DONATION_TYPES)];
+
+/// Months in the summary sheet when the caller does not choose.
+const DEFAULT_SUMMARY_MONTHS: i32 = 12;
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct XlsxExportOptions {
+    /// Column keys from `EXPORT_COLUMNS`, in output order
+    #[serde(default)]
+    pub columns: Option<Vec<String>>,
+    /// Header text per column key; the key itself when missing
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Same keys as `CsvExportOptions::labels`, plus sheet names ("sheet.summary",
+    /// "sheet.income", "sheet.expenses", "sheet.donations", "sheet.other") and the
+    /// summary captions ("summary.tithe_balance", "summary.maaser_balance",
+    /// "summary.chomesh_balance", "summary.month", "summary.income",
+    /// "summary.donations", "summary.expenses", "summary.total")
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Sheet direction; true for Hebrew
+    #[serde(default)]
+    pub right_to_left: bool,
+    /// Months of totals in the summary sheet, ending with the month of the
+    /// filters' `dateTo` (today when open-ended). 12 when missing.
+    #[serde(default)]
+    pub summary_months: Option<i32>,
+    /// "gregorian" (default) | "hebrew", for the summary months
+    #[serde(default)]
+    pub calendar: Option<String>,
+}
+
+/// Excel number format showing `currency`'s fraction digits and its symbol.
+fn currency_num_format(currency: &str) -> String {
+    let digits = minor_unit_exponent(currency) as usize;
+    let number = if digits == 0 { "#,##0".to_string() } else { format!("#,##0.{}", "0".repeat(digits)) };
+    let symbol = match currency {
+        "ILS" => "₪",
+        "USD" => "$",
+        "EUR" => "€",
+        "GBP" => "£",
+        other => other,
+    };
+    format!("{} \"{}\"", number, symbol)
+}
+
+/// Column width in characters for a column key.
+fn column_width(column: &str) -> f64 {
+    match column {
+        "description" => 30.0,
+        "id" | "user_id" | "source_recurring_id" => 38.0,
+        "recipient" | "category" | "created_at" | "updated_at" => 20.0,
+        "currency" | "original_currency" => 10.0,
+        _ => 15.0,
+    }
+}
+
+/// A valid, unique Excel sheet name: no []:*?/\, at most 31 characters.
+fn sheet_name(wanted: &str, taken: &[String]) -> String {
+    let clean: String = wanted
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .collect::<String>()
+        .trim_matches(|c: char| c == '\'' || c.is_whitespace())
+        .chars()
+        .take(31)
+        .collect();
+    let base = if clean.is_empty() { "Sheet".to_string() } else { clean };
+    let mut name = base.clone();
+    let mut n = 2;
+    while taken.iter().any(|t| t.to_lowercase() == name.to_lowercase()) {
+        let suffix = format!(" ({})", n);
+        name = format!("{}{}", base.chars().take(31 - suffix.chars().count()).collect::<String>(), suffix);
+        n += 1;
+    }
+    name
+}
+
+struct XlsxFormats {
+    header: Format,
+    date: Format,
+    rate: Format,
+    currencies: HashMap<String, Format>,
+}
+
+impl XlsxFormats {
+    fn new() -> Self {
+        XlsxFormats {
+            header: Format::new().set_bold().set_border_bottom(FormatBorder::Thin),
+            date: Format::new().set_num_format("dd/mm/yyyy"),
+            rate: Format::new().set_num_format("0.0000"),
+            currencies: HashMap::new(),
+        }
+    }
+
+    fn currency(&mut self, currency: &str) -> &Format {
+        self.currencies
+            .entry(currency.to_string())
+            .or_insert_with(|| Format::new().set_num_format(currency_num_format(currency)))
+    }
+}
+
+/// One transaction sheet being filled.
+struct TransactionSheet {
+    worksheet: Worksheet,
+    next_row: RowNum,
+}
+
+struct XlsxWriter<'a> {
+    columns: Vec<&'static str>,
+    labels: Labels<'a>,
+    headers: &'a HashMap<String, String>,
+    right_to_left: bool,
+    formats: XlsxFormats,
+    sheet_names: Vec<String>,
+}
+
+impl XlsxWriter<'_> {
+    fn caption(&self, key: &str, fallback: &str) -> String {
+        self.labels.label(key.to_string(), fallback)
+    }
+
+    /// A new sheet named after the `sheet.{group}` label, direction already set.
+    fn new_sheet(&mut self, group: &str) -> Ten10Result<Worksheet> {
+        let name = sheet_name(&self.caption(&format!("sheet.{}", group), group), &self.sheet_names);
+        self.sheet_names.push(name.clone());
+        let mut worksheet = Worksheet::new();
+        worksheet.set_name(name)?;
+        worksheet.set_right_to_left(self.right_to_left);
+        Ok(worksheet)
+    }
+
+    fn transaction_sheet(&mut self, group: &str) -> Ten10Result<TransactionSheet> {
+        let mut worksheet = self.new_sheet(group)?;
+        for (col, column) in self.columns.iter().enumerate() {
+            let col = col as ColNum;
+            let header = self.headers.get(*column).cloned().unwrap_or_else(|| column.to_string());
+            worksheet.write_string_with_format(0, col, header, &self.formats.header)?;
+            worksheet.set_column_width(col, column_width(column))?;
+        }
+        worksheet.set_freeze_panes(1, 0)?;
+        Ok(TransactionSheet { worksheet, next_row: 1 })
+    }
+
+    fn write_transaction(&mut self, sheet: &mut TransactionSheet, row: &TransactionForTable) -> Ten10Result<()> {
+        let t = &row.transaction;
+        let r = sheet.next_row;
+        for (col, column) in self.columns.iter().enumerate() {
+            let col = col as ColNum;
+            let ws = &mut sheet.worksheet;
+            match *column {
+                "date" => match ExcelDateTime::parse_from_str(&t.date) {
+                    Ok(date) => ws.write_datetime_with_format(r, col, &date, &self.formats.date)?,
+                    Err(_) => ws.write_string(r, col, &t.date)?,
+                },
+                "amount" => ws.write_number_with_format(r, col, t.amount.to_major(), self.formats.currency(&t.currency))?,
+                "original_amount" => match (t.original_amount, &t.original_currency) {
+                    (Some(amount), Some(currency)) => {
+                        ws.write_number_with_format(r, col, amount.to_major(), self.formats.currency(currency))?
+                    }
+                    _ => ws,
+                },
+                "conversion_rate" => match t.conversion_rate {
+                    Some(rate) => ws.write_number_with_format(r, col, rate, &self.formats.rate)?,
+                    None => ws,
+                },
+                other => ws.write_string(r, col, self.labels.cell(other, row))?,
+            };
+        }
+        sheet.next_row += 1;
+        Ok(())
+    }
+
+    /// Tithe balance (as of the export's end date) and monthly totals from the chart query.
+    fn summary_sheet(
+        &mut self,
+        conn: &Connection,
+        as_of: Option<&str>,
+        end_date: NaiveDate,
+        months: i32,
+        calendar: &str,
+    ) -> Ten10Result<Worksheet> {
+        let default_currency = default_currency_or_ils(conn)?;
+        let balance = compute_tithe_balance(conn, as_of)?;
+        let monthly = query_monthly_summary(conn, end_date, months, calendar, MonthlySummaryBreakdowns::default())?;
+
+        let mut ws = self.new_sheet("summary")?;
+        let money = self.formats.currency(&default_currency).clone();
+        let header = &self.formats.header;
+        ws.set_column_width(0, 24)?;
+        for col in 1..4 {
+            ws.set_column_width(col, 16)?;
+        }
+
+        let balances = [
+            ("summary.tithe_balance", "Tithe balance", balance.total_balance),
+            ("summary.maaser_balance", "Maaser balance", balance.maaser_balance),
+            ("summary.chomesh_balance", "Chomesh balance", balance.chomesh_balance),
+        ];
+        for (r, (key, fallback, value)) in balances.into_iter().enumerate() {
+            ws.write_string_with_format(r as RowNum, 0, self.caption(key, fallback), header)?;
+            ws.write_number_with_format(r as RowNum, 1, value, &money)?;
+        }
+
+        let first = balances.len() as RowNum + 1;
+        let columns = [
+            ("summary.month", "Month"),
+            ("summary.income", "Income"),
+            ("summary.donations", "Donations"),
+            ("summary.expenses", "Expenses"),
+        ];
+        for (col, (key, fallback)) in columns.into_iter().enumerate() {
+            ws.write_string_with_format(first, col as ColNum, self.caption(key, fallback), header)?;
+        }
+
+        for (i, month) in monthly.iter().enumerate() {
+            let r = first + 1 + i as RowNum;
+            let label = match &month.month_name {
+                // "5784-13" -> "Adar II 5784"
+                Some(name) => format!("{} {}", name, month.month_label.split('-').next().unwrap_or_default()),
+                None => month.month_label.clone(),
+            };
+            ws.write_string(r, 0, label)?;
+            for (col, value) in [month.income, month.donations, month.expenses].into_iter().enumerate() {
+                ws.write_number_with_format(r, col as ColNum + 1, value, &money)?;
+            }
+        }
+
+        let total_row = first + 1 + monthly.len() as RowNum;
+        ws.write_string_with_format(total_row, 0, self.caption("summary.total", "Total"), header)?;
+        let bold_money = money.clone().set_bold();
+        let totals = monthly.last().map(|month| month.cumulative).unwrap_or_default();
+        for (col, total) in [totals.income, totals.donations, totals.expenses].into_iter().enumerate() {
+            ws.write_number_with_format(total_row, col as ColNum + 1, total, &bold_money)?;
+        }
+        Ok(ws)
+    }
+}
+
+/// Builds the export workbook: a summary sheet, then one sheet per transaction group
+/// with the rows matching `filters`. Returns it with the number of transactions.
+pub(crate) fn build_transactions_workbook(
+    conn: &Connection,
+    filters: &TransactionFiltersPayload,
+    options: &XlsxExportOptions,
+) -> Ten10Result<(Workbook, usize)> {
+    let calendar = options.calendar.as_deref().unwrap_or("gregorian");
+    let months = options.summary_months.unwrap_or(DEFAULT_SUMMARY_MONTHS);
+    if !(1..=MAX_SUMMARY_MONTHS).contains(&months) {
+        return Err(Ten10Error::validation(format!("Invalid summary months: {}", months)));
+    }
+    let end_date = match filters.date_to.as_deref() {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| Ten10Error::validation(format!("Error parsing dateTo '{}': {}", date, e)))?,
+        None => Local::now().date_naive(),
+    };
+
+    let mut writer = XlsxWriter {
+        columns: resolve_columns(&options.columns)?,
+        labels: Labels(&options.labels),
+        headers: &options.headers,
+        right_to_left: options.right_to_left,
+        formats: XlsxFormats::new(),
+        sheet_names: Vec::new(),
+    };
+
+    let summary = writer.summary_sheet(conn, filters.date_to.as_deref(), end_date, months, calendar)?;
+    let mut sheets = SHEET_GROUPS
+        .iter()
+        .map(|(group, _)| writer.transaction_sheet(group))
+        .collect::<Ten10Result<Vec<_>>>()?;
+    let mut other: Option<TransactionSheet> = None;
+
+    let (query, params) = build_export_query(filters);
+    let params: Vec<&dyn ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    let mut stmt = conn.prepare(&query)?;
+    let mut rows = stmt.query(params.as_slice())?;
+
+    let mut count = 0;
+    while let Some(row) = rows.next()? {
+        let row = export_row(row)?;
+        let group = SHEET_GROUPS.iter().position(|(_, types)| types.contains(&row.transaction.transaction_type.as_str()));
+        let sheet = match group {
+            Some(i) => &mut sheets[i],
+            None => match other {
+                Some(ref mut sheet) => sheet,
+                None => other.insert(writer.transaction_sheet("other")?),
+            },
+        };
+        writer.write_transaction(sheet, &row)?;
+        count += 1;
+    }
+
+    let mut workbook = Workbook::new();
+    workbook.push_worksheet(summary);
+    for sheet in sheets.into_iter().chain(other) {
+        workbook.push_worksheet(sheet.worksheet);
+    }
+    Ok((workbook, count))
+}
+
+/// Writes the transactions matching `filters` as an Excel workbook at `path`.
+/// Returns the number of transactions written.
+#[tauri::command]
+pub fn export_transactions_xlsx(
+    db_state: State<'_, DbState>,
+    filters: TransactionFiltersPayload,
+    path: String,
+    options: Option<XlsxExportOptions>,
+) -> Result<usize, Ten10Error> {
+    let options = options.unwrap_or_default();
+    let (mut workbook, count) = {
+        let conn = db_state.read()?;
+        build_transactions_workbook(&conn, &filters, &options)?
+    };
+    write_atomically(&path, |out| {
+        workbook.save_to_writer(out)?;
+        Ok(count)
+    })?;
+    println!("[Rust] export_transactions_xlsx: {} rows written to {}", count, path);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO recurring_transactions
+                (id, start_date, next_due_date, day_of_month, total_occurrences, amount, currency, type, created_at, updated_at)
+                VALUES ('r1', '2024-01-01', '2024-04-01', 1, 12, 3000, 'ILS', 'donation', '2024-01-01', '2024-01-01');
+             INSERT INTO transactions
+                (id, date, amount, currency, description, type, category, is_chomesh, payment_method, created_at)
+                VALUES ('t1', '2024-01-10', 1234550, 'ILS', 'משכורת, ינואר', 'income', 'salary', 1, 'cash', '1');
+             INSERT INTO transactions
+                (id, date, amount, currency, description, type, recipient, created_at, source_recurring_id, occurrence_number)
+                VALUES ('t2', '2024-03-01', 3000, 'ILS', 'say \"hi\"', 'donation', 'ישיבה', '2', 'r1', 3);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn filters(value: serde_json::Value) -> TransactionFiltersPayload {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn export(conn: &Connection, filters: &TransactionFiltersPayload, options: &CsvExportOptions) -> (usize, String) {
+        let mut out = Vec::new();
+        let count = write_transactions_csv(conn, filters, options, &mut out).unwrap();
+        (count, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn writes_bom_localized_headers_and_labels() {
+        let conn = test_db();
+        let options = CsvExportOptions {
+            columns: Some(["date", "type", "category", "amount", "is_chomesh", "recurring_progress"].map(String::from).to_vec()),
+            headers: HashMap::from([("date".to_string(), "תאריך".to_string())]),
+            labels: HashMap::from([
+                ("type.income".to_string(), "הכנסה".to_string()),
+                ("category.income.salary".to_string(), "משכורת".to_string()),
+                ("boolean.yes".to_string(), "כן".to_string()),
+            ]),
+            delimiter: None,
+        };
+        let (count, csv) = export(&conn, &filters(json!({})), &options);
+
+        assert_eq!(count, 2);
+        assert!(csv.starts_with('\u{FEFF}'));
+        let lines: Vec<_> = csv.trim_start_matches('\u{FEFF}').split("\r\n").collect();
+        assert_eq!(lines[0], "תאריך,type,category,amount,is_chomesh,recurring_progress");
+        assert_eq!(lines[1], "2024-03-01,donation,,30.00,,3/12");
+        assert_eq!(lines[2], "2024-01-10,הכנסה,משכורת,12345.50,כן,");
+    }
+
+    #[test]
+    fn quotes_fields_and_honours_delimiter_and_filters() {
+        let conn = test_db();
+        let options = CsvExportOptions {
+            columns: Some(vec!["description".to_string(), "amount".to_string()]),
+            delimiter: Some(";".to_string()),
+            ..Default::default()
+        };
+
+        let (_, csv) = export(&conn, &filters(json!({})), &options);
+        let lines: Vec<_> = csv.trim_start_matches('\u{FEFF}').split("\r\n").collect();
+        assert_eq!(lines[1], r#""say ""hi""";30.00"#);
+        // A comma is not special with ';' as the delimiter.
+        assert_eq!(lines[2], "משכורת, ינואר;12345.50");
+
+        let (count, _) = export(&conn, &filters(json!({ "types": ["income"] })), &options);
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn rejects_unknown_columns_and_delimiters() {
+        let conn = test_db();
+        let mut out = Vec::new();
+        let bad_column = CsvExportOptions { columns: Some(vec!["password".to_string()]), ..Default::default() };
+        assert!(matches!(
+            write_transactions_csv(&conn, &filters(json!({})), &bad_column, &mut out),
+            Err(Ten10Error::Validation(_))
+        ));
+        let bad_delimiter = CsvExportOptions { delimiter: Some("|".to_string()), ..Default::default() };
+        assert!(write_transactions_csv(&conn, &filters(json!({})), &bad_delimiter, &mut out).is_err());
+    }
+
+    fn sheet_names(workbook: &mut Workbook) -> Vec<String> {
+        workbook.worksheets().iter().map(|ws| ws.name()).collect()
+    }
+
+    #[test]
+    fn xlsx_has_a_summary_and_a_sheet_per_transaction_group() {
+        let conn = test_db();
+        let options = XlsxExportOptions {
+            labels: HashMap::from([
+                ("sheet.summary".to_string(), "סיכום".to_string()),
+                ("sheet.income".to_string(), "הכנסות".to_string()),
+            ]),
+            right_to_left: true,
+            ..Default::default()
+        };
+        let (mut workbook, count) =
+            build_transactions_workbook(&conn, &filters(json!({ "dateTo": "2024-03-31" })), &options).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(sheet_names(&mut workbook), vec!["סיכום", "הכנסות", "expenses", "donations"]);
+
+        let mut bytes = Vec::new();
+        workbook.save_to_writer(&mut bytes).unwrap();
+        assert!(bytes.starts_with(b"PK"));
+
+        // Types outside the three groups get their own sheet, only when present.
+        conn.execute_batch(
+            "INSERT INTO transactions (id, date, amount, currency, type, created_at)
+             VALUES ('t3', '2023-12-31', 50000, 'ILS', 'initial_balance', '3');",
+        )
+        .unwrap();
+        let (mut workbook, count) =
+            build_transactions_workbook(&conn, &filters(json!({})), &XlsxExportOptions::default()).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(sheet_names(&mut workbook), vec!["summary", "income", "expenses", "donations", "other"]);
+    }
+
+    #[test]
+    fn xlsx_formats_and_sheet_names() {
+        assert_eq!(currency_num_format("ILS"), "#,##0.00 \"₪\"");
+        assert_eq!(currency_num_format("JPY"), "#,##0 \"JPY\"");
+        assert_eq!(currency_num_format("KWD"), "#,##0.000 \"KWD\"");
+
+        assert_eq!(sheet_name("Income / Expenses [2024]", &[]), "Income _ Expenses _2024_");
+        assert_eq!(sheet_name("Summary", &["summary".to_string()]), "Summary (2)");
+        assert_eq!(sheet_name(&"x".repeat(40), &[]).chars().count(), 31);
+        assert_eq!(sheet_name("  ", &[]), "Sheet");
+    }
+
+    #[test]
+    fn xlsx_rejects_bad_summary_options() {
+        let conn = test_db();
+        let months = XlsxExportOptions { summary_months: Some(0), ..Default::default() };
+        assert!(matches!(
+            build_transactions_workbook(&conn, &filters(json!({})), &months),
+            Err(Ten10Error::Validation(_))
+        ));
+        let calendar = XlsxExportOptions { calendar: Some("julian".to_string()), ..Default::default() };
+        assert!(build_transactions_workbook(&conn, &filters(json!({})), &calendar).is_err());
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::models::{RecurringTransaction, Transaction};
 use crate::DbState;
+use crate::error::Ten10Error;
 use tauri::State;
 
 use super::recurring_transaction_commands::insert_recurring_transaction_row;
@@ -14,30 +15,28 @@ pub fn import_desktop_data_bulk(
     mode: String,
     recurring: Vec<RecurringTransaction>,
     transactions: Vec<Transaction>,
-) -> Result<(), String> {
+) -> Result<(), Ten10Error> {
     if mode != "replace" && mode != "merge" {
-        return Err(format!("invalid import mode: {}", mode));
+        return Err(Ten10Error::validation(format!("invalid import mode: {}", mode)));
     }
 
-    let mut conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
+    let mut conn_guard = db_state.0.lock()?;
     let conn = &mut *conn_guard;
 
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let tx = conn.transaction()?;
 
     if mode == "replace" {
-        tx.execute("DELETE FROM recurring_transactions", [])
-            .map_err(|e| e.to_string())?;
-        tx.execute("DELETE FROM transactions", [])
-            .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM recurring_transactions", [])?;
+        tx.execute("DELETE FROM transactions", [])?;
     }
 
     for rec in &recurring {
-        insert_recurring_transaction_row(&tx, rec).map_err(|e| e.to_string())?;
+        insert_recurring_transaction_row(&tx, rec)?;
     }
     for t in &transactions {
-        insert_transaction_row(&tx, t).map_err(|e| e.to_string())?;
+        insert_transaction_row(&tx, t)?;
     }
 
-    tx.commit().map_err(|e| e.to_string())?;
+    tx.commit()?;
     Ok(())
 }
@@ -3,13 +3,22 @@ use serde::Serialize;
 use tauri::State; // Import Serialize
                   // We might need to import Connection from rusqlite if it's not exposed via DbState directly in a usable way here.
                   // For now, assuming DbState and its usage pattern allows access as in main.rs
+use crate::tithe_rules::{
+    chomesh_obligation_case, maaser_obligation_case, rules_join, tithable_income_case,
+};
 use crate::transaction_types::income_types_condition;
+use crate::error::Ten10Error;
+use crate::money::major_sum_sql;
 
 // Define a struct to hold the aggregation result
 #[derive(Debug, Serialize)]
 pub struct IncomeAggregationResult {
     total_income: f64,
     chomesh_amount: f64,
+    // Evaluated through the tithe rules in force on each transaction's date
+    tithable_income: f64,
+    maaser_obligation: f64,
+    chomesh_obligation: f64,
 }
 
 #[tauri::command]
@@ -17,25 +26,34 @@ pub fn get_desktop_total_income_in_range(
     db_state: State<'_, DbState>,
     start_date: String, // Expecting "YYYY-MM-DD"
     end_date: String,   // Expecting "YYYY-MM-DD"
-) -> Result<IncomeAggregationResult, String> {
-    let conn_guard = db_state
-        .0
-        .lock()
-        .map_err(|e| format!("DB lock error: {}", e))?;
+) -> Result<IncomeAggregationResult, Ten10Error> {
+    let conn_guard = db_state.read()?;
 
     // Updated SQL query from sql_queries/sqlite/income/select_total_income.sql
     // Reflects the change to select both total_income and chomesh_amount
     // AND uses is_chomesh for SQLite.
+    // The tithe columns also need recognized expenses, so the type filter moved into CASEs.
     let sql = format!(
         "SELECT
-            COALESCE(SUM(amount), 0) AS total_income,
-            COALESCE(SUM(CASE WHEN is_chomesh THEN amount ELSE 0 END), 0) AS chomesh_amount
+            {total_income} AS total_income,
+            {chomesh_amount} AS chomesh_amount,
+            {tithable} AS tithable_income,
+            {maaser} AS maaser_obligation,
+            {chomesh} AS chomesh_obligation
         FROM transactions
+        {join}
         WHERE
-            {} AND
             date >= ?1 AND
             date <= ?2;",
-        income_types_condition()
+        total_income = major_sum_sql(&format!("CASE WHEN {} THEN amount ELSE 0 END", income_types_condition())),
+        chomesh_amount = major_sum_sql(&format!(
+            "CASE WHEN {} AND is_chomesh THEN amount ELSE 0 END",
+            income_types_condition()
+        )),
+        tithable = major_sum_sql(&tithable_income_case()),
+        maaser = major_sum_sql(&maaser_obligation_case()),
+        chomesh = major_sum_sql(&chomesh_obligation_case()),
+        join = rules_join("transactions"),
     );
 
     println!(
@@ -47,6 +65,9 @@ pub fn get_desktop_total_income_in_range(
         Ok(IncomeAggregationResult {
             total_income: row.get::<usize, f64>(0)?,
             chomesh_amount: row.get::<usize, f64>(1)?,
+            tithable_income: row.get::<usize, f64>(2)?,
+            maaser_obligation: row.get::<usize, f64>(3)?,
+            chomesh_obligation: row.get::<usize, f64>(4)?,
         })
     }) {
         Ok(result) => {
@@ -58,7 +79,7 @@ pub fn get_desktop_total_income_in_range(
         }
         Err(e) => {
             eprintln!("Desktop Query Error (income_commands.rs): {}", e);
-            Err(format!("Failed to fetch income and chomesh: {}", e))
+            Err(e.into())
         }
     }
 }
@@ -1,11 +1,22 @@
-use rusqlite::params;
+use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use crate::DbState;
+use crate::commands::category_commands::{category_depth, category_tree_ctes};
+use crate::hebrew_calendar::gregorian_range;
+use crate::tithe_rules::{
+    chomesh_obligation_case, maaser_obligation_case, rules_join, tithable_income_case,
+};
 use crate::transaction_types::{
     donation_types_condition, expense_types_condition, income_types_condition,
 };
+use crate::error::Ten10Error;
+use crate::money::major_sum_sql;
+
+// Every range command takes an optional `calendar`: "gregorian" (default) or
+// "hebrew", in which case start_date/end_date are Hebrew "YYYY-MM-DD" dates
+// (see hebrew_calendar.rs) and are converted before querying.
 
 // ─── Return types ─────────────────────────────────────────────────────────────
 
@@ -13,6 +24,9 @@ use crate::transaction_types::{
 pub struct CategoryBreakdownItem {
     pub category: String,
     pub total_amount: f64,
+    /// Whether it has subcategories to drill down into
+    #[serde(default)]
+    pub has_children: bool,
 }
 
 
@@ -34,47 +48,101 @@ pub struct DonationRecipientItem {
     pub recipient: String,
     pub total_amount: f64,
     pub last_description: Option<String>,
+    /// None for the description-based groups of donations without a recipient
+    pub recipient_id: Option<String>,
 }
 
 // ─── 1. Category Breakdown ────────────────────────────────────────────────────
 
-#[tauri::command]
-pub fn get_desktop_category_breakdown(
-    db_state: State<'_, DbState>,
-    start_date: String,
-    end_date: String,
-    transaction_type: String,
-) -> Result<Vec<CategoryBreakdownItem>, String> {
-    let type_condition = match transaction_type.as_str() {
+/// Totals per category, largest first (top 10). By default per category as
+/// stored; `level` rolls subcategories up into their ancestor at that level
+/// (0 = top-level categories), and `parent` drills down into the direct
+/// subcategories of one category (its own rows are listed under it).
+pub(crate) fn query_category_breakdown(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    transaction_type: &str,
+    level: Option<i64>,
+    parent: Option<&str>,
+) -> Result<Vec<CategoryBreakdownItem>, Ten10Error> {
+    let type_condition = match transaction_type {
         "expense"  => expense_types_condition(),
         "income"   => income_types_condition(),
         "donation" => donation_types_condition(),
-        other      => return Err(format!("Invalid transaction_type: {}", other)),
+        other      => return Err(Ten10Error::validation(format!("Invalid transaction_type: {}", other))),
     };
 
+    let level = match parent {
+        Some(parent) => Some(category_depth(conn, parent)?.ok_or_else(|| Ten10Error::not_found("Category", parent))? + 1),
+        None => level,
+    };
+    if level.is_some_and(|level| level < 0) {
+        return Err(Ten10Error::validation("Category level must not be negative."));
+    }
+
+    // A category deeper than `level` counts toward its ancestor at that level.
+    let group_expr = match level {
+        Some(_) => "COALESCE(
+                      (SELECT l.ancestor FROM category_lineage l
+                       JOIN category_depth d ON d.key = l.ancestor
+                       WHERE l.key = t.category AND d.depth = ?3),
+                      t.category, 'other')",
+        None => "COALESCE(t.category, 'other')",
+    };
+    let parent_condition = if parent.is_some() {
+        "AND t.category IN (SELECT key FROM category_lineage WHERE ancestor = ?4)"
+    } else {
+        ""
+    };
     let sql = format!(
-        "SELECT COALESCE(category, 'other') AS category, SUM(amount) AS total_amount
-         FROM transactions
-         WHERE {} AND date >= ?1 AND date <= ?2
-         GROUP BY COALESCE(category, 'other')
-         ORDER BY total_amount DESC
+        "WITH RECURSIVE {tree}
+         SELECT g.category, g.total_amount,
+                EXISTS(SELECT 1 FROM categories c WHERE c.parent_key = g.category) AS has_children
+         FROM (
+           SELECT {group_expr} AS category, {total} AS total_amount
+           FROM transactions t
+           WHERE {} AND t.date >= ?1 AND t.date <= ?2 {parent_condition}
+           GROUP BY 1
+         ) g
+         ORDER BY g.total_amount DESC
          LIMIT 10",
-        type_condition
+        type_condition,
+        tree = category_tree_ctes(),
+        total = major_sum_sql("t.amount"),
     );
 
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn_guard.prepare(&sql).map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map(params![start_date, end_date], |row| {
-            Ok(CategoryBreakdownItem {
-                category: row.get(0)?,
-                total_amount: row.get(1)?,
-            })
+    let mut stmt = conn.prepare(&sql)?;
+    let row_to_item = |row: &rusqlite::Row<'_>| {
+        Ok(CategoryBreakdownItem {
+            category: row.get(0)?,
+            total_amount: row.get(1)?,
+            has_children: row.get(2)?,
         })
-        .map_err(|e| e.to_string())?;
+    };
+    let rows = match (level, parent) {
+        (Some(level), Some(parent)) => stmt.query_map(params![start_date, end_date, level, parent], row_to_item)?,
+        (Some(level), None) => stmt.query_map(params![start_date, end_date, level], row_to_item)?,
+        _ => stmt.query_map(params![start_date, end_date], row_to_item)?,
+    };
 
     rows.collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())
+        .map_err(Ten10Error::from)
+}
+
+#[tauri::command]
+pub fn get_desktop_category_breakdown(
+    db_state: State<'_, DbState>,
+    start_date: String,
+    end_date: String,
+    transaction_type: String,
+    calendar: Option<String>,
+    level: Option<i64>,
+    parent: Option<String>,
+) -> Result<Vec<CategoryBreakdownItem>, Ten10Error> {
+    let (start_date, end_date) = gregorian_range(calendar.as_deref(), &start_date, &end_date)?;
+    let conn_guard = db_state.read()?;
+    query_category_breakdown(&conn_guard, &start_date, &end_date, &transaction_type, level, parent.as_deref())
 }
 
 // ─── 3. Payment Method Breakdown ──────────────────────────────────────────────
@@ -84,31 +152,33 @@ pub fn get_desktop_payment_method_breakdown(
     db_state: State<'_, DbState>,
     start_date: String,
     end_date: String,
-) -> Result<Vec<PaymentMethodBreakdownItem>, String> {
+    calendar: Option<String>,
+) -> Result<Vec<PaymentMethodBreakdownItem>, Ten10Error> {
+    let (start_date, end_date) = gregorian_range(calendar.as_deref(), &start_date, &end_date)?;
     let expense_cond = expense_types_condition();
     let sql = format!(
-        "SELECT COALESCE(payment_method, 'other') AS payment_method, SUM(amount) AS total_amount
+        "SELECT COALESCE(payment_method, 'other') AS payment_method, {total} AS total_amount
          FROM transactions
          WHERE {} AND date >= ?1 AND date <= ?2
          GROUP BY COALESCE(payment_method, 'other')
          ORDER BY total_amount DESC
          LIMIT 20",
-        expense_cond
+        expense_cond,
+        total = major_sum_sql("amount"),
     );
 
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn_guard.prepare(&sql).map_err(|e| e.to_string())?;
+    let conn_guard = db_state.read()?;
+    let mut stmt = conn_guard.prepare(&sql)?;
     let rows = stmt
         .query_map(params![start_date, end_date], |row| {
             Ok(PaymentMethodBreakdownItem {
                 payment_method: row.get(0)?,
                 total_amount: row.get(1)?,
             })
-        })
-        .map_err(|e| e.to_string())?;
+        })?;
 
     rows.collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())
+        .map_err(Ten10Error::from)
 }
 
 // ─── 4. Recurring vs One-Time ─────────────────────────────────────────────────
@@ -118,17 +188,21 @@ pub fn get_desktop_recurring_vs_onetime(
     db_state: State<'_, DbState>,
     start_date: String,
     end_date: String,
-) -> Result<Vec<RecurringVsOnetimeItem>, String> {
-    let sql =
+    calendar: Option<String>,
+) -> Result<Vec<RecurringVsOnetimeItem>, Ten10Error> {
+    let (start_date, end_date) = gregorian_range(calendar.as_deref(), &start_date, &end_date)?;
+    let sql = format!(
         "SELECT (source_recurring_id IS NOT NULL) AS is_recurring,
-                SUM(amount) AS total_amount,
+                {total} AS total_amount,
                 COUNT(*) AS tx_count
          FROM transactions
          WHERE type != 'initial_balance' AND date >= ?1 AND date <= ?2
-         GROUP BY (source_recurring_id IS NOT NULL)";
+         GROUP BY (source_recurring_id IS NOT NULL)",
+        total = major_sum_sql("amount"),
+    );
 
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn_guard.prepare(sql).map_err(|e| e.to_string())?;
+    let conn_guard = db_state.read()?;
+    let mut stmt = conn_guard.prepare(&sql)?;
     let rows = stmt
         .query_map(params![start_date, end_date], |row| {
             let is_rec: i32 = row.get(0)?;
@@ -137,11 +211,10 @@ pub fn get_desktop_recurring_vs_onetime(
                 total_amount: row.get(1)?,
                 tx_count: row.get(2)?,
             })
-        })
-        .map_err(|e| e.to_string())?;
+        })?;
 
     rows.collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())
+        .map_err(Ten10Error::from)
 }
 
 // ─── 6. Daily Transaction Heatmap ────────────────────────────────────────────
@@ -159,7 +232,9 @@ pub fn get_desktop_daily_heatmap(
     start_date: String,
     end_date: String,
     type_group: Option<String>,
-) -> Result<Vec<DailyHeatmapItem>, String> {
+    calendar: Option<String>,
+) -> Result<Vec<DailyHeatmapItem>, Ten10Error> {
+    let (start_date, end_date) = gregorian_range(calendar.as_deref(), &start_date, &end_date)?;
     let group = type_group.as_deref().unwrap_or("all");
     let type_filter = match group {
         "income"   => format!(" AND {}", income_types_condition()),
@@ -168,16 +243,17 @@ pub fn get_desktop_daily_heatmap(
         _          => String::new(), // "all" — no extra filter
     };
     let sql = format!(
-        "SELECT date AS tx_date, COUNT(*) AS tx_count, SUM(amount) AS total_amount
+        "SELECT date AS tx_date, COUNT(*) AS tx_count, {total} AS total_amount
          FROM transactions
          WHERE type != 'initial_balance' AND date >= ?1 AND date <= ?2{}
          GROUP BY date
          ORDER BY date",
-        type_filter
+        type_filter,
+        total = major_sum_sql("amount"),
     );
 
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn_guard.prepare(&sql).map_err(|e| e.to_string())?;
+    let conn_guard = db_state.read()?;
+    let mut stmt = conn_guard.prepare(&sql)?;
     let rows = stmt
         .query_map(rusqlite::params![start_date, end_date], |row| {
             Ok(DailyHeatmapItem {
@@ -185,11 +261,10 @@ pub fn get_desktop_daily_heatmap(
                 tx_count: row.get::<usize, i64>(1)? as i32,
                 total_amount: row.get(2)?,
             })
-        })
-        .map_err(|e| e.to_string())?;
+        })?;
 
     rows.collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())
+        .map_err(Ten10Error::from)
 }
 
 // ─── 7. Combined breakdowns bundle ────────────────────────────────────────────
@@ -208,41 +283,44 @@ pub fn get_desktop_analytics_breakdowns(
     db_state: State<'_, DbState>,
     start_date: String,
     end_date: String,
-) -> Result<AnalyticsBreakdownsBundle, String> {
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
+    calendar: Option<String>,
+) -> Result<AnalyticsBreakdownsBundle, Ten10Error> {
+    let (start_date, end_date) = gregorian_range(calendar.as_deref(), &start_date, &end_date)?;
+    let conn_guard = db_state.read()?;
     let expense_cond = expense_types_condition();
 
     // Payment methods (expenses only)
     let pm_sql = format!(
-        "SELECT COALESCE(payment_method, 'other') AS payment_method, SUM(amount) AS total_amount
+        "SELECT COALESCE(payment_method, 'other') AS payment_method, {total} AS total_amount
          FROM transactions
          WHERE {} AND date >= ?1 AND date <= ?2
          GROUP BY COALESCE(payment_method, 'other')
          ORDER BY total_amount DESC
          LIMIT 20",
-        expense_cond
+        expense_cond,
+        total = major_sum_sql("amount"),
     );
-    let mut pm_stmt = conn_guard.prepare(&pm_sql).map_err(|e| e.to_string())?;
+    let mut pm_stmt = conn_guard.prepare(&pm_sql)?;
     let payment_methods: Vec<PaymentMethodBreakdownItem> = pm_stmt
         .query_map(params![start_date, end_date], |row| {
             Ok(PaymentMethodBreakdownItem {
                 payment_method: row.get(0)?,
                 total_amount: row.get(1)?,
             })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<_, _>>()
-        .map_err(|e| e.to_string())?;
+        })?
+        .collect::<Result<_, _>>()?;
 
     // Recurring vs one-time
-    let rvo_sql =
+    let rvo_sql = format!(
         "SELECT (source_recurring_id IS NOT NULL) AS is_recurring,
-                SUM(amount) AS total_amount,
+                {total} AS total_amount,
                 COUNT(*) AS tx_count
          FROM transactions
          WHERE type != 'initial_balance' AND date >= ?1 AND date <= ?2
-         GROUP BY (source_recurring_id IS NOT NULL)";
-    let mut rvo_stmt = conn_guard.prepare(rvo_sql).map_err(|e| e.to_string())?;
+         GROUP BY (source_recurring_id IS NOT NULL)",
+        total = major_sum_sql("amount"),
+    );
+    let mut rvo_stmt = conn_guard.prepare(&rvo_sql)?;
     let recurring_vs_onetime: Vec<RecurringVsOnetimeItem> = rvo_stmt
         .query_map(params![start_date, end_date], |row| {
             let is_rec: i32 = row.get(0)?;
@@ -251,44 +329,14 @@ pub fn get_desktop_analytics_breakdowns(
                 total_amount: row.get(1)?,
                 tx_count: row.get(2)?,
             })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<_, _>>()
-        .map_err(|e| e.to_string())?;
+        })?
+        .collect::<Result<_, _>>()?;
 
     // Donation recipients
-    let rec_sql = format!(
-        "SELECT display_key AS recipient, total_amount, display_key AS last_description
-         FROM (
-           SELECT
-             COALESCE(NULLIF(TRIM(COALESCE(description,'')), ''),
-                      NULLIF(TRIM(COALESCE(recipient,'')), ''),
-                      'other') AS display_key,
-             SUM(amount) AS total_amount
-           FROM transactions
-           WHERE {}
-             AND date >= ?1 AND date <= ?2
-           GROUP BY COALESCE(NULLIF(TRIM(COALESCE(description,'')), ''),
-                              NULLIF(TRIM(COALESCE(recipient,'')), ''),
-                              'other')
-           ORDER BY total_amount DESC
-           LIMIT 50
-         )
-         ORDER BY total_amount DESC",
-        donation_types_condition(),
-    );
-    let mut rec_stmt = conn_guard.prepare(&rec_sql).map_err(|e| e.to_string())?;
+    let mut rec_stmt = conn_guard.prepare(&donation_recipients_sql())?;
     let recipients: Vec<DonationRecipientItem> = rec_stmt
-        .query_map(params![start_date, end_date], |row| {
-            Ok(DonationRecipientItem {
-                recipient: row.get(0)?,
-                total_amount: row.get(1)?,
-                last_description: row.get(2)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<_, _>>()
-        .map_err(|e| e.to_string())?;
+        .query_map(params![start_date, end_date], donation_recipient_item)?
+        .collect::<Result<_, _>>()?;
 
     Ok(AnalyticsBreakdownsBundle { payment_methods, recurring_vs_onetime, recipients })
 }
@@ -303,6 +351,10 @@ pub struct AnalyticsRangeStats {
     pub total_expenses: f64,
     pub total_donations: f64,
     pub non_tithe_donation_amount: f64,
+    // Evaluated through the tithe rules in force on each transaction's date
+    pub tithable_income: f64,
+    pub maaser_obligation: f64,
+    pub chomesh_obligation: f64,
 }
 
 #[tauri::command]
@@ -310,22 +362,34 @@ pub fn get_desktop_analytics_range_stats(
     db_state: State<'_, DbState>,
     start_date: String,
     end_date: String,
-) -> Result<AnalyticsRangeStats, String> {
+    calendar: Option<String>,
+) -> Result<AnalyticsRangeStats, Ten10Error> {
+    let (start_date, end_date) = gregorian_range(calendar.as_deref(), &start_date, &end_date)?;
     let sql = format!(
         "SELECT
-           COALESCE(SUM(CASE WHEN ({income}) THEN amount ELSE 0 END), 0) AS total_income,
-           COALESCE(SUM(CASE WHEN is_chomesh THEN amount ELSE 0 END),  0) AS chomesh_amount,
-           COALESCE(SUM(CASE WHEN ({expense}) THEN amount ELSE 0 END), 0) AS total_expenses,
-           COALESCE(SUM(CASE WHEN ({donation}) THEN amount ELSE 0 END), 0) AS total_donations,
-           COALESCE(SUM(CASE WHEN type = 'non_tithe_donation' THEN amount ELSE 0 END), 0) AS non_tithe_donation_amount
+           {total_income} AS total_income,
+           {chomesh_amount} AS chomesh_amount,
+           {total_expenses} AS total_expenses,
+           {total_donations} AS total_donations,
+           {non_tithe} AS non_tithe_donation_amount,
+           {tithable} AS tithable_income,
+           {maaser} AS maaser_obligation,
+           {chomesh} AS chomesh_obligation
          FROM transactions
+         {join}
          WHERE date >= ?1 AND date <= ?2",
-        income = income_types_condition().trim_matches(|c| c == '(' || c == ')'),
-        expense = expense_types_condition().trim_matches(|c| c == '(' || c == ')'),
-        donation = donation_types_condition().trim_matches(|c| c == '(' || c == ')')
+        total_income = major_sum_sql(&format!("CASE WHEN {} THEN amount ELSE 0 END", income_types_condition())),
+        chomesh_amount = major_sum_sql("CASE WHEN is_chomesh THEN amount ELSE 0 END"),
+        total_expenses = major_sum_sql(&format!("CASE WHEN {} THEN amount ELSE 0 END", expense_types_condition())),
+        total_donations = major_sum_sql(&format!("CASE WHEN {} THEN amount ELSE 0 END", donation_types_condition())),
+        non_tithe = major_sum_sql("CASE WHEN type = 'non_tithe_donation' THEN amount ELSE 0 END"),
+        tithable = major_sum_sql(&tithable_income_case()),
+        maaser = major_sum_sql(&maaser_obligation_case()),
+        chomesh = major_sum_sql(&chomesh_obligation_case()),
+        join = rules_join("transactions"),
     );
 
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
+    let conn_guard = db_state.read()?;
     conn_guard
         .query_row(&sql, params![start_date, end_date], |row| {
             Ok(AnalyticsRangeStats {
@@ -334,57 +398,107 @@ pub fn get_desktop_analytics_range_stats(
                 total_expenses:            row.get(2)?,
                 total_donations:           row.get(3)?,
                 non_tithe_donation_amount: row.get(4)?,
+                tithable_income:           row.get(5)?,
+                maaser_obligation:         row.get(6)?,
+                chomesh_obligation:        row.get(7)?,
             })
         })
-        .map_err(|e| e.to_string())
+        .map_err(Ten10Error::from)
 }
 
 // ─── 5. Donation Recipients Breakdown ─────────────────────────────────────────
 
-#[tauri::command]
-pub fn get_desktop_donation_recipients_breakdown(
-    db_state: State<'_, DbState>,
-    start_date: String,
-    end_date: String,
-) -> Result<Vec<DonationRecipientItem>, String> {
-    // Group by COALESCE(description, recipient, 'other') — uses description first.
-    // Order by SUM(amount) DESC (largest first), LIMIT 50.
-    let sql = format!(
-        "SELECT
-           display_key AS recipient,
-           total_amount,
-           display_key AS last_description
+/// Donation totals per recipient (its canonical name) in `?1..?2`, largest first,
+/// at most 50. Donations without a recipient are grouped by description.
+fn donation_recipients_sql() -> String {
+    format!(
+        "SELECT COALESCE(r.name, g.display_key) AS recipient, g.total_amount, g.last_description, g.recipient_id
          FROM (
            SELECT
-             COALESCE(NULLIF(TRIM(COALESCE(description,'')), ''),
-                      NULLIF(TRIM(COALESCE(recipient,'')), ''),
-                      'other') AS display_key,
-             SUM(amount) AS total_amount
+             recipient_id,
+             COALESCE(NULLIF(TRIM(COALESCE(description,'')), ''), 'other') AS display_key,
+             {total} AS total_amount,
+             MAX(date) AS last_date,
+             description AS last_description
            FROM transactions
            WHERE {}
              AND date >= ?1 AND date <= ?2
-           GROUP BY COALESCE(NULLIF(TRIM(COALESCE(description,'')), ''),
-                              NULLIF(TRIM(COALESCE(recipient,'')), ''),
-                              'other')
+           GROUP BY COALESCE(recipient_id,
+                             'description:' || COALESCE(NULLIF(TRIM(COALESCE(description,'')), ''), 'other'))
            ORDER BY total_amount DESC
            LIMIT 50
-         )
-         ORDER BY total_amount DESC",
+         ) g
+         LEFT JOIN recipients r ON r.id = g.recipient_id
+         ORDER BY g.total_amount DESC",
         donation_types_condition(),
-    );
+        total = major_sum_sql("amount"),
+    )
+}
 
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn_guard.prepare(&sql).map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map(params![start_date, end_date], |row| {
-            Ok(DonationRecipientItem {
-                recipient: row.get(0)?,
-                total_amount: row.get(1)?,
-                last_description: row.get(2)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
+fn donation_recipient_item(row: &rusqlite::Row<'_>) -> rusqlite::Result<DonationRecipientItem> {
+    Ok(DonationRecipientItem {
+        recipient: row.get(0)?,
+        total_amount: row.get(1)?,
+        last_description: row.get(2)?,
+        recipient_id: row.get(3)?,
+    })
+}
+
+#[tauri::command]
+pub fn get_desktop_donation_recipients_breakdown(
+    db_state: State<'_, DbState>,
+    start_date: String,
+    end_date: String,
+    calendar: Option<String>,
+) -> Result<Vec<DonationRecipientItem>, Ten10Error> {
+    let (start_date, end_date) = gregorian_range(calendar.as_deref(), &start_date, &end_date)?;
+    let conn_guard = db_state.read()?;
+    let mut stmt = conn_guard.prepare(&donation_recipients_sql())?;
+    let rows = stmt.query_map(params![start_date, end_date], donation_recipient_item)?;
 
     rows.collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())
+        .map_err(Ten10Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn category_breakdown_rolls_up_and_drills_down() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO categories (key, parent_key, sort_order, created_at, updated_at) VALUES
+                ('rent', 'housing', 0, '0', '0'),
+                ('arnona', 'housing', 1, '0', '0'),
+                ('arnona north', 'arnona', 0, '0', '0');
+            INSERT INTO transactions (id, date, amount, currency, type, category) VALUES
+                ('t1', '2024-01-01', 300000, 'ILS', 'expense', 'rent'),
+                ('t2', '2024-01-02', 50000, 'ILS', 'expense', 'arnona'),
+                ('t3', '2024-01-03', 20000, 'ILS', 'recognized-expense', 'arnona north'),
+                ('t4', '2024-01-04', 10000, 'ILS', 'expense', 'housing'),
+                ('t5', '2024-01-05', 40000, 'ILS', 'expense', 'food'),
+                ('t6', '2024-01-06', 5000, 'ILS', 'expense', 'Pets');",
+        )
+        .unwrap();
+        let breakdown = |level: Option<i64>, parent: Option<&str>| -> Vec<(String, f64, bool)> {
+            query_category_breakdown(&conn, "2024-01-01", "2024-12-31", "expense", level, parent)
+                .unwrap()
+                .into_iter()
+                .map(|i| (i.category, i.total_amount, i.has_children))
+                .collect()
+        };
+
+        assert_eq!(breakdown(None, None)[0], ("rent".to_string(), 3000.0, false));
+        assert_eq!(
+            breakdown(Some(0), None),
+            vec![("housing".to_string(), 3800.0, true), ("food".to_string(), 400.0, false), ("Pets".to_string(), 50.0, false)]
+        );
+        assert_eq!(
+            breakdown(None, Some("housing")),
+            vec![("rent".to_string(), 3000.0, false), ("arnona".to_string(), 700.0, true), ("housing".to_string(), 100.0, true)]
+        );
+        assert!(query_category_breakdown(&conn, "2024-01-01", "2024-12-31", "expense", None, Some("nope")).is_err());
+    }
 }
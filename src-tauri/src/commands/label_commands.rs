@@ -0,0 +1,328 @@
+// src-tauri/src/commands/label_commands.rs
+// Renaming and merging user-typed categories and payment methods across all
+// history: every transaction and recurring definition carrying one of the
+// selected values gets the new one. A change is previewed first (row counts per
+// value), then applied in a single transaction.
+//
+// Values are matched exactly as stored, i.e. as `get_distinct_categories` and
+// `get_distinct_payment_methods` list them, so near-duplicates like
+// "supermarket " are merged by selecting them alongside "Supermarket".
+
+use chrono::Utc;
+use rusqlite::{params_from_iter, Connection, OptionalExtension, ToSql};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use super::category_commands::rename_category_key;
+use crate::error::{Ten10Error, Ten10Result};
+use crate::transaction_types::category_type_group;
+use crate::DbState;
+
+/// Tables whose rows carry the labels.
+const LABELED_TABLES: [&str; 2] = ["transactions", "recurring_transactions"];
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelField {
+    Category,
+    PaymentMethod,
+}
+
+impl LabelField {
+    fn column(self) -> &'static str {
+        match self {
+            LabelField::Category => "category",
+            LabelField::PaymentMethod => "payment_method",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LabelChangePayload {
+    pub field: LabelField,
+    /// The stored values to replace; one value is a rename, several a merge.
+    pub from: Vec<String>,
+    pub to: String,
+    /// Limits the change to a type group ("income", "expense", "donation"
+    /// include their derived types), as categories are listed per type.
+    #[serde(default)]
+    pub transaction_type: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelChangeCounts {
+    pub transactions: usize,
+    pub recurring_transactions: usize,
+}
+
+impl LabelChangeCounts {
+    fn add(&mut self, table: &str, count: usize) {
+        match table {
+            "transactions" => self.transactions += count,
+            _ => self.recurring_transactions += count,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LabelValueCounts {
+    pub value: String,
+    pub counts: LabelChangeCounts,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LabelChangePreview {
+    pub field: LabelField,
+    pub to: String,
+    /// Every selected value with the rows it is on, including unused ones
+    pub values: Vec<LabelValueCounts>,
+    pub total: LabelChangeCounts,
+    /// Whether `to` is already in use, i.e. the change merges into it
+    pub merges_into_existing: bool,
+}
+
+/// A validated change: `to` trimmed, `from` deduplicated and without `to` itself.
+struct LabelChange {
+    field: LabelField,
+    from: Vec<String>,
+    to: String,
+    types: Option<Vec<String>>,
+}
+
+impl LabelChange {
+    fn new(payload: LabelChangePayload) -> Ten10Result<Self> {
+        let to = payload.to.trim().to_string();
+        if to.is_empty() {
+            return Err(Ten10Error::validation("The new name must not be empty."));
+        }
+        let mut from: Vec<String> = Vec::new();
+        for value in payload.from {
+            if value != to && !from.contains(&value) {
+                from.push(value);
+            }
+        }
+        if from.is_empty() {
+            return Err(Ten10Error::validation("Select at least one value to rename or merge."));
+        }
+        let types = payload
+            .transaction_type
+            .filter(|t| !t.is_empty())
+            .map(|t| category_type_group(&t).into_iter().map(str::to_string).collect());
+        Ok(Self { field: payload.field, from, to, types })
+    }
+
+    /// `WHERE` condition matching `values` (numbered from `?first`) within the
+    /// type group, with its parameters.
+    fn condition(&self, values: &[String], first: usize) -> (String, Vec<String>) {
+        let mut params: Vec<String> = values.to_vec();
+        let placeholders = |start: usize, len: usize| {
+            (start..start + len).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", ")
+        };
+        let mut sql = format!("{} IN ({})", self.field.column(), placeholders(first, values.len()));
+        if let Some(types) = &self.types {
+            sql.push_str(&format!(" AND type IN ({})", placeholders(first + values.len(), types.len())));
+            params.extend(types.iter().cloned());
+        }
+        (sql, params)
+    }
+}
+
+fn preview(conn: &Connection, change: &LabelChange) -> Ten10Result<LabelChangePreview> {
+    let mut values: Vec<LabelValueCounts> = change
+        .from
+        .iter()
+        .map(|value| LabelValueCounts { value: value.clone(), counts: LabelChangeCounts::default() })
+        .collect();
+    let mut total = LabelChangeCounts::default();
+    let mut merges_into_existing = false;
+
+    for table in LABELED_TABLES {
+        let column = change.field.column();
+        let (condition, params) = change.condition(&change.from, 1);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {column}, COUNT(*) FROM {table} WHERE {condition} GROUP BY {column}"
+        ))?;
+        let rows = stmt.query_map(params_from_iter(params.iter()), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+        })?;
+        for row in rows {
+            let (value, count) = row?;
+            if let Some(entry) = values.iter_mut().find(|v| v.value == value) {
+                entry.counts.add(table, count);
+            }
+            total.add(table, count);
+        }
+
+        let (condition, params) = change.condition(std::slice::from_ref(&change.to), 1);
+        let existing: Option<i64> = conn
+            .query_row(
+                &format!("SELECT 1 FROM {table} WHERE {condition} LIMIT 1"),
+                params_from_iter(params.iter()),
+                |row| row.get(0),
+            )
+            .optional()?;
+        merges_into_existing |= existing.is_some();
+    }
+
+    Ok(LabelChangePreview {
+        field: change.field,
+        to: change.to.clone(),
+        values,
+        total,
+        merges_into_existing,
+    })
+}
+
+/// Sets the field to `to` on the rows of `table` carrying one of the `from` values.
+fn relabel_rows(conn: &Connection, change: &LabelChange, table: &str, now: &str) -> Ten10Result<usize> {
+    let (condition, params) = change.condition(&change.from, 3);
+    let mut all_params: Vec<&dyn ToSql> = vec![&change.to, &now];
+    all_params.extend(params.iter().map(|p| p as &dyn ToSql));
+    Ok(conn.execute(
+        &format!("UPDATE {table} SET {} = ?1, updated_at = ?2 WHERE {condition}", change.field.column()),
+        all_params.as_slice(),
+    )?)
+}
+
+fn apply(conn: &Connection, change: &LabelChange) -> Ten10Result<LabelChangeCounts> {
+    let mut counts = LabelChangeCounts::default();
+    let now = Utc::now().to_rfc3339();
+    for table in LABELED_TABLES {
+        counts.add(table, relabel_rows(conn, change, table, &now)?);
+    }
+    // The category tree and the budgets follow a value everywhere it is renamed;
+    // a change limited to one type group leaves them, as the value may still be
+    // used by other types.
+    if change.types.is_none() {
+        relabel_rows(conn, change, "budgets", &now)?;
+        if change.field == LabelField::Category {
+            for from in &change.from {
+                rename_category_key(conn, from, &change.to)?;
+            }
+        }
+    }
+    Ok(counts)
+}
+
+/// The rows a rename or merge would change, per selected value.
+#[tauri::command]
+pub fn preview_label_change(
+    db_state: State<'_, DbState>,
+    change: LabelChangePayload,
+) -> Result<LabelChangePreview, Ten10Error> {
+    let change = LabelChange::new(change)?;
+    let conn = db_state.read()?;
+    preview(&conn, &change)
+}
+
+/// Renames or merges the selected values into `to` on every transaction and
+/// recurring definition, all or nothing. Returns the changed row counts.
+#[tauri::command]
+pub fn apply_label_change(
+    db_state: State<'_, DbState>,
+    change: LabelChangePayload,
+) -> Result<LabelChangeCounts, Ten10Error> {
+    let change = LabelChange::new(change)?;
+    let mut conn = db_state.0.lock()?;
+    let tx = conn.transaction()?;
+    let counts = apply(&tx, &change)?;
+    tx.commit()?;
+    println!(
+        "[Rust DEBUG] apply_label_change: {:?} {:?} -> '{}' on {} transactions, {} recurring",
+        change.field, change.from, change.to, counts.transactions, counts.recurring_transactions
+    );
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO transactions (id, date, amount, currency, type, category, payment_method) VALUES
+                ('t1', '2024-01-01', 100, 'ILS', 'expense', 'Supermarket', 'cash'),
+                ('t2', '2024-01-02', 100, 'ILS', 'recognized-expense', 'supermarket ', 'Visa'),
+                ('t3', '2024-01-03', 100, 'ILS', 'expense', 'סופר', 'visa'),
+                ('t4', '2024-01-04', 100, 'ILS', 'income', 'supermarket ', NULL);
+            INSERT INTO recurring_transactions (id, start_date, next_due_date, day_of_month, amount, currency, type, category, payment_method, created_at, updated_at) VALUES
+                ('r1', '2024-01-01', '2024-02-01', 1, 100, 'ILS', 'expense', 'סופר', 'visa', '0', '0');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn column(conn: &Connection, table: &str, column: &str) -> Vec<Option<String>> {
+        conn.prepare(&format!("SELECT {column} FROM {table} ORDER BY id"))
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn change(field: LabelField, from: &[&str], to: &str, transaction_type: Option<&str>) -> LabelChange {
+        LabelChange::new(LabelChangePayload {
+            field,
+            from: from.iter().map(|s| s.to_string()).collect(),
+            to: to.to_string(),
+            transaction_type: transaction_type.map(str::to_string),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn merges_categories_within_the_type_group() {
+        let conn = test_db();
+        let merge = change(LabelField::Category, &["supermarket ", "סופר", "Supermarket"], " Supermarket ", Some("expense"));
+        assert_eq!(merge.from, vec!["supermarket ", "סופר"]);
+
+        let preview = preview(&conn, &merge).unwrap();
+        let per_value: Vec<_> = preview
+            .values
+            .iter()
+            .map(|v| (v.value.as_str(), v.counts.transactions, v.counts.recurring_transactions))
+            .collect();
+        assert_eq!(per_value, vec![("supermarket ", 1, 0), ("סופר", 1, 1)]);
+        assert_eq!(preview.total, LabelChangeCounts { transactions: 2, recurring_transactions: 1 });
+        assert!(preview.merges_into_existing);
+
+        let counts = apply(&conn, &merge).unwrap();
+        assert_eq!(counts, preview.total);
+        assert_eq!(
+            column(&conn, "transactions", "category"),
+            vec![Some("Supermarket".into()), Some("Supermarket".into()), Some("Supermarket".into()), Some("supermarket ".into())]
+        );
+        assert_eq!(column(&conn, "recurring_transactions", "category"), vec![Some("Supermarket".into())]);
+    }
+
+    #[test]
+    fn renames_payment_methods_everywhere_and_validates() {
+        let conn = test_db();
+        let rename = change(LabelField::PaymentMethod, &["visa", "Visa"], "Credit card", None);
+        let preview = preview(&conn, &rename).unwrap();
+        assert!(!preview.merges_into_existing);
+
+        let counts = apply(&conn, &rename).unwrap();
+        assert_eq!(counts, LabelChangeCounts { transactions: 2, recurring_transactions: 1 });
+        assert_eq!(
+            column(&conn, "transactions", "payment_method"),
+            vec![Some("cash".into()), Some("Credit card".into()), Some("Credit card".into()), None]
+        );
+
+        let invalid = |from: &[&str], to: &str| {
+            LabelChange::new(LabelChangePayload {
+                field: LabelField::Category,
+                from: from.iter().map(|s| s.to_string()).collect(),
+                to: to.to_string(),
+                transaction_type: None,
+            })
+            .is_err()
+        };
+        assert!(invalid(&["a"], "  "));
+        assert!(invalid(&["b"], "b"));
+        assert!(invalid(&[], "b"));
+    }
+}
@@ -8,3 +8,14 @@ pub mod recurring_transaction_commands;
 pub mod platform_commands;
 pub mod db_commands;
 pub mod insights_commands;
+pub mod tithe_rules_commands;
+pub mod calendar_commands;
+pub mod export_commands;
+pub mod tax_report_commands;
+pub mod recipient_commands;
+pub mod label_commands;
+pub mod category_commands;
+pub mod budget_commands;
+pub mod pledge_commands;
+pub mod exchange_rate_commands;
+pub mod default_currency_commands;
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::error::Ten10Error;
 
 #[derive(Serialize, Deserialize)]
 pub struct PlatformInfo {
@@ -24,9 +25,9 @@ pub struct PlatformInfo {
 ///
 /// # Errors
 ///
-/// Returns an error string if platform information cannot be retrieved.
+/// Returns a `Ten10Error` if platform information cannot be retrieved.
 #[tauri::command]
-pub async fn get_platform_info(app: tauri::AppHandle) -> Result<PlatformInfo, String> {
+pub async fn get_platform_info(app: tauri::AppHandle) -> Result<PlatformInfo, Ten10Error> {
     let app_version = app.package_info().version.to_string();
     let os = tauri_plugin_os::platform().to_string();
     
@@ -45,7 +46,9 @@ pub async fn get_platform_info(app: tauri::AppHandle) -> Result<PlatformInfo, St
 }
 
 #[tauri::command]
-pub async fn copy_to_clipboard(app: tauri::AppHandle, text: String) -> Result<(), String> {
+pub async fn copy_to_clipboard(app: tauri::AppHandle, text: String) -> Result<(), Ten10Error> {
     use tauri_plugin_clipboard_manager::ClipboardExt;
-    app.clipboard().write_text(text).map_err(|e| e.to_string())
+    app.clipboard()
+        .write_text(text)
+        .map_err(|e| Ten10Error::Io(std::io::Error::other(e.to_string())))
 }
@@ -0,0 +1,470 @@
+// src-tauri/src/commands/pledge_commands.rs
+// Pledges: a promise to donate a fixed amount to a recipient by a deadline,
+// e.g. 5,000 NIS a year paid in installments.
+//
+// A pledge is paid by donations, in its own currency: the ones linked to it
+// explicitly and the ones generated by its payment plan (a recurring donation
+// created from the pledge). Progress is measured against a straight line from
+// the start date to the deadline.
+
+use chrono::{Datelike, Local, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use super::db_commands::default_currency_or_ils;
+use super::recipient_commands::resolve_recipient_id;
+use super::recurring_transaction_commands::{advance_due_date, insert_recurring_transaction_row};
+use crate::error::{Ten10Error, Ten10Result};
+use crate::models::{Pledge, RecurringTransaction};
+use crate::money::Money;
+use crate::transaction_types::DONATION_TYPES;
+use crate::DbState;
+
+const PLEDGE_SELECT: &str =
+    "SELECT p.*, r.name AS recipient FROM pledges p JOIN recipients r ON r.id = p.recipient_id";
+
+#[derive(Deserialize, Debug)]
+pub struct PledgePayload {
+    /// Recipient name or alias; an unknown name creates the recipient
+    pub recipient: String,
+    pub amount: Money,
+    /// Defaults to the default currency
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// Defaults to today
+    #[serde(default)]
+    pub start_date: Option<String>,
+    pub deadline: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PledgeProgress {
+    pub pledge: Pledge,
+    pub as_of: String,
+    pub paid: Money,
+    pub payment_count: i64,
+    pub remaining: Money,
+    /// What a steady pace from `start_date` to `deadline` would have paid by `as_of`
+    pub expected_paid: Money,
+    /// `paid` minus `expected_paid`; negative when behind schedule
+    pub schedule_difference: Money,
+    pub on_schedule: bool,
+    /// "active", "fulfilled", "overdue" or "cancelled"
+    pub status: String,
+}
+
+fn parse_date(value: &str) -> Ten10Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| Ten10Error::validation(format!("Invalid date: {}", value)))
+}
+
+fn load_pledge(conn: &Connection, id: &str) -> Ten10Result<Pledge> {
+    conn.query_row(&format!("{} WHERE p.id = ?1", PLEDGE_SELECT), params![id], Pledge::from_row)
+        .optional()?
+        .ok_or_else(|| Ten10Error::not_found("Pledge", id))
+}
+
+/// Total (in the pledge currency) and count of the payments made on `pledge` up to `as_of`.
+fn paid_minor(conn: &Connection, pledge: &Pledge, as_of: NaiveDate) -> RusqliteResult<(i64, i64)> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(COALESCE(original_amount, amount)), 0), COUNT(*)
+         FROM transactions
+         WHERE (id IN (SELECT transaction_id FROM pledge_payments WHERE pledge_id = ?1)
+                OR (?2 IS NOT NULL AND source_recurring_id = ?2))
+           AND date <= ?3",
+        params![pledge.id, pledge.recurring_id, as_of.to_string()],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+}
+
+pub(crate) fn pledge_progress(conn: &Connection, pledge: Pledge, as_of: NaiveDate) -> Ten10Result<PledgeProgress> {
+    let (start, deadline) = (parse_date(&pledge.start_date)?, parse_date(&pledge.deadline)?);
+    let amount = pledge.amount.to_minor(&pledge.currency);
+    let (paid, payment_count) = paid_minor(conn, &pledge, as_of)?;
+
+    let expected = if as_of < start {
+        0
+    } else if as_of >= deadline {
+        amount
+    } else {
+        let elapsed = (as_of - start).num_days() + 1;
+        let total = (deadline - start).num_days() + 1;
+        (amount as i128 * elapsed as i128 / total as i128) as i64
+    };
+    let remaining = (amount - paid).max(0);
+    let status = if pledge.cancelled {
+        "cancelled"
+    } else if remaining == 0 {
+        "fulfilled"
+    } else if as_of > deadline {
+        "overdue"
+    } else {
+        "active"
+    };
+
+    let currency = pledge.currency.clone();
+    Ok(PledgeProgress {
+        as_of: as_of.to_string(),
+        paid: Money::from_minor(paid, &currency),
+        payment_count,
+        remaining: Money::from_minor(remaining, &currency),
+        expected_paid: Money::from_minor(expected, &currency),
+        schedule_difference: Money::from_minor(paid - expected, &currency),
+        on_schedule: paid >= expected,
+        status: status.to_string(),
+        pledge,
+    })
+}
+
+fn all_pledge_progress(conn: &Connection, as_of: NaiveDate) -> Ten10Result<Vec<PledgeProgress>> {
+    let pledges = conn
+        .prepare(&format!("{} ORDER BY p.deadline, r.name", PLEDGE_SELECT))?
+        .query_map([], Pledge::from_row)?
+        .collect::<RusqliteResult<Vec<_>>>()?;
+    pledges.into_iter().map(|pledge| pledge_progress(conn, pledge, as_of)).collect()
+}
+
+fn as_of_or_today(as_of: Option<String>) -> Ten10Result<NaiveDate> {
+    match as_of {
+        Some(date) => parse_date(&date),
+        None => Ok(Local::now().date_naive()),
+    }
+}
+
+/// Validates the payload and writes it as pledge `id`.
+fn save_pledge(conn: &Connection, id: &str, payload: PledgePayload, is_new: bool) -> Ten10Result<()> {
+    let recipient_id = resolve_recipient_id(conn, Some(&payload.recipient))?
+        .ok_or_else(|| Ten10Error::validation("A pledge needs a recipient."))?;
+    if payload.amount.to_major() <= 0.0 {
+        return Err(Ten10Error::validation("Pledge amount must be positive."));
+    }
+    let currency = match payload.currency.as_deref().map(str::trim) {
+        Some(currency) if !currency.is_empty() => currency.to_string(),
+        _ => default_currency_or_ils(conn)?,
+    };
+    let start_date = match payload.start_date {
+        Some(date) => parse_date(&date)?,
+        None => Local::now().date_naive(),
+    };
+    let deadline = parse_date(&payload.deadline)?;
+    if deadline < start_date {
+        return Err(Ten10Error::validation("The deadline must not be before the start date."));
+    }
+    let notes = payload.notes.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    let now = Utc::now().to_rfc3339();
+
+    let sql = if is_new {
+        "INSERT INTO pledges (id, recipient_id, amount, currency, start_date, deadline, notes, cancelled, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)"
+    } else {
+        "UPDATE pledges SET recipient_id = ?2, amount = ?3, currency = ?4, start_date = ?5, deadline = ?6,
+             notes = ?7, cancelled = ?8, updated_at = ?9
+         WHERE id = ?1"
+    };
+    let written = conn.execute(
+        sql,
+        params![
+            id,
+            recipient_id,
+            payload.amount.to_minor(&currency),
+            currency,
+            start_date.to_string(),
+            deadline.to_string(),
+            notes,
+            payload.cancelled,
+            now,
+        ],
+    )?;
+    if written == 0 {
+        return Err(Ten10Error::not_found("Pledge", id));
+    }
+    Ok(())
+}
+
+/// Links a donation paid in the pledge's currency to the pledge.
+pub(crate) fn link_payment(conn: &Connection, pledge_id: &str, transaction_id: &str) -> Ten10Result<()> {
+    let pledge = load_pledge(conn, pledge_id)?;
+    let (transaction_type, paid_currency): (String, String) = conn
+        .query_row(
+            "SELECT type, COALESCE(original_currency, currency) FROM transactions WHERE id = ?1",
+            params![transaction_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| Ten10Error::not_found("Transaction", transaction_id))?;
+    if !DONATION_TYPES.contains(&transaction_type.as_str()) {
+        return Err(Ten10Error::validation("Only donations can pay a pledge."));
+    }
+    if paid_currency != pledge.currency {
+        return Err(Ten10Error::validation(format!(
+            "The pledge is in {}, the donation was paid in {}.",
+            pledge.currency, paid_currency
+        )));
+    }
+    conn.execute(
+        "INSERT OR IGNORE INTO pledge_payments (pledge_id, transaction_id) VALUES (?1, ?2)",
+        params![pledge_id, transaction_id],
+    )?;
+    Ok(())
+}
+
+/// Creates a recurring donation paying what is left of the pledge in
+/// `installments` equal payments (rounded up to the minor unit) from `first_date`.
+pub(crate) fn create_payment_plan(
+    conn: &Connection,
+    pledge_id: &str,
+    frequency: &str,
+    installments: i32,
+    first_date: NaiveDate,
+) -> Ten10Result<RecurringTransaction> {
+    let pledge = load_pledge(conn, pledge_id)?;
+    if let Some(recurring_id) = &pledge.recurring_id {
+        let active: Option<String> = conn
+            .query_row(
+                "SELECT id FROM recurring_transactions WHERE id = ?1 AND status = 'active'",
+                params![recurring_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if active.is_some() {
+            return Err(Ten10Error::validation("The pledge already has an active payment plan."));
+        }
+    }
+    if installments < 1 {
+        return Err(Ten10Error::validation("A payment plan needs at least one installment."));
+    }
+    if advance_due_date(first_date, frequency, first_date.day() as i32).is_none() {
+        return Err(Ten10Error::validation(format!("Invalid frequency: {}", frequency)));
+    }
+    // Every payment so far, whatever its date.
+    let all_time = NaiveDate::from_ymd_opt(9999, 12, 31).unwrap_or(NaiveDate::MAX);
+    let progress = pledge_progress(conn, pledge.clone(), all_time)?;
+    let remaining = progress.remaining.to_minor(&pledge.currency);
+    if remaining == 0 {
+        return Err(Ten10Error::validation("The pledge is already paid in full."));
+    }
+    let installment = (remaining + installments as i64 - 1) / installments as i64;
+
+    let now = Utc::now().to_rfc3339();
+    let plan = RecurringTransaction {
+        id: Uuid::new_v4().to_string(),
+        user_id: None,
+        status: "active".to_string(),
+        start_date: first_date.to_string(),
+        next_due_date: first_date.to_string(),
+        frequency: frequency.to_string(),
+        day_of_month: first_date.day() as i32,
+        total_occurrences: Some(installments),
+        execution_count: 0,
+        description: pledge.notes.clone(),
+        amount: Money::from_minor(installment, &pledge.currency),
+        currency: pledge.currency.clone(),
+        transaction_type: "donation".to_string(),
+        category: None,
+        is_chomesh: None,
+        recipient: Some(pledge.recipient.clone()),
+        payment_method: None,
+        created_at: now.clone(),
+        updated_at: now.clone(),
+        original_amount: None,
+        original_currency: None,
+        conversion_rate: None,
+        conversion_date: None,
+        rate_source: None,
+    };
+    insert_recurring_transaction_row(conn, &plan)?;
+    conn.execute(
+        "UPDATE pledges SET recurring_id = ?2, updated_at = ?3 WHERE id = ?1",
+        params![pledge_id, plan.id, now],
+    )?;
+    Ok(plan)
+}
+
+/// Every pledge with its progress as of `as_of` (today by default), by deadline.
+#[tauri::command]
+pub fn get_pledges(db_state: State<'_, DbState>, as_of: Option<String>) -> Result<Vec<PledgeProgress>, Ten10Error> {
+    let as_of = as_of_or_today(as_of)?;
+    let conn = db_state.read()?;
+    all_pledge_progress(&conn, as_of)
+}
+
+#[tauri::command]
+pub fn get_pledge_progress(
+    db_state: State<'_, DbState>,
+    id: String,
+    as_of: Option<String>,
+) -> Result<PledgeProgress, Ten10Error> {
+    let as_of = as_of_or_today(as_of)?;
+    let conn = db_state.read()?;
+    pledge_progress(&conn, load_pledge(&conn, &id)?, as_of)
+}
+
+/// Pledges past their deadline and not paid in full.
+#[tauri::command]
+pub fn get_overdue_pledges(
+    db_state: State<'_, DbState>,
+    as_of: Option<String>,
+) -> Result<Vec<PledgeProgress>, Ten10Error> {
+    let as_of = as_of_or_today(as_of)?;
+    let conn = db_state.read()?;
+    Ok(all_pledge_progress(&conn, as_of)?.into_iter().filter(|p| p.status == "overdue").collect())
+}
+
+#[tauri::command]
+pub fn add_pledge(db_state: State<'_, DbState>, pledge: PledgePayload) -> Result<Pledge, Ten10Error> {
+    let mut conn = db_state.0.lock()?;
+    let tx = conn.transaction()?;
+    let id = Uuid::new_v4().to_string();
+    save_pledge(&tx, &id, pledge, true)?;
+    let saved = load_pledge(&tx, &id)?;
+    tx.commit()?;
+    Ok(saved)
+}
+
+#[tauri::command]
+pub fn update_pledge(db_state: State<'_, DbState>, id: String, pledge: PledgePayload) -> Result<Pledge, Ten10Error> {
+    let mut conn = db_state.0.lock()?;
+    let tx = conn.transaction()?;
+    save_pledge(&tx, &id, pledge, false)?;
+    let saved = load_pledge(&tx, &id)?;
+    tx.commit()?;
+    Ok(saved)
+}
+
+/// Deletes a pledge and its payment links; the donations and any payment plan stay.
+#[tauri::command]
+pub fn delete_pledge(db_state: State<'_, DbState>, id: String) -> Result<(), Ten10Error> {
+    let conn = db_state.0.lock()?;
+    match conn.execute("DELETE FROM pledges WHERE id = ?1", params![id])? {
+        0 => Err(Ten10Error::not_found("Pledge", id)),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn link_pledge_payment(
+    db_state: State<'_, DbState>,
+    pledge_id: String,
+    transaction_id: String,
+) -> Result<(), Ten10Error> {
+    let conn = db_state.0.lock()?;
+    link_payment(&conn, &pledge_id, &transaction_id)
+}
+
+#[tauri::command]
+pub fn unlink_pledge_payment(
+    db_state: State<'_, DbState>,
+    pledge_id: String,
+    transaction_id: String,
+) -> Result<(), Ten10Error> {
+    let conn = db_state.0.lock()?;
+    conn.execute(
+        "DELETE FROM pledge_payments WHERE pledge_id = ?1 AND transaction_id = ?2",
+        params![pledge_id, transaction_id],
+    )?;
+    Ok(())
+}
+
+/// Generates a standing donation that pays off the rest of the pledge.
+#[tauri::command]
+pub fn create_pledge_payment_plan(
+    db_state: State<'_, DbState>,
+    pledge_id: String,
+    frequency: String,
+    installments: i32,
+    first_date: String,
+) -> Result<RecurringTransaction, Ten10Error> {
+    let first_date = parse_date(&first_date)?;
+    let mut conn = db_state.0.lock()?;
+    let tx = conn.transaction()?;
+    let plan = create_payment_plan(&tx, &pledge_id, &frequency, installments, first_date)?;
+    tx.commit()?;
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO transactions (id, date, amount, currency, type, recipient) VALUES
+                ('d1', '2024-02-01', 100000, 'ILS', 'donation', 'Yeshiva'),
+                ('d2', '2024-03-01', 150000, 'ILS', 'donation', 'Yeshiva'),
+                ('e1', '2024-03-01', 50000, 'ILS', 'expense', NULL);
+            INSERT INTO transactions (id, date, amount, currency, type, recipient, original_amount, original_currency) VALUES
+                ('u1', '2024-03-01', 37000, 'ILS', 'donation', 'Yeshiva', 10000, 'USD');",
+        )
+        .unwrap();
+        crate::commands::recipient_commands::link_unlinked_recipients(&conn).unwrap();
+        let payload = PledgePayload {
+            recipient: "yeshiva".to_string(),
+            amount: Money::from_major(5000.0, "ILS").unwrap(),
+            currency: None,
+            start_date: Some("2024-01-01".to_string()),
+            deadline: "2024-12-31".to_string(),
+            notes: None,
+            cancelled: false,
+        };
+        save_pledge(&conn, "p1", payload, true).unwrap();
+        conn
+    }
+
+    #[test]
+    fn tracks_payments_against_a_steady_schedule() {
+        let conn = test_db();
+        link_payment(&conn, "p1", "d1").unwrap();
+        link_payment(&conn, "p1", "d2").unwrap();
+        assert!(matches!(link_payment(&conn, "p1", "e1"), Err(Ten10Error::Validation(_))));
+        assert!(matches!(link_payment(&conn, "p1", "u1"), Err(Ten10Error::Validation(_))));
+
+        let progress = pledge_progress(&conn, load_pledge(&conn, "p1").unwrap(), date("2024-07-01")).unwrap();
+        assert_eq!(progress.pledge.recipient, "Yeshiva");
+        assert_eq!((progress.paid.to_major(), progress.payment_count), (2500.0, 2));
+        assert_eq!(progress.remaining.to_major(), 2500.0);
+        // 183 of 366 days.
+        assert_eq!(progress.expected_paid.to_major(), 2500.0);
+        assert!(progress.on_schedule);
+        assert_eq!(progress.status, "active");
+
+        let early = pledge_progress(&conn, load_pledge(&conn, "p1").unwrap(), date("2024-02-15")).unwrap();
+        assert_eq!((early.paid.to_major(), early.on_schedule), (1000.0, true));
+
+        let overdue = pledge_progress(&conn, load_pledge(&conn, "p1").unwrap(), date("2025-01-01")).unwrap();
+        assert_eq!((overdue.status.as_str(), overdue.schedule_difference.to_major()), ("overdue", -2500.0));
+    }
+
+    #[test]
+    fn payment_plan_pays_off_the_rest() {
+        let conn = test_db();
+        link_payment(&conn, "p1", "d1").unwrap();
+        let plan = create_payment_plan(&conn, "p1", "monthly", 3, date("2024-04-10")).unwrap();
+        assert_eq!((plan.amount.to_major(), plan.total_occurrences), (1333.34, Some(3)));
+        assert_eq!(plan.recipient.as_deref(), Some("Yeshiva"));
+        assert!(matches!(
+            create_payment_plan(&conn, "p1", "monthly", 3, date("2024-04-10")),
+            Err(Ten10Error::Validation(_))
+        ));
+
+        // Occurrences of the plan count as payments without being linked.
+        conn.execute(
+            "INSERT INTO transactions (id, date, amount, currency, type, source_recurring_id) VALUES ('r1', '2024-04-10', 133334, 'ILS', 'donation', ?1)",
+            params![plan.id],
+        )
+        .unwrap();
+        let progress = pledge_progress(&conn, load_pledge(&conn, "p1").unwrap(), date("2024-04-30")).unwrap();
+        assert_eq!(progress.paid.to_major(), 2333.34);
+    }
+}
@@ -0,0 +1,424 @@
+// src-tauri/src/commands/recipient_commands.rs
+// Donation recipients as an entity: CRUD, aliases and merging.
+//
+// Transactions keep the recipient text as typed and link to a recipient through
+// `recipient_id`. The link is resolved on every save from the text, matching a
+// recipient's name or one of its aliases (case-insensitive, trimmed); unknown
+// text creates a new recipient. Names and aliases are unique across recipients,
+// so a text always resolves to exactly one recipient.
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
+use serde::Deserialize;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::error::{Ten10Error, Ten10Result};
+use crate::models::Recipient;
+use crate::DbState;
+
+/// Tables whose rows link to a recipient.
+const LINKED_TABLES: [&str; 2] = ["transactions", "recurring_transactions"];
+
+#[derive(Deserialize, Debug, Default)]
+pub struct RecipientPayload {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub tax_deductible: bool,
+    #[serde(default)]
+    pub registration_number: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+fn find_recipient_id(conn: &Connection, name: &str) -> RusqliteResult<Option<String>> {
+    conn.query_row(
+        "SELECT id FROM recipients WHERE name = ?1
+         UNION ALL
+         SELECT recipient_id FROM recipient_aliases WHERE alias = ?1
+         LIMIT 1",
+        params![name],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// The recipient `text` refers to, created when no name or alias matches.
+/// None for a missing or blank text.
+pub(crate) fn resolve_recipient_id(conn: &Connection, text: Option<&str>) -> RusqliteResult<Option<String>> {
+    let name = match text.map(str::trim) {
+        Some(name) if !name.is_empty() => name,
+        _ => return Ok(None),
+    };
+    if let Some(id) = find_recipient_id(conn, name)? {
+        return Ok(Some(id));
+    }
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO recipients (id, name, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)",
+        params![id, name, Utc::now().to_rfc3339()],
+    )?;
+    Ok(Some(id))
+}
+
+/// Links every row that has recipient text but no `recipient_id`, creating
+/// recipients as needed. Returns the number of rows linked. Test fixtures use it
+/// to link rows seeded with plain SQL.
+#[cfg(test)]
+pub(crate) fn link_unlinked_recipients(conn: &Connection) -> RusqliteResult<usize> {
+    let mut linked = 0;
+    for table in LINKED_TABLES {
+        let names: Vec<String> = conn
+            .prepare(&format!(
+                "SELECT DISTINCT TRIM(recipient) FROM {} WHERE recipient_id IS NULL AND TRIM(COALESCE(recipient, '')) <> ''",
+                table
+            ))?
+            .query_map([], |row| row.get(0))?
+            .collect::<RusqliteResult<_>>()?;
+        for name in names {
+            let id = resolve_recipient_id(conn, Some(&name))?;
+            linked += conn.execute(
+                &format!(
+                    "UPDATE {} SET recipient_id = ?1 WHERE recipient_id IS NULL AND TRIM(recipient) = ?2 COLLATE NOCASE",
+                    table
+                ),
+                params![id, name],
+            )?;
+        }
+    }
+    Ok(linked)
+}
+
+fn load_recipient(conn: &Connection, id: &str) -> Ten10Result<Recipient> {
+    let mut recipient = conn
+        .query_row("SELECT * FROM recipients WHERE id = ?1", params![id], Recipient::from_row)
+        .optional()?
+        .ok_or_else(|| Ten10Error::not_found("Recipient", id))?;
+    recipient.aliases = load_aliases(conn, id)?;
+    Ok(recipient)
+}
+
+fn load_aliases(conn: &Connection, id: &str) -> RusqliteResult<Vec<String>> {
+    conn.prepare("SELECT alias FROM recipient_aliases WHERE recipient_id = ?1 ORDER BY alias")?
+        .query_map(params![id], |row| row.get(0))?
+        .collect()
+}
+
+/// Trims the payload and drops empty or repeated aliases (and ones equal to the name).
+fn normalize(payload: RecipientPayload) -> Ten10Result<RecipientPayload> {
+    let name = payload.name.trim().to_string();
+    if name.is_empty() {
+        return Err(Ten10Error::validation("Recipient name must not be empty."));
+    }
+    let mut aliases: Vec<String> = Vec::new();
+    for alias in payload.aliases.iter().map(|a| a.trim()) {
+        let taken = alias.eq_ignore_ascii_case(&name) || aliases.iter().any(|a| a.eq_ignore_ascii_case(alias));
+        if !alias.is_empty() && !taken {
+            aliases.push(alias.to_string());
+        }
+    }
+    let blank_to_none = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    Ok(RecipientPayload {
+        name,
+        aliases,
+        registration_number: blank_to_none(payload.registration_number),
+        notes: blank_to_none(payload.notes),
+        ..payload
+    })
+}
+
+/// Fails when the name or an alias already belongs to a recipient other than `own_id`.
+fn ensure_names_free(conn: &Connection, payload: &RecipientPayload, own_id: Option<&str>) -> Ten10Result<()> {
+    for name in std::iter::once(&payload.name).chain(&payload.aliases) {
+        if let Some(owner) = find_recipient_id(conn, name)? {
+            if Some(owner.as_str()) != own_id {
+                return Err(Ten10Error::validation(format!(
+                    "'{}' is already the name or an alias of another recipient; merge the two instead.",
+                    name
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Stores `payload` as recipient `id` (inserting it when `is_new`) and links
+/// rows whose text is its name or one of its aliases.
+fn save_recipient(conn: &Connection, id: &str, payload: &RecipientPayload, is_new: bool) -> Ten10Result<()> {
+    let now = Utc::now().to_rfc3339();
+    if is_new {
+        conn.execute(
+            "INSERT INTO recipients (id, name, tax_deductible, registration_number, notes, archived, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+            params![id, payload.name, payload.tax_deductible, payload.registration_number, payload.notes, payload.archived, now],
+        )?;
+    } else {
+        conn.execute(
+            "UPDATE recipients SET name = ?2, tax_deductible = ?3, registration_number = ?4, notes = ?5,
+                archived = ?6, updated_at = ?7
+             WHERE id = ?1",
+            params![id, payload.name, payload.tax_deductible, payload.registration_number, payload.notes, payload.archived, now],
+        )?;
+    }
+
+    conn.execute("DELETE FROM recipient_aliases WHERE recipient_id = ?1", params![id])?;
+    for alias in &payload.aliases {
+        conn.execute("INSERT INTO recipient_aliases (alias, recipient_id) VALUES (?1, ?2)", params![alias, id])?;
+    }
+
+    for table in LINKED_TABLES {
+        for name in std::iter::once(&payload.name).chain(&payload.aliases) {
+            conn.execute(
+                &format!("UPDATE {} SET recipient_id = ?1 WHERE TRIM(recipient) = ?2 COLLATE NOCASE", table),
+                params![id, name],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Number of transactions, recurring definitions and pledges linked to recipient `id`.
+fn usage_count(conn: &Connection, id: &str) -> RusqliteResult<i64> {
+    conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM transactions WHERE recipient_id = ?1)
+              + (SELECT COUNT(*) FROM recurring_transactions WHERE recipient_id = ?1)
+              + (SELECT COUNT(*) FROM pledges WHERE recipient_id = ?1)",
+        params![id],
+        |row| row.get(0),
+    )
+}
+
+pub(crate) fn merge_recipient_rows(conn: &Connection, source_id: &str, target_id: &str) -> Ten10Result<()> {
+    if source_id == target_id {
+        return Err(Ten10Error::validation("A recipient cannot be merged into itself."));
+    }
+    let source = load_recipient(conn, source_id)?;
+    let target = load_recipient(conn, target_id)?;
+
+    for table in LINKED_TABLES.iter().chain(&["pledges"]) {
+        conn.execute(
+            &format!("UPDATE {} SET recipient_id = ?2 WHERE recipient_id = ?1", table),
+            params![source_id, target_id],
+        )?;
+    }
+    conn.execute("DELETE FROM recipient_aliases WHERE recipient_id = ?1", params![source_id])?;
+    conn.execute("DELETE FROM recipients WHERE id = ?1", params![source_id])?;
+
+    // The source's spellings keep resolving, now to the target.
+    let mut merged = RecipientPayload {
+        name: target.name,
+        aliases: target.aliases.into_iter().chain(std::iter::once(source.name)).chain(source.aliases).collect(),
+        tax_deductible: target.tax_deductible || source.tax_deductible,
+        registration_number: target.registration_number.or(source.registration_number),
+        notes: target.notes.or(source.notes),
+        archived: target.archived,
+    };
+    merged = normalize(merged)?;
+    save_recipient(conn, target_id, &merged, false)
+}
+
+/// All recipients by name; archived ones only when `include_archived`.
+#[tauri::command]
+pub fn get_recipients(
+    db_state: State<'_, DbState>,
+    include_archived: Option<bool>,
+) -> Result<Vec<Recipient>, Ten10Error> {
+    let conn = db_state.read()?;
+    let mut stmt = conn.prepare("SELECT * FROM recipients WHERE ?1 OR archived = 0 ORDER BY name")?;
+    let mut recipients = stmt
+        .query_map(params![include_archived.unwrap_or(false)], Recipient::from_row)?
+        .collect::<RusqliteResult<Vec<_>>>()?;
+    for recipient in &mut recipients {
+        recipient.aliases = load_aliases(&conn, &recipient.id)?;
+    }
+    Ok(recipients)
+}
+
+#[tauri::command]
+pub fn get_recipient_by_id(db_state: State<'_, DbState>, id: String) -> Result<Recipient, Ten10Error> {
+    let conn = db_state.read()?;
+    load_recipient(&conn, &id)
+}
+
+#[tauri::command]
+pub fn add_recipient(db_state: State<'_, DbState>, recipient: RecipientPayload) -> Result<Recipient, Ten10Error> {
+    let payload = normalize(recipient)?;
+    let mut conn = db_state.0.lock()?;
+    let tx = conn.transaction()?;
+    ensure_names_free(&tx, &payload, None)?;
+    let id = Uuid::new_v4().to_string();
+    save_recipient(&tx, &id, &payload, true)?;
+    let saved = load_recipient(&tx, &id)?;
+    tx.commit()?;
+    Ok(saved)
+}
+
+/// Replaces every field of recipient `id`, aliases included.
+#[tauri::command]
+pub fn update_recipient(
+    db_state: State<'_, DbState>,
+    id: String,
+    recipient: RecipientPayload,
+) -> Result<Recipient, Ten10Error> {
+    let payload = normalize(recipient)?;
+    let mut conn = db_state.0.lock()?;
+    let tx = conn.transaction()?;
+    load_recipient(&tx, &id)?;
+    ensure_names_free(&tx, &payload, Some(&id))?;
+    save_recipient(&tx, &id, &payload, false)?;
+    let saved = load_recipient(&tx, &id)?;
+    tx.commit()?;
+    Ok(saved)
+}
+
+/// Moves every transaction of `source_id` to `target_id`, keeps the source's
+/// name and aliases as aliases of the target, and deletes the source.
+#[tauri::command]
+pub fn merge_recipients(
+    db_state: State<'_, DbState>,
+    source_id: String,
+    target_id: String,
+) -> Result<Recipient, Ten10Error> {
+    let mut conn = db_state.0.lock()?;
+    let tx = conn.transaction()?;
+    merge_recipient_rows(&tx, &source_id, &target_id)?;
+    let merged = load_recipient(&tx, &target_id)?;
+    tx.commit()?;
+    Ok(merged)
+}
+
+/// Deletes a recipient no transaction uses; used ones can be archived or merged.
+#[tauri::command]
+pub fn delete_recipient(db_state: State<'_, DbState>, id: String) -> Result<(), Ten10Error> {
+    let conn = db_state.0.lock()?;
+    let used = usage_count(&conn, &id)?;
+    if used > 0 {
+        return Err(Ten10Error::validation(format!(
+            "Recipient is used by {} transactions or pledges; archive or merge it instead.",
+            used
+        )));
+    }
+    conn.execute("DELETE FROM recipient_aliases WHERE recipient_id = ?1", params![id])?;
+    match conn.execute("DELETE FROM recipients WHERE id = ?1", params![id])? {
+        0 => Err(Ten10Error::not_found("Recipient", id)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO transactions (id, date, amount, currency, type, recipient) VALUES
+                ('t1', '2024-01-01', 10000, 'ILS', 'donation', 'Yeshivat Mir'),
+                ('t2', '2024-02-01', 10000, 'ILS', 'donation', ' yeshivat mir'),
+                ('t3', '2024-03-01', 10000, 'ILS', 'donation', 'Mir Yeshiva'),
+                ('t4', '2024-03-01', 10000, 'ILS', 'donation', ''),
+                ('t5', '2024-03-01', 10000, 'ILS', 'income', NULL);",
+        )
+        .unwrap();
+        // Seeds recipients from the rows above, as the upgrade does for existing data.
+        assert_eq!(link_unlinked_recipients(&conn).unwrap(), 3);
+        conn
+    }
+
+    fn links(conn: &Connection) -> Vec<(String, Option<String>)> {
+        conn.prepare(
+            "SELECT t.id, r.name FROM transactions t LEFT JOIN recipients r ON r.id = t.recipient_id ORDER BY t.id",
+        )
+        .unwrap()
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .unwrap()
+        .collect::<RusqliteResult<_>>()
+        .unwrap()
+    }
+
+    fn id_of(conn: &Connection, name: &str) -> String {
+        find_recipient_id(conn, name).unwrap().unwrap()
+    }
+
+    #[test]
+    fn seeding_creates_one_recipient_per_spelling_and_links_rows() {
+        let conn = test_db();
+        let names: Vec<String> = conn
+            .prepare("SELECT name FROM recipients ORDER BY name")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<RusqliteResult<_>>()
+            .unwrap();
+        assert_eq!(names, vec!["Mir Yeshiva", "Yeshivat Mir"]);
+
+        let mir = Some("Yeshivat Mir".to_string());
+        assert_eq!(
+            links(&conn),
+            vec![
+                ("t1".to_string(), mir.clone()),
+                ("t2".to_string(), mir),
+                ("t3".to_string(), Some("Mir Yeshiva".to_string())),
+                ("t4".to_string(), None),
+                ("t5".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn aliases_resolve_and_must_be_unique() {
+        let conn = test_db();
+        let mir = id_of(&conn, "Yeshivat Mir");
+
+        let taken = RecipientPayload {
+            name: "Yeshivat Mir".to_string(),
+            aliases: vec!["Mir Yeshiva".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(ensure_names_free(&conn, &normalize(taken).unwrap(), Some(&mir)), Err(Ten10Error::Validation(_))));
+
+        let payload = normalize(RecipientPayload {
+            name: " Yeshivat Mir ".to_string(),
+            aliases: vec!["ישיבת מיר".to_string(), "yeshivat mir".to_string(), " ".to_string()],
+            tax_deductible: true,
+            registration_number: Some("580000000".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(payload.aliases, vec!["ישיבת מיר"]);
+        save_recipient(&conn, &mir, &payload, false).unwrap();
+
+        assert_eq!(resolve_recipient_id(&conn, Some("ישיבת מיר ")).unwrap(), Some(mir.clone()));
+        assert_eq!(resolve_recipient_id(&conn, Some("  ")).unwrap(), None);
+        let created = resolve_recipient_id(&conn, Some("Gemach")).unwrap().unwrap();
+        assert_eq!(load_recipient(&conn, &created).unwrap().name, "Gemach");
+
+        let saved = load_recipient(&conn, &mir).unwrap();
+        assert!(saved.tax_deductible);
+        assert_eq!(saved.aliases, vec!["ישיבת מיר"]);
+    }
+
+    #[test]
+    fn merge_moves_rows_and_keeps_spellings_as_aliases() {
+        let conn = test_db();
+        let (mir, other) = (id_of(&conn, "Yeshivat Mir"), id_of(&conn, "Mir Yeshiva"));
+        conn.execute("UPDATE recipients SET tax_deductible = 1 WHERE id = ?1", params![other]).unwrap();
+
+        merge_recipient_rows(&conn, &other, &mir).unwrap();
+
+        assert!(links(&conn).iter().take(3).all(|(_, name)| name.as_deref() == Some("Yeshivat Mir")));
+        let merged = load_recipient(&conn, &mir).unwrap();
+        assert_eq!(merged.aliases, vec!["Mir Yeshiva"]);
+        assert!(merged.tax_deductible);
+        assert_eq!(resolve_recipient_id(&conn, Some("mir yeshiva")).unwrap(), Some(mir.clone()));
+        assert!(matches!(load_recipient(&conn, &other), Err(Ten10Error::NotFound { .. })));
+
+        assert!(matches!(merge_recipient_rows(&conn, &mir, &mir), Err(Ten10Error::Validation(_))));
+        assert_eq!(usage_count(&conn, &mir).unwrap(), 3);
+    }
+}
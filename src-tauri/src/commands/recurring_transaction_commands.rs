@@ -1,13 +1,21 @@
 // src-tauri/src/commands/recurring_transaction_commands.rs
 
-use crate::models::RecurringTransaction;
+use crate::models::{RecurringTransaction, Transaction};
+use crate::money::Money;
 use crate::DbState;
-use chrono::Local;
-use rusqlite::{params, Connection, Result as RusqliteResult};
+use crate::error::Ten10Error;
+use chrono::{Datelike, Days, Local, Months, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
 use serde::{Deserialize, Serialize};
 use rusqlite::types::ToSql;
+use std::collections::HashMap;
 use tauri::State;
 
+use super::db_commands::stored_default_currency;
+use super::exchange_rate_commands::{resolve_rate, RateLookupOptions, RateMethod, ResolvedRate};
+use super::recipient_commands::resolve_recipient_id;
+use super::transaction_commands::insert_transaction_row;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TableSortingPayload {
     pub field: String,
@@ -44,12 +52,291 @@ fn get_due_recurring_transactions(
 #[tauri::command]
 pub fn get_due_recurring_transactions_handler(
     db_state: State<'_, DbState>,
-) -> std::result::Result<Vec<RecurringTransaction>, String> {
-    let conn = db_state.0.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<RecurringTransaction>, Ten10Error> {
+    let conn = db_state.read()?;
     let today_str = Local::now().format("%Y-%m-%d").to_string();
     
     get_due_recurring_transactions(&conn, &today_str)
-        .map_err(|e| format!("Failed to query due transactions: {}", e))
+        .map_err(Ten10Error::from)
+}
+
+// ─── Execution engine ─────────────────────────────────────────────────────────
+// Catches up every missed occurrence of every due definition inside ONE SQLite
+// transaction: either all generated rows and schedule updates land, or none do.
+
+/// Upper bound on occurrences generated per definition in a single run
+/// (a daily schedule left alone for ~27 years). Guards against runaway loops.
+const MAX_OCCURRENCES_PER_RUN: i32 = 10_000;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CreatedOccurrence {
+    pub recurring_id: String,
+    pub transaction_id: String,
+    pub date: String,
+    pub occurrence_number: i32,
+    pub amount: Money,
+    pub currency: String,
+    #[serde(rename = "type")]
+    pub transaction_type: String,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SkippedDefinition {
+    pub recurring_id: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct RecurringExecutionReport {
+    pub processed_definitions: usize,
+    pub created: Vec<CreatedOccurrence>,
+    pub completed_ids: Vec<String>,
+    pub skipped: Vec<SkippedDefinition>,
+}
+
+/// Next due date after `current` for the given frequency.
+/// Monthly and yearly keep `day_of_month` and clamp it to the month length
+/// (Jan 31 → Feb 29 → Mar 31; Feb 29, 2024 → Feb 28, 2025 → Feb 29, 2028).
+/// Returns None for an unknown frequency.
+pub(crate) fn advance_due_date(current: NaiveDate, frequency: &str, day_of_month: i32) -> Option<NaiveDate> {
+    let months_ahead = |months: u32| {
+        let first = current.with_day(1)?.checked_add_months(Months::new(months))?;
+        let days_in_month = first.checked_add_months(Months::new(1))?.pred_opt()?.day();
+        first.with_day((day_of_month.max(1) as u32).min(days_in_month))
+    };
+    match frequency {
+        "daily" => current.checked_add_days(Days::new(1)),
+        "weekly" => current.checked_add_days(Days::new(7)),
+        "monthly" => months_ahead(1),
+        "yearly" => months_ahead(12),
+        _ => None,
+    }
+}
+
+/// Builds the transaction for one occurrence (id, date and numbering are filled in by the caller).
+/// `fresh_rates` maps an original currency to a just-fetched rate into the default currency;
+/// it is only used for templates with an automatic rate (manual rates are always kept).
+/// Without a fresh rate, `stored_rate` (the local rate for the occurrence date) is used.
+/// Ok(None) when a legacy template has no rate at all; Err when the converted amount is out of range.
+fn build_occurrence(
+    rec: &RecurringTransaction,
+    default_currency: &str,
+    fresh_rates: &HashMap<String, f64>,
+    stored_rate: Option<&ResolvedRate>,
+    today: &str,
+) -> Result<Option<Transaction>, Ten10Error> {
+    let mut tx = Transaction {
+        id: String::new(),
+        user_id: rec.user_id.clone(),
+        date: String::new(),
+        amount: rec.amount,
+        currency: rec.currency.clone(),
+        description: rec.description.clone(),
+        transaction_type: rec.transaction_type.clone(),
+        category: rec.category.clone(),
+        is_chomesh: rec.is_chomesh,
+        recipient: rec.recipient.clone(),
+        payment_method: rec.payment_method.clone(),
+        created_at: None,
+        updated_at: None,
+        source_recurring_id: Some(rec.id.clone()),
+        occurrence_number: None,
+        original_amount: None,
+        original_currency: None,
+        conversion_rate: None,
+        conversion_date: None,
+        rate_source: None,
+    };
+
+    match (&rec.original_amount, &rec.original_currency, &rec.conversion_rate) {
+        (Some(original_amount), Some(original_currency), Some(_)) => {
+            tx.original_amount = Some(*original_amount);
+            tx.original_currency = Some(original_currency.clone());
+            let fresh = fresh_rates.get(original_currency).copied();
+            // Rates of past transactions are no better than the template's own.
+            let stored = stored_rate.filter(|r| r.method != RateMethod::Transaction);
+            match (rec.rate_source.as_deref(), fresh, stored) {
+                (Some("manual"), _, _) | (_, None, None) => {
+                    // Manual rate, or no other rate available: keep the stored conversion.
+                    tx.conversion_rate = rec.conversion_rate;
+                    tx.conversion_date = rec.conversion_date.clone();
+                    tx.rate_source = Some(rec.rate_source.clone().unwrap_or_else(|| "auto".to_string()));
+                }
+                (_, Some(rate), _) => {
+                    tx.amount = original_amount.convert(rate, default_currency)?;
+                    tx.currency = default_currency.to_string();
+                    tx.conversion_rate = Some(rate);
+                    tx.conversion_date = Some(today.to_string());
+                    tx.rate_source = Some("auto".to_string());
+                }
+                (_, None, Some(stored)) => {
+                    tx.amount = original_amount.convert(stored.rate, default_currency)?;
+                    tx.currency = default_currency.to_string();
+                    tx.conversion_rate = Some(stored.rate);
+                    tx.conversion_date = Some(stored.date.clone());
+                    tx.rate_source = Some(stored.source.clone());
+                }
+            }
+        }
+        _ if rec.currency != default_currency => {
+            // Legacy template without stored conversion details.
+            let (rate, date, source) = match (fresh_rates.get(&rec.currency), stored_rate) {
+                (Some(&rate), _) => (rate, today.to_string(), "auto".to_string()),
+                (None, Some(stored)) => (stored.rate, stored.date.clone(), stored.source.clone()),
+                (None, None) => return Ok(None),
+            };
+            tx.amount = rec.amount.convert(rate, default_currency)?;
+            tx.currency = default_currency.to_string();
+            tx.original_amount = Some(rec.amount);
+            tx.original_currency = Some(rec.currency.clone());
+            tx.conversion_rate = Some(rate);
+            tx.conversion_date = Some(date);
+            tx.rate_source = Some(source);
+        }
+        _ => {}
+    }
+
+    Ok(Some(tx))
+}
+
+/// Generates every occurrence due on or before `today` and advances the schedules.
+/// Runs in a single SQLite transaction; any error rolls back the whole run.
+pub(crate) fn process_due_recurring(
+    conn: &mut Connection,
+    today: NaiveDate,
+    fresh_rates: &HashMap<String, f64>,
+) -> RusqliteResult<RecurringExecutionReport> {
+    let today_str = today.format("%Y-%m-%d").to_string();
+    let now = Utc::now().to_rfc3339();
+
+    let tx = conn.transaction()?;
+
+    let default_currency = stored_default_currency(&tx)?;
+
+    let due = get_due_recurring_transactions(&tx, &today_str)?;
+    let mut report = RecurringExecutionReport {
+        processed_definitions: due.len(),
+        ..Default::default()
+    };
+
+    for rec in due {
+        let mut due_date = match NaiveDate::parse_from_str(&rec.next_due_date, "%Y-%m-%d") {
+            Ok(d) => d,
+            Err(e) => {
+                report.skipped.push(SkippedDefinition {
+                    recurring_id: rec.id.clone(),
+                    reason: format!("invalid next_due_date '{}': {}", rec.next_due_date, e),
+                });
+                continue;
+            }
+        };
+        if advance_due_date(due_date, &rec.frequency, rec.day_of_month).is_none() {
+            report.skipped.push(SkippedDefinition {
+                recurring_id: rec.id.clone(),
+                reason: format!("unsupported frequency '{}'", rec.frequency),
+            });
+            continue;
+        }
+
+        let default_currency = default_currency.clone().unwrap_or_else(|| rec.currency.clone());
+        let paid_currency = rec.original_currency.as_deref().unwrap_or(&rec.currency);
+
+        let mut execution_count = rec.execution_count;
+        let mut generated = 0;
+        let reached_total = |count: i32| rec.total_occurrences.is_some_and(|total| count >= total);
+
+        while due_date <= today && !reached_total(execution_count) && generated < MAX_OCCURRENCES_PER_RUN {
+            let stored_rate = if paid_currency == default_currency {
+                None
+            } else {
+                resolve_rate(&tx, paid_currency, &default_currency, due_date, &RateLookupOptions::default())?
+            };
+            match build_occurrence(&rec, &default_currency, fresh_rates, stored_rate.as_ref(), &today_str) {
+                Ok(Some(mut occurrence)) => {
+                    occurrence.id = uuid::Uuid::new_v4().to_string();
+                    occurrence.date = due_date.format("%Y-%m-%d").to_string();
+                    occurrence.occurrence_number = Some(execution_count + 1);
+                    occurrence.created_at = Some(now.clone());
+                    occurrence.updated_at = Some(now.clone());
+                    insert_transaction_row(&tx, &occurrence)?;
+
+                    report.created.push(CreatedOccurrence {
+                        recurring_id: rec.id.clone(),
+                        transaction_id: occurrence.id,
+                        date: occurrence.date,
+                        occurrence_number: execution_count + 1,
+                        amount: occurrence.amount,
+                        currency: occurrence.currency,
+                        transaction_type: occurrence.transaction_type,
+                        description: occurrence.description,
+                    });
+                }
+                Err(e) => {
+                    // The converted amount does not fit: leave this occurrence due
+                    // (uncounted) so it runs once the template is fixed.
+                    report.skipped.push(SkippedDefinition { recurring_id: rec.id.clone(), reason: e.to_string() });
+                    break;
+                }
+                Ok(None) => {
+                    // No rate to convert a legacy foreign-currency template: the
+                    // occurrence is counted but not recorded (same as the JS service).
+                    report.skipped.push(SkippedDefinition {
+                        recurring_id: rec.id.clone(),
+                        reason: format!(
+                            "no exchange rate {} -> {} for {}",
+                            rec.currency, default_currency, due_date
+                        ),
+                    });
+                }
+            }
+
+            execution_count += 1;
+            generated += 1;
+            due_date = match advance_due_date(due_date, &rec.frequency, rec.day_of_month) {
+                Some(d) => d,
+                None => break,
+            };
+        }
+
+        let status = if reached_total(execution_count) {
+            report.completed_ids.push(rec.id.clone());
+            "completed".to_string()
+        } else {
+            rec.status.clone()
+        };
+
+        tx.execute(
+            "UPDATE recurring_transactions
+             SET next_due_date = ?1, execution_count = ?2, status = ?3, updated_at = ?4
+             WHERE id = ?5",
+            params![due_date.format("%Y-%m-%d").to_string(), execution_count, status, now, rec.id],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(report)
+}
+
+/// Executes all due recurring transactions (catching up missed occurrences) atomically.
+/// `fresh_rates` optionally maps original currency → current rate into the default currency.
+#[tauri::command]
+pub fn process_due_recurring_transactions(
+    db_state: State<'_, DbState>,
+    fresh_rates: Option<HashMap<String, f64>>,
+) -> Result<RecurringExecutionReport, Ten10Error> {
+    let mut conn = db_state.0.lock()?;
+    let today = Local::now().date_naive();
+
+    let report = process_due_recurring(&mut conn, today, &fresh_rates.unwrap_or_default())?;
+
+    println!(
+        "[RUST] process_due_recurring_transactions: {} definitions, {} transactions created, {} completed",
+        report.processed_definitions,
+        report.created.len(),
+        report.completed_ids.len()
+    );
+    Ok(report)
 }
 
 /// Shared INSERT for `recurring_transactions` (used by handler and bulk import).
@@ -58,8 +345,8 @@ pub(crate) fn insert_recurring_transaction_row(
     rec_transaction: &RecurringTransaction,
 ) -> RusqliteResult<()> {
     conn.execute(
-        "INSERT INTO recurring_transactions (id, user_id, status, start_date, next_due_date, frequency, day_of_month, total_occurrences, execution_count, description, amount, currency, type, category, is_chomesh, recipient, payment_method, created_at, updated_at, original_amount, original_currency, conversion_rate, conversion_date, rate_source)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)",
+        "INSERT INTO recurring_transactions (id, user_id, status, start_date, next_due_date, frequency, day_of_month, total_occurrences, execution_count, description, amount, currency, type, category, is_chomesh, recipient, payment_method, created_at, updated_at, original_amount, original_currency, conversion_rate, conversion_date, rate_source, recipient_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25)",
         params![
             rec_transaction.id,
             rec_transaction.user_id,
@@ -71,7 +358,7 @@ pub(crate) fn insert_recurring_transaction_row(
             rec_transaction.total_occurrences,
             rec_transaction.execution_count,
             rec_transaction.description,
-            rec_transaction.amount,
+            rec_transaction.amount.to_minor(&rec_transaction.currency),
             rec_transaction.currency,
             rec_transaction.transaction_type,
             rec_transaction.category,
@@ -80,11 +367,14 @@ pub(crate) fn insert_recurring_transaction_row(
             rec_transaction.payment_method,
             rec_transaction.created_at,
             rec_transaction.updated_at,
-            rec_transaction.original_amount,
+            rec_transaction
+                .original_amount
+                .map(|m| m.to_minor(rec_transaction.original_currency.as_deref().unwrap_or_default())),
             rec_transaction.original_currency,
             rec_transaction.conversion_rate,
             rec_transaction.conversion_date,
             rec_transaction.rate_source,
+            resolve_recipient_id(conn, rec_transaction.recipient.as_deref())?,
         ],
     )?;
     Ok(())
@@ -94,18 +384,14 @@ pub(crate) fn insert_recurring_transaction_row(
 pub fn add_recurring_transaction_handler(
     db_state: State<'_, DbState>,
     rec_transaction: RecurringTransaction,
-) -> std::result::Result<(), String> {
+) -> Result<(), Ten10Error> {
     println!(
         "[RUST] add_recurring_transaction_handler called with: {:?}",
         rec_transaction
     );
-    let conn = db_state
-        .0
-        .lock()
-        .map_err(|e| format!("DB lock error: {}", e))?;
+    let conn = db_state.0.lock()?;
 
-    insert_recurring_transaction_row(&conn, &rec_transaction)
-        .map_err(|e| format!("Failed to insert recurring transaction: {}", e))?;
+    insert_recurring_transaction_row(&conn, &rec_transaction)?;
 
     Ok(())
 }
@@ -114,8 +400,8 @@ pub fn add_recurring_transaction_handler(
 pub fn get_recurring_transactions_handler(
     db_state: State<'_, DbState>,
     args: GetRecurringTransactionsArgs,
-) -> std::result::Result<Vec<RecurringTransaction>, String> {
-    let conn = db_state.0.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<RecurringTransaction>, Ten10Error> {
+    let conn = db_state.read()?;
     let sorting = args.sorting;
     let filters = args.filters;
 
@@ -194,14 +480,13 @@ pub fn get_recurring_transactions_handler(
         where_sql, sort_field, sort_direction
     );
 
-    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&query)?;
     let params_slice: Vec<&dyn ToSql> = params.iter().map(|p| p.as_ref()).collect();
-    let rows = stmt.query_map(params_slice.as_slice(), |row| RecurringTransaction::from_row(row))
-        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params_slice.as_slice(), |row| RecurringTransaction::from_row(row))?;
 
     let mut recurring = Vec::new();
     for row in rows {
-        recurring.push(row.map_err(|e| e.to_string())?);
+        recurring.push(row?);
     }
     Ok(recurring)
 }
@@ -211,16 +496,39 @@ pub fn update_recurring_transaction_handler(
     db_state: State<'_, DbState>,
     id: String,
     updates: serde_json::Value,
-) -> std::result::Result<RecurringTransaction, String> {
-    let conn = db_state.0.lock().map_err(|e| e.to_string())?;
+) -> Result<RecurringTransaction, Ten10Error> {
+    let conn = db_state.0.lock()?;
     
     let mut set_clauses = Vec::new();
     let mut params: Vec<Box<dyn ToSql>> = Vec::new();
-    let updates_map = updates.as_object().ok_or("Invalid updates format")?;
+    let updates_map = updates
+        .as_object()
+        .ok_or_else(|| Ten10Error::validation("Invalid updates format"))?;
+
+    let current = conn
+        .query_row("SELECT * FROM recurring_transactions WHERE id = ?1", params![id], RecurringTransaction::from_row)
+        .optional()?
+        .ok_or_else(|| Ten10Error::not_found("RecurringTransaction", id.clone()))?;
 
     for (key, value) in updates_map {
         set_clauses.push(format!("{} = ?", key));
         match value {
+            // Amounts arrive in major units; store minor units of the (possibly updated) currency.
+            serde_json::Value::Number(_) if key == "amount" || key == "original_amount" => {
+                let (currency_key, stored_currency) = if key == "amount" {
+                    ("currency", Some(current.currency.as_str()))
+                } else {
+                    ("original_currency", current.original_currency.as_deref())
+                };
+                let currency = updates_map
+                    .get(currency_key)
+                    .and_then(|v| v.as_str())
+                    .or(stored_currency)
+                    .unwrap_or_default();
+                let amount: Money = serde_json::from_value(value.clone())
+                    .map_err(|e| Ten10Error::validation(format!("Invalid {}: {}", key, e)))?;
+                params.push(Box::new(amount.to_minor(currency)));
+            }
             serde_json::Value::Number(n) => {
                 if n.is_f64() {
                     params.push(Box::new(n.as_f64().unwrap()));
@@ -231,12 +539,28 @@ pub fn update_recurring_transaction_handler(
             serde_json::Value::String(s) => params.push(Box::new(s.clone())),
             serde_json::Value::Bool(b) => params.push(Box::new(*b)),
             serde_json::Value::Null => params.push(Box::new(rusqlite::types::Null)),
-            _ => return Err(format!("Unsupported value type for key: {}", key)),
+            _ => return Err(Ten10Error::validation(format!("Unsupported value type for key: {}", key))),
+        }
+        if key == "recipient" {
+            set_clauses.push("recipient_id = ?".to_string());
+            params.push(Box::new(resolve_recipient_id(&conn, value.as_str())?));
+        }
+        // A currency change without a new amount keeps the stored value, rescaled to the new minor unit.
+        let kept_amount = match key.as_str() {
+            "currency" if !updates_map.contains_key("amount") => Some(("amount", current.amount)),
+            "original_currency" if !updates_map.contains_key("original_amount") => {
+                current.original_amount.map(|amount| ("original_amount", amount))
+            }
+            _ => None,
+        };
+        if let (Some((amount_key, amount)), Some(currency)) = (kept_amount, value.as_str()) {
+            set_clauses.push(format!("{} = ?", amount_key));
+            params.push(Box::new(amount.to_minor(currency)));
         }
     }
     
     if set_clauses.is_empty() {
-        return Err("No fields to update".to_string());
+        return Err(Ten10Error::validation("No fields to update"));
     }
 
     set_clauses.push("updated_at = ?".to_string());
@@ -252,14 +576,11 @@ pub fn update_recurring_transaction_handler(
 
     let params_slice: Vec<&dyn ToSql> = final_params.iter().map(|p| p.as_ref()).collect();
 
-    conn.execute(&query, params_slice.as_slice())
-        .map_err(|e| format!("DB execute error: {}", e))?;
+    conn.execute(&query, params_slice.as_slice())?;
 
     // Fetch and return the updated transaction
-    let mut stmt = conn.prepare("SELECT * FROM recurring_transactions WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
-    let updated_rec = stmt.query_row(params![id], |row| RecurringTransaction::from_row(row))
-        .map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare("SELECT * FROM recurring_transactions WHERE id = ?1")?;
+    let updated_rec = stmt.query_row(params![id], |row| RecurringTransaction::from_row(row))?;
 
     Ok(updated_rec)
 } 
@@ -268,27 +589,218 @@ pub fn update_recurring_transaction_handler(
 pub fn get_recurring_transaction_by_id_handler(
     db_state: State<'_, DbState>,
     id: String,
-) -> std::result::Result<RecurringTransaction, String> {
-    let conn = db_state.0.lock().map_err(|e| e.to_string())?;
+) -> Result<RecurringTransaction, Ten10Error> {
+    let conn = db_state.read()?;
     
-    let mut stmt = conn.prepare("SELECT * FROM recurring_transactions WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare("SELECT * FROM recurring_transactions WHERE id = ?1")?;
         
-    let rec = stmt.query_row(params![id], |row| RecurringTransaction::from_row(row))
-        .map_err(|e| e.to_string())?;
-
-    Ok(rec)
+    stmt.query_row(params![id], |row| RecurringTransaction::from_row(row))
+        .optional()?
+        .ok_or_else(|| Ten10Error::not_found("RecurringTransaction", id))
 } 
 
 #[tauri::command]
 pub fn delete_recurring_transaction_handler(
     db_state: State<'_, DbState>,
     id: String,
-) -> std::result::Result<(), String> {
-    let conn = db_state.0.lock().map_err(|e| e.to_string())?;
+) -> Result<(), Ten10Error> {
+    let conn = db_state.0.lock()?;
     
-    conn.execute("DELETE FROM recurring_transactions WHERE id = ?1", params![id])
-        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM recurring_transactions WHERE id = ?1", params![id])?;
     
     Ok(())
 } 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("in-memory db");
+        crate::migrations::run_migrations(&mut conn).expect("schema");
+        conn.execute(
+            "INSERT INTO app_settings (key, value) VALUES ('default_currency', 'ILS')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn add_definition(conn: &Connection, id: &str, frequency: &str, next_due: &str, day: i32, total: Option<i32>) {
+        conn.execute(
+            "INSERT INTO recurring_transactions
+                (id, status, start_date, next_due_date, frequency, day_of_month, total_occurrences,
+                 execution_count, description, amount, currency, type, created_at, updated_at)
+             VALUES (?1, 'active', ?2, ?2, ?3, ?4, ?5, 0, 'הוראת קבע', 10000, 'ILS', 'donation', '2024-01-01', '2024-01-01')",
+            params![id, next_due, frequency, day, total],
+        )
+        .unwrap();
+    }
+
+    fn occurrences(conn: &Connection, rec_id: &str) -> Vec<(String, i32)> {
+        conn.prepare("SELECT date, occurrence_number FROM transactions WHERE source_recurring_id = ?1 ORDER BY date")
+            .unwrap()
+            .query_map(params![rec_id], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<RusqliteResult<_>>()
+            .unwrap()
+    }
+
+    fn schedule(conn: &Connection, rec_id: &str) -> (String, i32, String) {
+        conn.query_row(
+            "SELECT next_due_date, execution_count, status FROM recurring_transactions WHERE id = ?1",
+            params![rec_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn advance_handles_each_frequency() {
+        assert_eq!(advance_due_date(date("2024-01-31"), "daily", 31), Some(date("2024-02-01")));
+        assert_eq!(advance_due_date(date("2024-01-31"), "weekly", 31), Some(date("2024-02-07")));
+        assert_eq!(advance_due_date(date("2024-01-31"), "monthly", 31), Some(date("2024-02-29")));
+        assert_eq!(advance_due_date(date("2024-02-29"), "monthly", 31), Some(date("2024-03-31")));
+        assert_eq!(advance_due_date(date("2024-02-29"), "yearly", 29), Some(date("2025-02-28")));
+        assert_eq!(advance_due_date(date("2027-02-28"), "yearly", 29), Some(date("2028-02-29")));
+        assert_eq!(advance_due_date(date("2024-01-01"), "hourly", 1), None);
+    }
+
+    #[test]
+    fn currency_change_without_amount_keeps_the_value() {
+        use tauri::Manager;
+        let conn = test_db();
+        add_definition(&conn, "r1", "monthly", "2024-01-01", 1, None);
+        let app = tauri::test::mock_app();
+        app.manage(DbState::new(conn));
+
+        let updated = update_recurring_transaction_handler(app.state::<DbState>(), "r1".into(), serde_json::json!({ "currency": "JPY" }))
+            .unwrap();
+        assert_eq!((updated.amount.to_minor("JPY"), updated.currency.as_str()), (100, "JPY"));
+        let stored: i64 = app
+            .state::<DbState>()
+            .read()
+            .unwrap()
+            .query_row("SELECT amount FROM recurring_transactions WHERE id = 'r1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(stored, 100);
+    }
+
+    #[test]
+    fn catches_up_missed_monthly_occurrences() {
+        let mut conn = test_db();
+        add_definition(&conn, "r1", "monthly", "2024-01-31", 31, None);
+
+        let report = process_due_recurring(&mut conn, date("2024-04-15"), &HashMap::new()).unwrap();
+
+        assert_eq!(report.created.len(), 3);
+        assert_eq!(
+            occurrences(&conn, "r1"),
+            vec![
+                ("2024-01-31".to_string(), 1),
+                ("2024-02-29".to_string(), 2),
+                ("2024-03-31".to_string(), 3),
+            ]
+        );
+        assert_eq!(schedule(&conn, "r1"), ("2024-04-30".to_string(), 3, "active".to_string()));
+
+        // Running again on the same day creates nothing new.
+        let again = process_due_recurring(&mut conn, date("2024-04-15"), &HashMap::new()).unwrap();
+        assert!(again.created.is_empty());
+        assert_eq!(occurrences(&conn, "r1").len(), 3);
+    }
+
+    #[test]
+    fn completes_when_total_occurrences_reached() {
+        let mut conn = test_db();
+        add_definition(&conn, "r1", "weekly", "2024-01-01", 1, Some(2));
+
+        let report = process_due_recurring(&mut conn, date("2024-03-01"), &HashMap::new()).unwrap();
+
+        assert_eq!(report.created.len(), 2);
+        assert_eq!(report.completed_ids, vec!["r1".to_string()]);
+        assert_eq!(schedule(&conn, "r1"), ("2024-01-15".to_string(), 2, "completed".to_string()));
+    }
+
+    #[test]
+    fn not_yet_due_definitions_are_untouched() {
+        let mut conn = test_db();
+        add_definition(&conn, "r1", "daily", "2024-05-02", 2, None);
+
+        let report = process_due_recurring(&mut conn, date("2024-05-01"), &HashMap::new()).unwrap();
+
+        assert_eq!(report.processed_definitions, 0);
+        assert_eq!(schedule(&conn, "r1"), ("2024-05-02".to_string(), 0, "active".to_string()));
+    }
+
+    #[test]
+    fn auto_rate_templates_use_fresh_rate_and_manual_keep_stored() {
+        let mut conn = test_db();
+        conn.execute_batch(
+            "INSERT INTO recurring_transactions
+                (id, status, start_date, next_due_date, frequency, day_of_month, execution_count,
+                 amount, currency, type, created_at, updated_at,
+                 original_amount, original_currency, conversion_rate, conversion_date, rate_source)
+             VALUES
+                ('auto', 'active', '2024-01-01', '2024-01-01', 'monthly', 1, 0,
+                 35000, 'ILS', 'income', '2024-01-01', '2024-01-01', 10000, 'USD', 3.5, '2023-12-31', 'auto'),
+                ('manual', 'active', '2024-01-01', '2024-01-01', 'monthly', 1, 0,
+                 40000, 'ILS', 'income', '2024-01-01', '2024-01-01', 10000, 'USD', 4.0, '2023-12-31', 'manual');",
+        )
+        .unwrap();
+        let rates = HashMap::from([("USD".to_string(), 3.7)]);
+
+        process_due_recurring(&mut conn, date("2024-01-01"), &rates).unwrap();
+
+        let amount_of = |id: &str| -> (i64, f64) {
+            conn.query_row(
+                "SELECT amount, conversion_rate FROM transactions WHERE source_recurring_id = ?1",
+                params![id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap()
+        };
+        // Minor units: 100 USD at 3.7 is 370.00 ILS.
+        assert_eq!(amount_of("auto"), (37000, 3.7));
+        assert_eq!(amount_of("manual"), (40000, 4.0));
+    }
+
+    #[test]
+    fn offline_occurrences_convert_at_the_stored_rate_of_their_date() {
+        let mut conn = test_db();
+        conn.execute_batch(
+            "INSERT INTO recurring_transactions
+                (id, status, start_date, next_due_date, frequency, day_of_month, execution_count,
+                 amount, currency, type, created_at, updated_at,
+                 original_amount, original_currency, conversion_rate, conversion_date, rate_source)
+             VALUES
+                ('auto', 'active', '2024-01-01', '2024-01-01', 'monthly', 1, 0,
+                 35000, 'ILS', 'income', '2024-01-01', '2024-01-01', 10000, 'USD', 3.5, '2023-12-31', 'auto');
+             INSERT INTO exchange_rates (date, base, quote, rate, source, updated_at) VALUES
+                ('2023-12-29', 'USD', 'ILS', 3.6, 'boi', '0'),
+                ('2024-02-01', 'USD', 'ILS', 3.7, 'boi', '0');",
+        )
+        .unwrap();
+
+        process_due_recurring(&mut conn, date("2024-02-15"), &HashMap::new()).unwrap();
+
+        let converted: Vec<(String, i64, String, String)> = conn
+            .prepare("SELECT date, amount, conversion_date, rate_source FROM transactions ORDER BY date")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
+            .unwrap()
+            .collect::<RusqliteResult<_>>()
+            .unwrap();
+        assert_eq!(
+            converted,
+            vec![
+                ("2024-01-01".to_string(), 36000, "2023-12-29".to_string(), "boi".to_string()),
+                ("2024-02-01".to_string(), 37000, "2024-02-01".to_string(), "boi".to_string()),
+            ]
+        );
+    }
+}
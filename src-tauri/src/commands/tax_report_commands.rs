@@ -0,0 +1,300 @@
+// src-tauri/src/commands/tax_report_commands.rs
+// Annual donation report for the Section 46 tax credit: the donations of a tax
+// year (the Gregorian calendar year) totalled per recipient, with the recipients
+// the user marked as recognized institutions flagged as tax-deductible.
+//
+// Totals are in the default currency, which every `transactions.amount` is
+// recorded in. The per-currency breakdown shows what was actually paid: the
+// original amount and currency of converted rows, the amount otherwise. Each row
+// is scaled by its own currency before summing, so a row left in another
+// currency is not read as minor units of the default one.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::State;
+
+use super::db_commands::default_currency_or_ils;
+use super::recipient_commands::resolve_recipient_id;
+use crate::error::{Ten10Error, Ten10Result};
+use crate::money::{to_micro_sql, Money};
+use crate::transaction_types::donation_types_condition;
+use crate::DbState;
+
+/// Donations to one recipient in one currency.
+#[derive(Serialize, Debug, Clone)]
+pub struct CurrencyTotal {
+    pub currency: String,
+    pub amount: Money,
+    pub donation_count: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RecipientDonationTotal {
+    /// None (with `recipient`) for donations recorded without a recipient
+    pub recipient_id: Option<String>,
+    pub recipient: Option<String>,
+    pub tax_deductible: bool,
+    pub registration_number: Option<String>,
+    pub donation_count: i64,
+    /// In the report currency
+    pub total: Money,
+    /// What was paid, per original currency
+    pub by_currency: Vec<CurrencyTotal>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DonationTaxReport {
+    pub tax_year: i32,
+    pub start_date: String,
+    pub end_date: String,
+    /// The default currency all totals are in
+    pub currency: String,
+    /// Tax-deductible recipients first, then by total, largest first
+    pub recipients: Vec<RecipientDonationTotal>,
+    pub total: Money,
+    pub deductible_total: Money,
+    pub non_deductible_total: Money,
+}
+
+/// Donation rows of the period with their recipient's name and attributes.
+fn donations_cte() -> String {
+    format!(
+        "WITH donations AS (
+            SELECT t.recipient_id,
+                   r.name AS recipient,
+                   COALESCE(r.tax_deductible, 0) AS tax_deductible,
+                   r.registration_number,
+                   t.amount,
+                   t.currency,
+                   COALESCE(t.original_currency, t.currency) AS paid_currency,
+                   COALESCE(t.original_amount, t.amount) AS paid_amount
+            FROM transactions t
+            LEFT JOIN recipients r ON r.id = t.recipient_id
+            WHERE {} AND t.date >= ?1 AND t.date <= ?2
+        )",
+        donation_types_condition()
+    )
+}
+
+pub(crate) fn query_donation_tax_report(conn: &Connection, tax_year: i32) -> Ten10Result<DonationTaxReport> {
+    if !(1900..=9999).contains(&tax_year) {
+        return Err(Ten10Error::validation(format!("Invalid tax year: {}", tax_year)));
+    }
+    let (start_date, end_date) = (format!("{}-01-01", tax_year), format!("{}-12-31", tax_year));
+    let currency = default_currency_or_ils(conn)?;
+
+    let mut by_currency: HashMap<Option<String>, Vec<CurrencyTotal>> = HashMap::new();
+    let mut stmt = conn.prepare(&format!(
+        "{}
+        SELECT recipient_id, paid_currency, SUM(paid_amount), COUNT(*)
+        FROM donations
+        GROUP BY recipient_id, paid_currency
+        ORDER BY paid_currency",
+        donations_cte()
+    ))?;
+    let rows = stmt.query_map(params![start_date, end_date], |row| {
+        let paid_currency: String = row.get(1)?;
+        Ok((
+            row.get::<_, Option<String>>(0)?,
+            CurrencyTotal {
+                amount: Money::from_minor(row.get(2)?, &paid_currency),
+                currency: paid_currency,
+                donation_count: row.get(3)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (key, total) = row?;
+        by_currency.entry(key).or_default().push(total);
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        "{}
+        SELECT recipient_id, MAX(recipient), MAX(tax_deductible), MAX(registration_number), SUM({}), COUNT(*)
+        FROM donations
+        GROUP BY recipient_id
+        ORDER BY 3 DESC, 5 DESC, 2",
+        donations_cte(),
+        to_micro_sql("amount", "currency")
+    ))?;
+    let rows = stmt.query_map(params![start_date, end_date], |row| {
+        Ok((
+            row.get::<_, Option<String>>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, bool>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, i64>(4)?,
+            row.get::<_, i64>(5)?,
+        ))
+    })?;
+
+    // Micro-unit sums, rounded to the report currency's minor unit
+    let in_currency = |micro: i64| Money::from_minor(Money::from_micro(micro).to_minor(&currency), &currency);
+    let (mut deductible_micro, mut non_deductible_micro) = (0i64, 0i64);
+    let mut recipients = Vec::new();
+    for row in rows {
+        let (recipient_id, recipient, tax_deductible, registration_number, total_micro, donation_count) = row?;
+        if tax_deductible {
+            deductible_micro += total_micro;
+        } else {
+            non_deductible_micro += total_micro;
+        }
+        recipients.push(RecipientDonationTotal {
+            by_currency: by_currency.remove(&recipient_id).unwrap_or_default(),
+            recipient_id,
+            recipient,
+            tax_deductible,
+            registration_number,
+            donation_count,
+            total: in_currency(total_micro),
+        });
+    }
+
+    Ok(DonationTaxReport {
+        tax_year,
+        start_date,
+        end_date,
+        total: in_currency(deductible_micro + non_deductible_micro),
+        deductible_total: in_currency(deductible_micro),
+        non_deductible_total: in_currency(non_deductible_micro),
+        currency,
+        recipients,
+    })
+}
+
+/// Donations of `tax_year` per recipient, for the annual Section 46 report.
+#[tauri::command]
+pub fn get_desktop_donation_tax_report(
+    db_state: State<'_, DbState>,
+    tax_year: i32,
+) -> Result<DonationTaxReport, Ten10Error> {
+    let conn = db_state.read()?;
+    let report = query_donation_tax_report(&conn, tax_year)?;
+    println!(
+        "Desktop Query Result (tax_report_commands.rs): {} recipients in {}, deductible total {}",
+        report.recipients.len(),
+        tax_year,
+        report.deductible_total
+    );
+    Ok(report)
+}
+
+pub(crate) fn upsert_recipient_tax_deductible(
+    conn: &Connection,
+    recipient: &str,
+    tax_deductible: bool,
+) -> Ten10Result<()> {
+    let id = resolve_recipient_id(conn, Some(recipient))?
+        .ok_or_else(|| Ten10Error::validation("Recipient name must not be empty."))?;
+    conn.execute(
+        "UPDATE recipients SET tax_deductible = ?2, updated_at = ?3 WHERE id = ?1",
+        params![id, tax_deductible, Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Marks a recipient (by name or alias, as written on its donations) as a
+/// recognized institution whose donations count for the tax credit, or clears the mark.
+#[tauri::command]
+pub fn set_recipient_tax_deductible(
+    db_state: State<'_, DbState>,
+    recipient: String,
+    tax_deductible: bool,
+) -> Result<(), Ten10Error> {
+    let conn = db_state.0.lock()?;
+    upsert_recipient_tax_deductible(&conn, &recipient, tax_deductible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO transactions (id, date, amount, currency, type, recipient, original_amount, original_currency, created_at) VALUES
+                ('d0', '2023-12-31', 99900, 'ILS', 'donation', 'Yeshiva', NULL, NULL, '0'),
+                ('d1', '2024-01-05', 10000, 'ILS', 'donation', 'Yeshiva', NULL, NULL, '1'),
+                ('d2', '2024-03-05', 37000, 'ILS', 'donation', ' yeshiva ', 10000, 'USD', '2'),
+                ('d3', '2024-06-01', 5000, 'ILS', 'non_tithe_donation', 'Gemach', NULL, NULL, '3'),
+                ('d4', '2024-07-01', 2000, 'ILS', 'donation', NULL, NULL, NULL, '4'),
+                ('i1', '2024-07-01', 500000, 'ILS', 'income', 'Yeshiva', NULL, NULL, '5'),
+                ('d5', '2025-01-01', 1000, 'ILS', 'donation', 'Gemach', NULL, NULL, '6');",
+        )
+        .unwrap();
+        super::super::recipient_commands::link_unlinked_recipients(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn groups_a_tax_year_per_recipient_with_currency_breakdown() {
+        let conn = test_db();
+        upsert_recipient_tax_deductible(&conn, "YESHIVA", true).unwrap();
+
+        let report = query_donation_tax_report(&conn, 2024).unwrap();
+        assert_eq!((report.start_date.as_str(), report.end_date.as_str()), ("2024-01-01", "2024-12-31"));
+        assert_eq!(report.currency, "ILS");
+
+        let summary: Vec<_> = report
+            .recipients
+            .iter()
+            .map(|r| (r.recipient.as_deref(), r.tax_deductible, r.donation_count, r.total.to_major()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(Some("Yeshiva"), true, 2, 470.0), (Some("Gemach"), false, 1, 50.0), (None, false, 1, 20.0)]
+        );
+
+        let paid: Vec<_> = report.recipients[0]
+            .by_currency
+            .iter()
+            .map(|c| (c.currency.as_str(), c.amount.to_major(), c.donation_count))
+            .collect();
+        assert_eq!(paid, vec![("ILS", 100.0, 1), ("USD", 100.0, 1)]);
+
+        assert_eq!(report.total.to_major(), 540.0);
+        assert_eq!(report.deductible_total.to_major(), 470.0);
+        assert_eq!(report.non_deductible_total.to_major(), 70.0);
+    }
+
+    #[test]
+    fn tax_deductible_flag_is_set_by_name() {
+        let conn = test_db();
+        upsert_recipient_tax_deductible(&conn, " Gemach ", true).unwrap();
+        upsert_recipient_tax_deductible(&conn, "gemach", false).unwrap();
+        let rows: Vec<(String, bool)> = conn
+            .prepare("SELECT name, tax_deductible FROM recipients WHERE name LIKE 'gem%'")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows, vec![("Gemach".to_string(), false)]);
+
+        assert!(matches!(upsert_recipient_tax_deductible(&conn, "  ", true), Err(Ten10Error::Validation(_))));
+        assert!(matches!(query_donation_tax_report(&conn, 0), Err(Ten10Error::Validation(_))));
+    }
+
+    #[test]
+    fn totals_scale_each_row_by_its_own_currency() {
+        let conn = test_db();
+        conn.execute(
+            "INSERT INTO transactions (id, date, amount, currency, type, recipient, created_at)
+             VALUES ('d6', '2024-08-01', 1500, 'JPY', 'donation', 'Gemach', '7')",
+            [],
+        )
+        .unwrap();
+        super::super::recipient_commands::link_unlinked_recipients(&conn).unwrap();
+        upsert_recipient_tax_deductible(&conn, "Yeshiva", true).unwrap();
+
+        let report = query_donation_tax_report(&conn, 2024).unwrap();
+        let gemach = report.recipients.iter().find(|r| r.recipient.as_deref() == Some("Gemach")).unwrap();
+        assert_eq!((gemach.donation_count, gemach.total.to_major()), (2, 1550.0));
+        assert_eq!((report.deductible_total.to_major(), report.non_deductible_total.to_major()), (470.0, 1570.0));
+        assert_eq!(report.total.to_major(), 2040.0);
+    }
+}
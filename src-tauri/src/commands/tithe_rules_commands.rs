@@ -0,0 +1,87 @@
+// src-tauri/src/commands/tithe_rules_commands.rs
+// CRUD for the versioned tithe rules. Changing the rules means saving a new
+// version with an effective-from date; earlier transactions keep the version
+// that was in force on their own date.
+
+use chrono::{NaiveDate, Utc};
+use rusqlite::params;
+use tauri::State;
+
+use crate::models::TitheRules;
+use crate::DbState;
+use crate::error::Ten10Error;
+
+/// All rule versions, oldest first. The first one is the base version covering all earlier dates.
+#[tauri::command]
+pub fn get_tithe_rules(db_state: State<'_, DbState>) -> Result<Vec<TitheRules>, Ten10Error> {
+    let conn = db_state.read()?;
+    let mut stmt = conn.prepare("SELECT * FROM tithe_rules ORDER BY effective_from")?;
+    let rows = stmt
+        .query_map([], TitheRules::from_row)?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(Ten10Error::from)
+}
+
+/// Saves a rule version. A version with the same `effective_from` is replaced;
+/// otherwise a new version starts on that date.
+#[tauri::command]
+pub fn save_tithe_rules(db_state: State<'_, DbState>, rules: TitheRules) -> Result<TitheRules, Ten10Error> {
+    NaiveDate::parse_from_str(&rules.effective_from, "%Y-%m-%d")
+        .map_err(|e| Ten10Error::validation(format!("Invalid effective_from '{}': {}", rules.effective_from, e)))?;
+    for (name, value) in [
+        ("maaser_percentage", rules.maaser_percentage),
+        ("chomesh_percentage", rules.chomesh_percentage),
+    ] {
+        if !(0.0..=100.0).contains(&value) {
+            return Err(Ten10Error::validation(format!("{} must be between 0 and 100, got {}", name, value)));
+        }
+    }
+
+    let conn = db_state.0.lock()?;
+    conn.execute(
+        "INSERT INTO tithe_rules
+            (effective_from, maaser_percentage, chomesh_percentage, include_exempt_income, deduct_recognized_expenses, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(effective_from) DO UPDATE SET
+            maaser_percentage = excluded.maaser_percentage,
+            chomesh_percentage = excluded.chomesh_percentage,
+            include_exempt_income = excluded.include_exempt_income,
+            deduct_recognized_expenses = excluded.deduct_recognized_expenses",
+        params![
+            rules.effective_from,
+            rules.maaser_percentage,
+            rules.chomesh_percentage,
+            rules.include_exempt_income,
+            rules.deduct_recognized_expenses,
+            Utc::now().to_rfc3339(),
+        ],
+    )?;
+
+    conn.query_row(
+        "SELECT * FROM tithe_rules WHERE effective_from = ?1",
+        params![rules.effective_from],
+        TitheRules::from_row,
+    )
+    .map_err(Ten10Error::from)
+}
+
+/// Deletes a rule version; transactions in its period fall back to the previous version.
+/// The base (earliest) version cannot be deleted.
+#[tauri::command]
+pub fn delete_tithe_rules(db_state: State<'_, DbState>, id: i64) -> Result<(), Ten10Error> {
+    let conn = db_state.0.lock()?;
+    let base_id: i64 = conn
+        .query_row(
+            "SELECT id FROM tithe_rules ORDER BY effective_from LIMIT 1",
+            [],
+            |row| row.get(0),
+        )?;
+    if base_id == id {
+        return Err(Ten10Error::validation("The base tithe rules version cannot be deleted."));
+    }
+
+    match conn.execute("DELETE FROM tithe_rules WHERE id = ?1", params![id]) {
+        Ok(0) => Err(Ten10Error::not_found("TitheRules", id.to_string())),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
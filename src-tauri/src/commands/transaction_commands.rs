@@ -1,11 +1,20 @@
 // src-tauri/src/commands/transaction_commands.rs
 
 use crate::DbState;
+use crate::commands::budget_commands::{budget_alerts_for, notify_budget_alerts};
+use crate::commands::recipient_commands::resolve_recipient_id;
 use crate::models::{RecurringInfo, Transaction, TransactionForTable};
-use rusqlite::{params, ToSql};
+use crate::error::Ten10Error;
+use crate::commands::category_commands::category_tree_ctes;
+use crate::commands::exchange_rate_commands::{resolve_rate, RateLookupOptions};
+use crate::money::{minor_unit_scale_sql, Money};
+use crate::search::{fts_match_clause, fts_match_query, FTS_JOIN};
+use crate::transaction_types::category_type_group;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, OptionalExtension, ToSql};
 use rusqlite::Result as RusqliteResult;
 use serde::{Deserialize, Serialize};
-use tauri::State; // Assuming DbState is in lib.rs or main.rs and accessible
+use tauri::{AppHandle, State}; // Assuming DbState is in lib.rs or main.rs and accessible
 
 #[derive(Deserialize, Debug)]
 pub struct TransactionUpdatePayload {
@@ -14,7 +23,7 @@ pub struct TransactionUpdatePayload {
     // but only include fields that can actually be updated.
     // All fields should be Option<T> because the frontend might only send changed fields.
     pub date: Option<String>,
-    pub amount: Option<f64>,
+    pub amount: Option<Money>,
     pub currency: Option<String>,
     pub description: Option<String>,
     #[serde(rename = "type")]
@@ -23,7 +32,7 @@ pub struct TransactionUpdatePayload {
     pub is_chomesh: Option<bool>,
     pub recipient: Option<String>,
     pub payment_method: Option<Option<String>>,
-    pub original_amount: Option<f64>,
+    pub original_amount: Option<Money>,
     pub original_currency: Option<String>,
     pub conversion_rate: Option<f64>,
     pub conversion_date: Option<String>,
@@ -37,27 +46,56 @@ pub fn update_transaction_handler(
     db_state: State<'_, DbState>,
     id: String,
     payload: TransactionUpdatePayload,
-) -> std::result::Result<(), String> {
+) -> Result<(), Ten10Error> {
     println!(
         "[Rust DEBUG] update_transaction_handler called for ID: {} with payload: {:?}",
         id, payload
     );
-    let conn_guard = db_state
-        .0
-        .lock()
-        .map_err(|e| format!("DB lock error: {}", e))?;
+    let conn_guard = db_state.0.lock()?;
     let conn = &*conn_guard;
 
     let mut set_clauses: Vec<String> = Vec::new();
     let mut params_dynamic: Vec<Box<dyn ToSql>> = Vec::new();
 
+    // Amounts are stored in minor units of their currency: the one in the payload, else the stored one.
+    // A currency change without a new amount keeps the stored value, rescaled to the new minor unit.
+    let (mut amount, mut original_amount) = (payload.amount, payload.original_amount);
+    let (currency, original_currency) = if amount.is_some()
+        || original_amount.is_some()
+        || payload.currency.is_some()
+        || payload.original_currency.is_some()
+    {
+        let (stored_amount, stored, stored_original_amount, stored_original): (i64, String, Option<i64>, Option<String>) =
+            conn.query_row(
+                "SELECT amount, currency, original_amount, original_currency FROM transactions WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?
+            .ok_or_else(|| Ten10Error::not_found("Transaction", id.clone()))?;
+        if amount.is_none() && payload.currency.is_some() {
+            amount = Some(Money::from_minor(stored_amount, &stored));
+        }
+        if original_amount.is_none() && payload.original_currency.is_some() {
+            if let (Some(minor), Some(currency)) = (stored_original_amount, &stored_original) {
+                original_amount = Some(Money::from_minor(minor, currency));
+            }
+        }
+        (
+            payload.currency.clone().unwrap_or(stored),
+            payload.original_currency.clone().or(stored_original).unwrap_or_default(),
+        )
+    } else {
+        Default::default()
+    };
+
     if let Some(date) = &payload.date {
         set_clauses.push("date = ?".to_string());
         params_dynamic.push(Box::new(date.clone()));
     }
-    if let Some(amount) = payload.amount {
+    if let Some(amount) = amount {
         set_clauses.push("amount = ?".to_string());
-        params_dynamic.push(Box::new(amount));
+        params_dynamic.push(Box::new(amount.to_minor(&currency)));
     }
     if let Some(currency) = &payload.currency {
         set_clauses.push("currency = ?".to_string());
@@ -87,14 +125,16 @@ pub fn update_transaction_handler(
     if payload.recipient.is_some() {
         set_clauses.push("recipient = ?".to_string());
         params_dynamic.push(Box::new(payload.recipient.clone()));
+        set_clauses.push("recipient_id = ?".to_string());
+        params_dynamic.push(Box::new(resolve_recipient_id(conn, payload.recipient.as_deref())?));
     }
     if let Some(payment_method_opt) = payload.payment_method {
         set_clauses.push("payment_method = ?".to_string());
         params_dynamic.push(Box::new(payment_method_opt));
     }
-    if let Some(original_amount) = payload.original_amount {
+    if let Some(original_amount) = original_amount {
         set_clauses.push("original_amount = ?".to_string());
-        params_dynamic.push(Box::new(original_amount));
+        params_dynamic.push(Box::new(original_amount.to_minor(&original_currency)));
     }
     if let Some(original_currency) = &payload.original_currency {
         set_clauses.push("original_currency = ?".to_string());
@@ -117,7 +157,7 @@ pub fn update_transaction_handler(
 
     if set_clauses.is_empty() {
         // Or, if only updated_at is present, perhaps return Ok(()) if that's considered a valid no-op update.
-        return Err("No updatable fields provided.".to_string());
+        return Err(Ten10Error::validation("No updatable fields provided."));
     }
 
     params_dynamic.push(Box::new(id.clone()));
@@ -132,7 +172,7 @@ pub fn update_transaction_handler(
     let params_for_rusqlite: Vec<&dyn ToSql> = params_dynamic.iter().map(|p| p.as_ref()).collect();
 
     match conn.execute(&query, params_for_rusqlite.as_slice()) {
-        Ok(0) => Err(format!("Transaction with ID {} not found.", id)), // Changed message
+        Ok(0) => Err(Ten10Error::not_found("Transaction", id)),
         Ok(affected_rows) => {
             println!(
                 "[Rust DEBUG] Successfully updated {} row(s) for ID: {}",
@@ -141,12 +181,11 @@ pub fn update_transaction_handler(
             Ok(())
         }
         Err(e) => {
-            let err_msg = format!(
+            println!(
                 "[Rust ERROR] Failed to update transaction: {}. Query: {}",
                 e, query
             );
-            println!("{}", err_msg);
-            Err(err_msg)
+            Err(e.into())
         }
     }
 }
@@ -155,127 +194,175 @@ pub fn update_transaction_handler(
 pub fn delete_transaction_handler(
     db_state: State<'_, DbState>,
     transaction_id: String,
-) -> std::result::Result<(), String> {
-    let conn_guard = db_state
-        .0
-        .lock()
-        .map_err(|e| format!("DB lock error: {}", e))?;
+) -> Result<(), Ten10Error> {
+    let conn_guard = db_state.0.lock()?;
     let conn = &*conn_guard;
 
     match conn.execute(
         "DELETE FROM transactions WHERE id = ?1",
         params![transaction_id],
     ) {
-        Ok(0) => Err(format!(
-            "Transaction with ID {} not found or not deleted.",
-            transaction_id
-        )), // No rows affected
+        Ok(0) => Err(Ten10Error::not_found("Transaction", transaction_id)), // No rows affected
         Ok(_) => Ok(()), // Successfully deleted
-        Err(e) => Err(format!("Failed to delete transaction: {}", e)),
+        Err(e) => Err(e.into()),
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
-pub struct ExportFiltersPayload {
+pub struct TransactionFiltersPayload {
     search: Option<String>,
     date_from: Option<String>,
-    date_to: Option<String>,
+    pub(crate) date_to: Option<String>,
     types: Option<Vec<String>>,
     payment_methods: Option<Vec<String>>,
+    /// Range of the amount as paid (`original_amount` when converted), in major units
+    amount_min: Option<f64>,
+    amount_max: Option<f64>,
+    /// Categories with their subcategories
+    categories: Option<Vec<String>>,
+    /// The currency paid in: `original_currency` when converted, `currency` otherwise
+    currencies: Option<Vec<String>>,
+    is_chomesh: Option<bool>,
+    recipient_ids: Option<Vec<String>>,
+    /// Only rows converted from another currency (true) or recorded as is (false)
+    has_conversion: Option<bool>,
+    /// Inclusive date ranges (YYYY-MM-DD) on the record timestamps
+    created_from: Option<String>,
+    created_to: Option<String>,
+    updated_from: Option<String>,
+    updated_to: Option<String>,
+    // Filters for recurring transactions
     show_only: Option<String>,
     recurring_statuses: Option<Vec<String>>,
     recurring_frequencies: Option<Vec<String>>,
 }
 
-#[tauri::command]
-pub fn export_transactions_handler(
-    db_state: State<'_, DbState>,
-    filters: ExportFiltersPayload,
-) -> std::result::Result<Vec<TransactionForTable>, String> {
-    println!("[Rust DEBUG] export_transactions_handler called with filters: {:?}", filters);
-
-    let conn_guard = db_state
-        .0
-        .lock()
-        .map_err(|e| format!("[Rust ERROR] DB lock error: {}", e))?;
-    let conn = &*conn_guard;
+/// Columns of `TransactionForTable` (read by `export_row`) over `t` and `rt`.
+const TRANSACTION_FOR_TABLE_COLUMNS: &str = "
+    t.id, t.user_id, t.date, t.amount, t.currency, t.description,
+    t.type, t.category, t.is_chomesh,
+    t.recipient, t.payment_method, t.created_at, t.updated_at, t.source_recurring_id,
+    t.occurrence_number,
+    t.original_amount, t.original_currency, t.conversion_rate, t.conversion_date, t.rate_source,
+    rt.status as recurring_status,
+    rt.frequency as recurring_frequency,
+    rt.execution_count as recurring_execution_count,
+    rt.total_occurrences as recurring_total_occurrences,
+    rt.day_of_month as recurring_day_of_month_def,
+    rt.start_date as recurring_start_date,
+    rt.next_due_date as recurring_next_due_date
+";
+
+/// `FROM ... WHERE ...` selecting the transactions (`t`, with their recurring
+/// definition `rt`) that match `filters`, and its parameters numbered from ?1.
+pub(crate) struct FilteredTransactions {
+    from: String,
+    conditions: Vec<String>,
+    pub params: Vec<Box<dyn ToSql>>,
+    /// Whether the full-text index is joined (as `fts`), i.e. there is a search
+    pub searching: bool,
+}
 
-    let base_query = "
-        SELECT 
-            t.id, t.user_id, t.date, t.amount, t.currency, t.description, 
-            t.type, t.category, t.is_chomesh, 
-            t.recipient, t.payment_method, t.created_at, t.updated_at, t.source_recurring_id,
-            t.occurrence_number,
-            t.original_amount, t.original_currency, t.conversion_rate, t.conversion_date, t.rate_source,
-            rt.status as recurring_status,
-            rt.frequency as recurring_frequency,
-            rt.execution_count as recurring_execution_count,
-            rt.total_occurrences as recurring_total_occurrences,
-            rt.day_of_month as recurring_day_of_month_def,
-            rt.start_date as recurring_start_date,
-            rt.next_due_date as recurring_next_due_date
-        FROM transactions t
-        LEFT JOIN recurring_transactions rt ON t.source_recurring_id = rt.id
-    ";
+impl FilteredTransactions {
+    fn param(&mut self, value: impl ToSql + 'static) -> String {
+        self.params.push(Box::new(value));
+        format!("?{}", self.params.len())
+    }
 
-    let mut where_clauses: Vec<String> = Vec::new();
-    let mut sql_params_dynamic: Vec<Box<dyn ToSql>> = Vec::new();
-    let mut current_param_idx = 1;
-
-    if let Some(search_term) = &filters.search {
-        if !search_term.is_empty() {
-            where_clauses.push(format!("(LOWER(t.description) LIKE LOWER(?{0}) OR LOWER(t.category) LIKE LOWER(?{0}) OR LOWER(t.recipient) LIKE LOWER(?{0}) OR LOWER(t.payment_method) LIKE LOWER(?{0}))", current_param_idx));
-            sql_params_dynamic.push(Box::new(format!("%{}%", search_term)));
-            current_param_idx += 1;
-        }
+    fn list(&mut self, values: &[String]) -> String {
+        values.iter().map(|v| self.param(v.clone())).collect::<Vec<_>>().join(", ")
     }
 
-    if let Some(date_from) = &filters.date_from {
-        if !date_from.is_empty() {
-            where_clauses.push(format!("t.date >= ?{}", current_param_idx));
-            sql_params_dynamic.push(Box::new(date_from.clone()));
-            current_param_idx += 1;
+    pub(crate) fn sql(&self) -> String {
+        if self.conditions.is_empty() {
+            self.from.clone()
+        } else {
+            format!("{} WHERE {}", self.from, self.conditions.join(" AND "))
         }
     }
-    if let Some(date_to) = &filters.date_to {
-        if !date_to.is_empty() {
-            where_clauses.push(format!("t.date <= ?{}", current_param_idx));
-            sql_params_dynamic.push(Box::new(date_to.clone()));
-            current_param_idx += 1;
-        }
+}
+
+/// The one place the table, its count and the exports turn filters into SQL, so
+/// they always agree on which rows match. Empty values leave a filter off.
+pub(crate) fn filter_transactions(filters: &TransactionFiltersPayload) -> FilteredTransactions {
+    let mut query = FilteredTransactions {
+        from: " FROM transactions t".to_string(),
+        conditions: Vec::new(),
+        params: Vec::new(),
+        searching: false,
+    };
+    let mut where_clauses: Vec<String> = Vec::new();
+    let non_empty = |value: &Option<String>| value.clone().filter(|v| !v.is_empty());
+    let non_empty_list = |values: &Option<Vec<String>>| values.clone().filter(|v| !v.is_empty());
+
+    if let Some(match_query) = filters.search.as_deref().and_then(fts_match_query) {
+        query.from.push_str(FTS_JOIN);
+        query.params.push(Box::new(match_query));
+        where_clauses.push(fts_match_clause(query.params.len()));
+        query.searching = true;
     }
+    query.from.push_str(" LEFT JOIN recurring_transactions rt ON t.source_recurring_id = rt.id");
 
-    if let Some(types) = &filters.types {
-        if !types.is_empty() {
-            let placeholders: Vec<String> = (0..types.len())
-                .map(|i| format!("?{}", current_param_idx + i))
-                .collect();
-            where_clauses.push(format!("t.type IN ({})", placeholders.join(", ")));
-            for t_type in types {
-                sql_params_dynamic.push(Box::new(t_type.clone()));
-            }
-            current_param_idx += types.len();
-        }
+    if let Some(date_from) = non_empty(&filters.date_from) {
+        where_clauses.push(format!("t.date >= {}", query.param(date_from)));
+    }
+    if let Some(date_to) = non_empty(&filters.date_to) {
+        where_clauses.push(format!("t.date <= {}", query.param(date_to)));
+    }
+    if let Some(types) = non_empty_list(&filters.types) {
+        where_clauses.push(format!("t.type IN ({})", query.list(&types)));
+    }
+    if let Some(payment_methods) = non_empty_list(&filters.payment_methods) {
+        where_clauses.push(format!("t.payment_method IN ({})", query.list(&payment_methods)));
     }
 
-    if let Some(payment_methods) = &filters.payment_methods {
-        if !payment_methods.is_empty() {
-            let placeholders: Vec<String> = (0..payment_methods.len())
-                .map(|i| format!("?{}", current_param_idx + i))
-                .collect();
-            where_clauses.push(format!(
-                "t.payment_method IN ({})",
-                placeholders.join(", ")
-            ));
-            for method in payment_methods {
-                sql_params_dynamic.push(Box::new(method.clone()));
-            }
-            current_param_idx += payment_methods.len();
+    // --- Structured filters ---
+    let paid_amount = format!(
+        "(COALESCE(t.original_amount, t.amount) * 1.0 / {})",
+        minor_unit_scale_sql("COALESCE(t.original_currency, t.currency)")
+    );
+    if let Some(amount_min) = filters.amount_min {
+        where_clauses.push(format!("{} >= {}", paid_amount, query.param(amount_min)));
+    }
+    if let Some(amount_max) = filters.amount_max {
+        where_clauses.push(format!("{} <= {}", paid_amount, query.param(amount_max)));
+    }
+    if let Some(categories) = non_empty_list(&filters.categories) {
+        let list = query.list(&categories);
+        where_clauses.push(format!(
+            "(t.category IN ({list}) OR t.category IN (WITH RECURSIVE {} SELECT key FROM category_lineage WHERE ancestor IN ({list})))",
+            category_tree_ctes()
+        ));
+    }
+    if let Some(currencies) = non_empty_list(&filters.currencies) {
+        where_clauses.push(format!("COALESCE(t.original_currency, t.currency) IN ({})", query.list(&currencies)));
+    }
+    if let Some(is_chomesh) = filters.is_chomesh {
+        where_clauses.push(format!("COALESCE(t.is_chomesh, 0) = {}", query.param(is_chomesh)));
+    }
+    if let Some(recipient_ids) = non_empty_list(&filters.recipient_ids) {
+        where_clauses.push(format!("t.recipient_id IN ({})", query.list(&recipient_ids)));
+    }
+    match filters.has_conversion {
+        Some(true) => where_clauses.push("t.original_currency IS NOT NULL".to_string()),
+        Some(false) => where_clauses.push("t.original_currency IS NULL".to_string()),
+        None => {}
+    }
+    // Timestamps are RFC 3339, so a day ends before the next one starts.
+    for (column, from, to) in [
+        ("t.created_at", &filters.created_from, &filters.created_to),
+        ("t.updated_at", &filters.updated_from, &filters.updated_to),
+    ] {
+        if let Some(from) = non_empty(from) {
+            where_clauses.push(format!("{} >= {}", column, query.param(from)));
+        }
+        if let Some(to) = non_empty(to) {
+            where_clauses.push(format!("{} < date({}, '+1 day')", column, query.param(to)));
         }
     }
 
-    // --- Apply Recurring Transaction Filters for Export ---
+    // --- Recurring Transaction Filters ---
     if let Some(show_only) = &filters.show_only {
         match show_only.as_str() {
             "recurring" => {
@@ -284,79 +371,75 @@ pub fn export_transactions_handler(
             "regular" => {
                 where_clauses.push("t.source_recurring_id IS NULL".to_string());
             }
-            _ => {} // "all" or any other value means no filter on this
+            _ => {} // "all" or any other value means no filter
         }
     }
-
-    if let Some(statuses) = &filters.recurring_statuses {
-        if !statuses.is_empty() {
-            let placeholders: Vec<String> = (0..statuses.len())
-                .map(|i| format!("?{}", current_param_idx + i))
-                .collect();
-            where_clauses.push(format!("rt.status IN ({})", placeholders.join(", ")));
-            for status in statuses {
-                sql_params_dynamic.push(Box::new(status.clone()));
-            }
-            current_param_idx += statuses.len();
-        }
+    if let Some(statuses) = non_empty_list(&filters.recurring_statuses) {
+        where_clauses.push(format!("rt.status IN ({})", query.list(&statuses)));
     }
-
-    if let Some(frequencies) = &filters.recurring_frequencies {
-        if !frequencies.is_empty() {
-            let placeholders: Vec<String> = (0..frequencies.len())
-                .map(|i| format!("?{}", current_param_idx + i))
-                .collect();
-            where_clauses.push(format!("rt.frequency IN ({})", placeholders.join(", ")));
-            for freq in frequencies {
-                sql_params_dynamic.push(Box::new(freq.clone()));
-            }
-        }
+    if let Some(frequencies) = non_empty_list(&filters.recurring_frequencies) {
+        where_clauses.push(format!("rt.frequency IN ({})", query.list(&frequencies)));
     }
 
-    let mut final_query = base_query.to_string();
-    if !where_clauses.is_empty() {
-        final_query.push_str(" WHERE ");
-        final_query.push_str(&where_clauses.join(" AND "));
-    }
+    query.conditions = where_clauses;
+    query
+}
 
-    final_query.push_str(" ORDER BY t.date DESC, t.created_at DESC");
+/// SELECT (with recurring definition columns) and parameters for an export with `filters`,
+/// newest first. Shared by the JSON and file exports.
+pub(crate) fn build_export_query(filters: &TransactionFiltersPayload) -> (String, Vec<Box<dyn ToSql>>) {
+    let filtered = filter_transactions(filters);
+    let final_query = format!(
+        "SELECT {} {} ORDER BY t.date DESC, t.created_at DESC",
+        TRANSACTION_FOR_TABLE_COLUMNS,
+        filtered.sql()
+    );
+    (final_query, filtered.params)
+}
 
+/// Maps one row of `build_export_query` to a transaction with its recurring info.
+pub(crate) fn export_row(row: &rusqlite::Row<'_>) -> RusqliteResult<TransactionForTable> {
+    let transaction = crate::models::Transaction::from_row(row)?;
+    let recurring_info = match row.get::<_, Option<String>>("recurring_status")? {
+        Some(status) => Some(RecurringInfo {
+            status,
+            frequency: row.get("recurring_frequency")?,
+            execution_count: row.get("recurring_execution_count")?,
+            total_occurrences: row.get("recurring_total_occurrences").ok(),
+            day_of_month: row.get("recurring_day_of_month_def")?,
+            start_date: row.get("recurring_start_date")?,
+            next_due_date: row.get("recurring_next_due_date")?,
+        }),
+        None => None,
+    };
+    Ok(TransactionForTable {
+        transaction,
+        recurring_info,
+    })
+}
+
+#[tauri::command]
+pub fn export_transactions_handler(
+    db_state: State<'_, DbState>,
+    filters: TransactionFiltersPayload,
+) -> Result<Vec<TransactionForTable>, Ten10Error> {
+    println!("[Rust DEBUG] export_transactions_handler called with filters: {:?}", filters);
+
+    let conn_guard = db_state.read()?;
+    let conn = &*conn_guard;
+
+    let (final_query, sql_params_dynamic) = build_export_query(&filters);
     println!("[Rust DEBUG] Export query: {}", final_query);
 
     let params_for_rusqlite: Vec<&dyn ToSql> = sql_params_dynamic.iter().map(|p| p.as_ref()).collect();
 
-    let mut stmt = conn
-        .prepare(&final_query)
-        .map_err(|e| format!("[Rust ERROR] Failed to prepare statement: {}", e))?;
+    let mut stmt = conn.prepare(&final_query)?;
     
-    let transactions_iter = stmt
-        .query_map(params_for_rusqlite.as_slice(), |row| {
-            let transaction = crate::models::Transaction::from_row(row)?;
-            let recurring_info = match row.get::<_, Option<String>>("recurring_status")? {
-                Some(status) => Some(RecurringInfo {
-                    status,
-                    frequency: row.get("recurring_frequency")?,
-                    execution_count: row.get("recurring_execution_count")?,
-                    total_occurrences: row.get("recurring_total_occurrences").ok(),
-                    day_of_month: row.get("recurring_day_of_month_def")?,
-                    start_date: row.get("recurring_start_date")?,
-                    next_due_date: row.get("recurring_next_due_date")?,
-                }),
-                None => None,
-            };
-            Ok(TransactionForTable {
-                transaction,
-                recurring_info,
-            })
-        })
-        .map_err(|e| format!("[Rust ERROR] Failed to query transactions for export: {}", e))?;
+    let transactions_iter = stmt.query_map(params_for_rusqlite.as_slice(), export_row)?;
 
     let mut transactions_vec = Vec::new();
     for transaction_result in transactions_iter {
-        transactions_vec.push(
-            transaction_result
-                .map_err(|e| format!("[Rust ERROR] Failed to map transaction row for export: {}", e))?,
-        );
+        transactions_vec.push(transaction_result?);
     }
 
     println!("[Rust DEBUG] Export successful. Found {} transactions.", transactions_vec.len());
@@ -365,22 +448,82 @@ pub fn export_transactions_handler(
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
-pub struct TableFiltersPayload {
-    search: Option<String>,
-    date_from: Option<String>,
-    date_to: Option<String>,
-    types: Option<Vec<String>>,
-    payment_methods: Option<Vec<String>>,
-    // Filters for recurring transactions
-    show_only: Option<String>,
-    recurring_statuses: Option<Vec<String>>,
-    recurring_frequencies: Option<Vec<String>>,
-}
-
-#[derive(Deserialize, Debug)]
 pub struct TablePaginationPayload {
-    page: usize,  // Current page number (1-indexed from frontend)
+    #[serde(default = "first_page")]
+    page: usize,  // Current page number (1-indexed from frontend), ignored with a cursor
     limit: usize, // Items per page
+    /// `next_cursor` or `prev_cursor` of a previous response, for the same filters and sorting
+    #[serde(default)]
+    cursor: Option<String>,
+    /// Counting every match costs a scan; without it only `has_more` is known
+    #[serde(default = "include_total_by_default")]
+    include_total: bool,
+}
+
+fn first_page() -> usize {
+    1
+}
+
+fn include_total_by_default() -> bool {
+    true
+}
+
+/// Which side of its row a cursor continues on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum CursorSide {
+    After,
+    Before,
+}
+
+/// A keyset pagination cursor: the sort key and id of the row a page continues
+/// from, and the ordering they belong to. Sent to the frontend hex-encoded.
+#[derive(Serialize, Deserialize, Debug)]
+struct PageCursor {
+    order: String,
+    side: CursorSide,
+    key: serde_json::Value,
+    id: String,
+}
+
+impl PageCursor {
+    fn new(order: &str, side: CursorSide, key: &SqlValue, id: &str) -> Self {
+        let key = match key {
+            SqlValue::Integer(i) => serde_json::Value::from(*i),
+            SqlValue::Real(f) => serde_json::Value::from(*f),
+            SqlValue::Text(text) => serde_json::Value::from(text.as_str()),
+            SqlValue::Null | SqlValue::Blob(_) => serde_json::Value::Null,
+        };
+        PageCursor { order: order.to_string(), side, key, id: id.to_string() }
+    }
+
+    fn encode(&self) -> String {
+        serde_json::to_vec(self).unwrap_or_default().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// The cursor in `encoded`, which must have been taken under `order`.
+    fn decode(encoded: &str, order: &str) -> Result<Self, Ten10Error> {
+        let invalid = || Ten10Error::validation("Invalid or outdated page cursor.");
+        let bytes = (0..encoded.len())
+            .step_by(2)
+            .map(|i| encoded.get(i..i + 2).and_then(|hex| u8::from_str_radix(hex, 16).ok()))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+        let cursor: PageCursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+        if cursor.order != order {
+            return Err(invalid());
+        }
+        Ok(cursor)
+    }
+
+    fn key_param(&self) -> SqlValue {
+        match &self.key {
+            serde_json::Value::Number(n) => n.as_i64().map_or_else(|| SqlValue::Real(n.as_f64().unwrap_or(0.0)), SqlValue::Integer),
+            serde_json::Value::String(text) => SqlValue::Text(text.clone()),
+            serde_json::Value::Bool(b) => SqlValue::Integer(*b as i64),
+            _ => SqlValue::Null,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -391,7 +534,7 @@ pub struct TableSortingPayload {
 
 #[derive(Deserialize, Debug)]
 pub struct GetFilteredTransactionsArgs {
-    filters: TableFiltersPayload,
+    filters: TransactionFiltersPayload,
     pagination: TablePaginationPayload,
     sorting: TableSortingPayload,
 }
@@ -400,251 +543,135 @@ pub struct GetFilteredTransactionsArgs {
 #[serde(rename_all = "camelCase")]
 pub struct PaginatedTransactionsResponse {
     transactions: Vec<TransactionForTable>,
-    total_count: i64,
+    /// None when the request skipped counting (`include_total: false`)
+    total_count: Option<i64>,
+    /// Whether rows follow this page
+    has_more: bool,
+    next_cursor: Option<String>,
+    prev_cursor: Option<String>,
 }
 
 #[tauri::command]
 pub fn get_filtered_transactions_handler(
     db_state: State<'_, DbState>,
     args: GetFilteredTransactionsArgs,
-) -> std::result::Result<PaginatedTransactionsResponse, String> {
+) -> Result<PaginatedTransactionsResponse, Ten10Error> {
     let filters = args.filters;
     let pagination = args.pagination;
     let sorting = args.sorting;
 
     println!("[Rust DEBUG] get_filtered_transactions_handler called with filters: {:?}, pagination: {:?}, sorting: {:?}", filters, pagination, sorting);
 
-    let conn_guard = db_state
-        .0
-        .lock()
-        .map_err(|e| format!("[Rust ERROR] DB lock error: {}", e))?;
+    let conn_guard = db_state.read()?;
     let conn = &*conn_guard;
 
-    let base_select = "
-        SELECT 
-            t.id, t.user_id, t.date, t.amount, t.currency, t.description, 
-            t.type, t.category, t.is_chomesh, 
-            t.recipient, t.payment_method, t.created_at, t.updated_at, t.source_recurring_id,
-            t.occurrence_number,
-            t.original_amount, t.original_currency, t.conversion_rate, t.conversion_date, t.rate_source,
-            rt.status as recurring_status,
-            rt.frequency as recurring_frequency,
-            rt.execution_count as recurring_execution_count,
-            rt.total_occurrences as recurring_total_occurrences,
-            rt.day_of_month as recurring_day_of_month_def,
-            rt.start_date as recurring_start_date,
-            rt.next_due_date as recurring_next_due_date
-    ";
-    let base_from = "
-        FROM transactions t
-        LEFT JOIN recurring_transactions rt ON t.source_recurring_id = rt.id
-    ";
-
-    let mut where_clauses: Vec<String> = Vec::new();
-    let mut sql_params_dynamic: Vec<Box<dyn ToSql>> = Vec::new();
-    let mut current_param_idx = 1;
-
-    if let Some(search_term) = &filters.search {
-        if !search_term.is_empty() {
-            where_clauses.push(format!("(LOWER(t.description) LIKE LOWER(?{0}) OR LOWER(t.category) LIKE LOWER(?{0}) OR LOWER(t.recipient) LIKE LOWER(?{0}) OR LOWER(t.payment_method) LIKE LOWER(?{0}))", current_param_idx));
-            sql_params_dynamic.push(Box::new(format!("%{}%", search_term)));
-            current_param_idx += 1;
-        }
-    }
-
-    if let Some(date_from) = &filters.date_from {
-        if !date_from.is_empty() {
-            where_clauses.push(format!("t.date >= ?{}", current_param_idx));
-            sql_params_dynamic.push(Box::new(date_from.clone()));
-            current_param_idx += 1;
-        }
-    }
-    if let Some(date_to) = &filters.date_to {
-        if !date_to.is_empty() {
-            where_clauses.push(format!("t.date <= ?{}", current_param_idx));
-            sql_params_dynamic.push(Box::new(date_to.clone()));
-            current_param_idx += 1;
-        }
-    }
-
-    if let Some(types) = &filters.types {
-        if !types.is_empty() {
-            let placeholders: Vec<String> = (0..types.len())
-                .map(|i| format!("?{}", current_param_idx + i))
-                .collect();
-            where_clauses.push(format!("t.type IN ({})", placeholders.join(", ")));
-            for t_type in types {
-                sql_params_dynamic.push(Box::new(t_type.clone()));
-            }
-            current_param_idx += types.len();
-        }
-    }
-
-    if let Some(payment_methods) = &filters.payment_methods {
-        if !payment_methods.is_empty() {
-            let placeholders: Vec<String> = (0..payment_methods.len())
-                .map(|i| format!("?{}", current_param_idx + i))
-                .collect();
-            where_clauses.push(format!(
-                "t.payment_method IN ({})",
-                placeholders.join(", ")
-            ));
-            for method in payment_methods {
-                sql_params_dynamic.push(Box::new(method.clone()));
-            }
-            current_param_idx += payment_methods.len();
-        }
-    }
-
-    // --- New Recurring Transaction Filters ---
-    if let Some(show_only) = &filters.show_only {
-        match show_only.as_str() {
-            "recurring" => {
-                where_clauses.push("t.source_recurring_id IS NOT NULL".to_string());
-            }
-            "regular" => {
-                where_clauses.push("t.source_recurring_id IS NULL".to_string());
-            }
-            _ => {} // "all" or any other value means no filter
-        }
-    }
-    
-    if let Some(statuses) = &filters.recurring_statuses {
-        if !statuses.is_empty() {
-            let placeholders: Vec<String> = (0..statuses.len())
-                .map(|i| format!("?{}", current_param_idx + i))
-                .collect();
-            where_clauses.push(format!("rt.status IN ({})", placeholders.join(", ")));
-            for status in statuses {
-                sql_params_dynamic.push(Box::new(status.clone()));
-            }
-            current_param_idx += statuses.len();
-        }
-    }
-
-    if let Some(frequencies) = &filters.recurring_frequencies {
-        if !frequencies.is_empty() {
-            let placeholders: Vec<String> = (0..frequencies.len())
-                .map(|i| format!("?{}", current_param_idx + i))
-                .collect();
-            where_clauses.push(format!("rt.frequency IN ({})", placeholders.join(", ")));
-            for frequency in frequencies {
-                sql_params_dynamic.push(Box::new(frequency.clone()));
-            }
-            current_param_idx += frequencies.len();
-        }
-    }
+    let mut filtered = filter_transactions(&filters);
 
-    let where_clause_str = if !where_clauses.is_empty() {
-        format!(" WHERE {}", where_clauses.join(" AND "))
+    let total_count = if pagination.include_total {
+        let query_string_for_count = format!("SELECT COUNT(*) {}", filtered.sql());
+        println!("[Rust DEBUG] Count Query: {}", query_string_for_count);
+        let params_for_rusqlite: Vec<&dyn ToSql> = filtered.params.iter().map(|p| p.as_ref()).collect();
+        let total_count: i64 =
+            conn.query_row(&query_string_for_count, params_for_rusqlite.as_slice(), |row| row.get(0))?;
+        println!("[Rust DEBUG] Total count: {}", total_count);
+        Some(total_count)
     } else {
-        "".to_string()
+        None
     };
 
-    let query_string_for_count = format!(
-        "SELECT COUNT(t.id) {} {}",
-        base_from, where_clause_str
-    );
-
-    println!("[Rust DEBUG] Count Query: {}", query_string_for_count);
-    let params_for_rusqlite: Vec<&dyn ToSql> = sql_params_dynamic.iter().map(|p| p.as_ref()).collect();
-
-    let total_count: i64 = conn
-        .query_row(
-            &query_string_for_count,
-            params_for_rusqlite.as_slice(),
-            |row| row.get(0),
-        )
-        .map_err(|e| e.to_string())?;
-
-    println!("[Rust DEBUG] Total count: {}", total_count);
-
-    sql_params_dynamic.push(Box::new(pagination.limit));
-    sql_params_dynamic.push(Box::new((pagination.page - 1) * pagination.limit));
-
-    // Map frontend field names to proper table-qualified column names
-    let sort_field = match sorting.field.as_str() {
+    // Map frontend field names to proper table-qualified sort keys. Nullable
+    // columns sort as '' / 0 so that the keyset comparison below holds for every row.
+    let sort_key = match sorting.field.as_str() {
         "date" => "t.date",
         "amount" => "t.amount",
-        "description" => "t.description",
+        "description" => "COALESCE(t.description, '')",
         "currency" => "t.currency",
         "type" => "t.type",
-        "category" => "t.category",
-        "recipient" => "t.recipient",
-        "payment_method" => "t.payment_method",
-        "is_chomesh" => "t.is_chomesh",
-        "created_at" => "t.created_at",
-        "updated_at" => "t.updated_at",
-        _ => "t.created_at", // Default fallback
+        "category" => "COALESCE(t.category, '')",
+        "recipient" => "COALESCE(t.recipient, '')",
+        "payment_method" => "COALESCE(t.payment_method, '')",
+        "is_chomesh" => "COALESCE(t.is_chomesh, 0)",
+        "created_at" => "COALESCE(t.created_at, '')",
+        "updated_at" => "COALESCE(t.updated_at, '')",
+        // Best match first (FTS5 rank is lower for better matches), only while searching
+        "relevance" if filtered.searching => "fts.rank",
+        _ => "COALESCE(t.created_at, '')", // Default fallback
     };
 
-    // Whitelist the sort direction — like sort_field above, user input must
+    // Whitelist the sort direction — like sort_key above, user input must
     // never be interpolated into the SQL string (defaults to ASC).
-    let sort_direction = if sorting.direction.eq_ignore_ascii_case("desc") {
-        "DESC"
-    } else {
-        "ASC"
+    let descending = sorting.direction.eq_ignore_ascii_case("desc");
+    let order = format!("{} {}", sort_key, if descending { "DESC" } else { "ASC" });
+
+    // Keyset pagination: rows strictly after (or before) the cursor row in
+    // (sort key, id) order. A previous page is read backwards and reversed.
+    let cursor = match pagination.cursor.as_deref().filter(|c| !c.is_empty()) {
+        Some(encoded) => Some(PageCursor::decode(encoded, &order)?),
+        None => None,
     };
+    let backwards = cursor.as_ref().is_some_and(|c| c.side == CursorSide::Before);
+    let (comparison, read_direction) = if descending != backwards { ("<", "DESC") } else { (">", "ASC") };
+    if let Some(cursor) = &cursor {
+        let key = filtered.param(cursor.key_param());
+        let id = filtered.param(cursor.id.clone());
+        filtered.conditions.push(format!("({}, t.id) {} ({}, {})", sort_key, comparison, key, id));
+    }
+    let offset = if cursor.is_some() { 0 } else { pagination.page.saturating_sub(1) * pagination.limit };
+    // One row more than the page tells whether another page follows.
+    let limit = filtered.param(pagination.limit as i64 + 1);
+    let offset_param = filtered.param(offset as i64);
 
     let query_string_for_data = format!(
-        "{} {} {} ORDER BY {} {} LIMIT ?{} OFFSET ?{}",
-        base_select,
-        base_from,
-        where_clause_str,
-        sort_field,
-        sort_direction,
-        current_param_idx,
-        current_param_idx + 1
+        "SELECT {}, {} AS page_sort_key {} ORDER BY {} {read_direction}, t.id {read_direction} LIMIT {} OFFSET {}",
+        TRANSACTION_FOR_TABLE_COLUMNS,
+        sort_key,
+        filtered.sql(),
+        sort_key,
+        limit,
+        offset_param
     );
 
     println!("[Rust DEBUG] Data Query: {}", query_string_for_data);
-    let params_for_rusqlite: Vec<&dyn ToSql> = sql_params_dynamic.iter().map(|p| p.as_ref()).collect();
-
-    let mut stmt = conn.prepare(&query_string_for_data).map_err(|e| e.to_string())?;
-    let transactions_iter = stmt
-        .query_map(params_for_rusqlite.as_slice(), |row| {
-            let recurring_status: Option<String> = row.get("recurring_status")?;
-            
-            let recurring_info = if recurring_status.is_some() {
-                Some(RecurringInfo {
-                    status: recurring_status.unwrap(),
-                    frequency: row.get("recurring_frequency")?,
-                    execution_count: row.get("recurring_execution_count")?,
-                    total_occurrences: row.get("recurring_total_occurrences")?,
-                    day_of_month: row.get("recurring_day_of_month_def")?,
-                    start_date: row.get("recurring_start_date")?,
-                    next_due_date: row.get("recurring_next_due_date")?,
-                })
-            } else {
-                None
-            };
-
-            Ok(TransactionForTable {
-                transaction: Transaction::from_row(row)?,
-                recurring_info,
-            })
-        })
-    .map_err(|e| e.to_string())?;
-
-    let mut transactions = Vec::new();
-    for tr in transactions_iter {
-        transactions.push(tr.map_err(|e| e.to_string())?);
+    let params_for_rusqlite: Vec<&dyn ToSql> = filtered.params.iter().map(|p| p.as_ref()).collect();
+
+    let mut stmt = conn.prepare(&query_string_for_data)?;
+    let rows = stmt.query_map(params_for_rusqlite.as_slice(), |row| {
+        Ok((export_row(row)?, row.get::<_, SqlValue>("page_sort_key")?))
+    })?;
+    let mut rows = rows.collect::<RusqliteResult<Vec<_>>>()?;
+
+    let read_more = rows.len() > pagination.limit;
+    rows.truncate(pagination.limit);
+    if backwards {
+        rows.reverse();
     }
+    let (has_more, has_previous) = if backwards { (true, read_more) } else { (read_more, cursor.is_some() || offset > 0) };
+    let cursor_at = |index: Option<usize>, side: CursorSide| {
+        index
+            .and_then(|i| rows.get(i))
+            .map(|(tr, key)| PageCursor::new(&order, side, key, &tr.transaction.id).encode())
+    };
+    let next_cursor = if has_more { cursor_at(rows.len().checked_sub(1), CursorSide::After) } else { None };
+    let prev_cursor = if has_previous { cursor_at(Some(0), CursorSide::Before) } else { None };
+    let transactions: Vec<TransactionForTable> = rows.into_iter().map(|(tr, _)| tr).collect();
 
     println!("[Rust DEBUG] Number of transactions fetched: {}", transactions.len());
-    println!("[Rust DEBUG] Returning PaginatedTransactionsResponse: total_count: {}, transactions_count: {}", total_count, transactions.len());
+    println!("[Rust DEBUG] Returning PaginatedTransactionsResponse: total_count: {:?}, transactions_count: {}, has_more: {}", total_count, transactions.len(), has_more);
 
     Ok(PaginatedTransactionsResponse {
         transactions,
         total_count,
+        has_more,
+        next_cursor,
+        prev_cursor,
     })
 }
 
 #[tauri::command]
 pub fn get_transactions_count(
     db_state: State<'_, DbState>,
-) -> std::result::Result<i64, String> {
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
+) -> Result<i64, Ten10Error> {
+    let conn_guard = db_state.read()?;
     let conn = &*conn_guard;
 
     // Use count with limit 1 logic for maximum speed if we just need existence,
@@ -655,7 +682,7 @@ pub fn get_transactions_count(
         "SELECT COUNT(*) FROM transactions",
         [],
         |row| row.get(0),
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     Ok(count)
 }
@@ -666,13 +693,13 @@ pub(crate) fn insert_transaction_row(
     transaction: &Transaction,
 ) -> RusqliteResult<()> {
     conn.execute(
-        "INSERT INTO transactions (id, user_id, date, amount, currency, description, type, category, is_chomesh, recipient, payment_method, created_at, updated_at, source_recurring_id, original_amount, original_currency, conversion_rate, conversion_date, rate_source)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+        "INSERT INTO transactions (id, user_id, date, amount, currency, description, type, category, is_chomesh, recipient, payment_method, created_at, updated_at, source_recurring_id, occurrence_number, original_amount, original_currency, conversion_rate, conversion_date, rate_source, recipient_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
         params![
             &transaction.id,
             &transaction.user_id,
             &transaction.date,
-            &transaction.amount,
+            transaction.amount.to_minor(&transaction.currency),
             &transaction.currency,
             &transaction.description,
             &transaction.transaction_type,
@@ -683,54 +710,51 @@ pub(crate) fn insert_transaction_row(
             &transaction.created_at,
             &transaction.updated_at,
             &transaction.source_recurring_id,
-            &transaction.original_amount,
+            &transaction.occurrence_number,
+            transaction
+                .original_amount
+                .map(|m| m.to_minor(transaction.original_currency.as_deref().unwrap_or_default())),
             &transaction.original_currency,
             &transaction.conversion_rate,
             &transaction.conversion_date,
             &transaction.rate_source,
+            resolve_recipient_id(conn, transaction.recipient.as_deref())?,
         ],
     )?;
     Ok(())
 }
 
+/// Saves a transaction and, when it pushes a budget past 80% or 100%, shows an alert.
 #[tauri::command]
-pub async fn add_transaction(db: State<'_, DbState>, transaction: Transaction) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    insert_transaction_row(&conn, &transaction).map_err(|e| e.to_string())?;
+pub async fn add_transaction(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    transaction: Transaction,
+) -> Result<(), Ten10Error> {
+    let conn = db.0.lock()?;
+    insert_transaction_row(&conn, &transaction)?;
+    // The transaction is saved; a failed budget check only costs the alert.
+    let alerts = budget_alerts_for(&conn, &transaction).unwrap_or_else(|e| {
+        eprintln!("[Budgets] Alert check failed: {}", e);
+        Vec::new()
+    });
+    drop(conn);
+    notify_budget_alerts(&app, &alerts);
     Ok(())
 }
 
+/// The latest rate of `from_currency` → `to_currency` up to today: from the
+/// exchange-rate store, else off the latest converted transaction.
 #[tauri::command]
 pub fn get_last_known_rate(
     db_state: State<'_, DbState>,
     from_currency: String,
     to_currency: String,
-) -> std::result::Result<Option<f64>, String> {
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
-    let conn = &*conn_guard;
-
-    // We want the latest transaction that has a conversion rate for this pair.
-    // The `transactions` table has `original_currency` and `currency` (which is the target, usually default).
-    // So we look for `original_currency = from` and `currency = to`.
-    // And `conversion_rate` IS NOT NULL.
-    
-    let query = "
-        SELECT conversion_rate 
-        FROM transactions 
-        WHERE original_currency = ?1 AND currency = ?2 AND conversion_rate IS NOT NULL
-        ORDER BY date DESC, created_at DESC 
-        LIMIT 1
-    ";
-
-    let mut stmt = conn.prepare(query).map_err(|e| e.to_string())?;
-    let mut rows = stmt.query(params![from_currency, to_currency]).map_err(|e| e.to_string())?;
-
-    if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-        let rate: f64 = row.get(0).map_err(|e| e.to_string())?;
-        Ok(Some(rate))
-    } else {
-        Ok(None)
-    }
+) -> Result<Option<f64>, Ten10Error> {
+    let conn = db_state.read()?;
+    let today = chrono::Local::now().date_naive();
+    let resolved = resolve_rate(&conn, &from_currency, &to_currency, today, &RateLookupOptions::default())?;
+    Ok(resolved.map(|r| r.rate))
 }
 
 /// Get distinct categories that the user has used for a specific transaction type.
@@ -743,18 +767,13 @@ pub fn get_last_known_rate(
 pub fn get_distinct_categories(
     db_state: State<'_, DbState>,
     transaction_type: String,
-) -> std::result::Result<Vec<String>, String> {
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<String>, Ten10Error> {
+    let conn_guard = db_state.read()?;
     let conn = &*conn_guard;
 
     // Map base type to include derived types
     // This ensures categories are shared across base and derived types
-    let types_to_query: Vec<&str> = match transaction_type.as_str() {
-        "income" => vec!["income", "exempt-income"],
-        "expense" => vec!["expense", "recognized-expense"],
-        "donation" => vec!["donation", "non_tithe_donation"],
-        _ => vec![transaction_type.as_str()],
-    };
+    let types_to_query = category_type_group(&transaction_type);
 
     // Build query with IN clause for multiple types
     let placeholders: Vec<String> = (1..=types_to_query.len())
@@ -768,7 +787,7 @@ pub fn get_distinct_categories(
         placeholders.join(", ")
     );
 
-    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&query)?;
     
     // Convert types to rusqlite params
     let params: Vec<&dyn rusqlite::ToSql> = types_to_query
@@ -777,12 +796,11 @@ pub fn get_distinct_categories(
         .collect();
     
     let categories_iter = stmt
-        .query_map(params.as_slice(), |row| row.get::<_, String>(0))
-        .map_err(|e| e.to_string())?;
+        .query_map(params.as_slice(), |row| row.get::<_, String>(0))?;
 
     let mut categories = Vec::new();
     for category_result in categories_iter {
-        categories.push(category_result.map_err(|e| e.to_string())?);
+        categories.push(category_result?);
     }
 
     println!(
@@ -799,8 +817,8 @@ pub fn get_distinct_categories(
 #[tauri::command]
 pub fn get_distinct_payment_methods(
     db_state: State<'_, DbState>,
-) -> std::result::Result<Vec<String>, String> {
-    let conn_guard = db_state.0.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<String>, Ten10Error> {
+    let conn_guard = db_state.read()?;
     let conn = &*conn_guard;
 
     let query = "
@@ -810,14 +828,13 @@ pub fn get_distinct_payment_methods(
         ORDER BY payment_method
     ";
 
-    let mut stmt = conn.prepare(query).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(query)?;
     let methods_iter = stmt
-        .query_map([], |row| row.get::<_, String>(0))
-        .map_err(|e| e.to_string())?;
+        .query_map([], |row| row.get::<_, String>(0))?;
 
     let mut methods = Vec::new();
     for method_result in methods_iter {
-        methods.push(method_result.map_err(|e| e.to_string())?);
+        methods.push(method_result?);
     }
 
     println!(
@@ -839,57 +856,35 @@ mod tests {
     use super::*;
     use rusqlite::Connection;
     use serde_json::json;
-    use std::sync::Mutex;
     use tauri::Manager;
 
     fn test_db() -> Connection {
-        let conn = Connection::open_in_memory().expect("in-memory db");
-        conn.execute_batch(
-            "CREATE TABLE transactions (
-                id TEXT PRIMARY KEY, user_id TEXT, date TEXT NOT NULL, amount REAL NOT NULL,
-                currency TEXT NOT NULL, description TEXT, type TEXT NOT NULL, category TEXT,
-                is_chomesh INTEGER, recipient TEXT, payment_method TEXT, created_at TEXT,
-                updated_at TEXT, source_recurring_id TEXT, occurrence_number INTEGER,
-                original_amount REAL, original_currency TEXT, conversion_rate REAL,
-                conversion_date TEXT, rate_source TEXT
-            );
-            CREATE TABLE recurring_transactions (
-                id TEXT PRIMARY KEY, user_id TEXT, status TEXT NOT NULL DEFAULT 'active',
-                start_date TEXT NOT NULL, next_due_date TEXT NOT NULL,
-                frequency TEXT NOT NULL DEFAULT 'monthly', day_of_month INTEGER NOT NULL,
-                total_occurrences INTEGER, execution_count INTEGER NOT NULL DEFAULT 0,
-                description TEXT, amount REAL NOT NULL, currency TEXT NOT NULL,
-                type TEXT NOT NULL, category TEXT, is_chomesh INTEGER, recipient TEXT,
-                payment_method TEXT, created_at TEXT NOT NULL, updated_at TEXT NOT NULL,
-                original_amount REAL, original_currency TEXT, conversion_rate REAL,
-                conversion_date TEXT, rate_source TEXT
-            );",
-        )
-        .expect("schema");
+        let mut conn = Connection::open_in_memory().expect("in-memory db");
+        crate::migrations::run_migrations(&mut conn).expect("schema");
 
         conn.execute_batch(
             "INSERT INTO recurring_transactions
                 (id, status, start_date, next_due_date, frequency, day_of_month,
                  execution_count, amount, currency, type, created_at, updated_at)
              VALUES ('rec1', 'active', '2024-01-01', '2024-04-01', 'monthly', 1,
-                 3, 30.0, 'ILS', 'donation', '2024-01-01', '2024-01-01');
+                 3, 3000, 'ILS', 'donation', '2024-01-01', '2024-01-01');
 
              INSERT INTO transactions
                 (id, date, amount, currency, description, type, category, is_chomesh,
                  payment_method, created_at)
-             VALUES ('t1', '2024-01-10', 100.0, 'ILS', 'משכורת ינואר', 'income', 'salary', 1,
+             VALUES ('t1', '2024-01-10', 10000, 'ILS', 'משכורת ינואר', 'income', 'salary', 1,
                  'cash', '2024-01-10T10:00:00Z');
 
              INSERT INTO transactions
                 (id, date, amount, currency, description, type, category,
                  payment_method, created_at)
-             VALUES ('t2', '2024-02-15', 50.0, 'ILS', 'מכולת שכונתית', 'expense', 'food',
+             VALUES ('t2', '2024-02-15', 5000, 'ILS', 'מכולת שכונתית', 'expense', 'food',
                  'credit', '2024-02-15T10:00:00Z');
 
              INSERT INTO transactions
                 (id, date, amount, currency, description, type, recipient,
                  payment_method, created_at, source_recurring_id)
-             VALUES ('t3', '2024-03-01', 30.0, 'ILS', 'תרומה חודשית', 'donation', 'ישיבה',
+             VALUES ('t3', '2024-03-01', 3000, 'ILS', 'תרומה חודשית', 'donation', 'ישיבה',
                  'cash', '2024-03-01T10:00:00Z', 'rec1');",
         )
         .expect("seed");
@@ -898,7 +893,7 @@ mod tests {
 
     fn mock_app() -> tauri::App<tauri::test::MockRuntime> {
         let app = tauri::test::mock_app();
-        app.manage(crate::DbState(Mutex::new(test_db())));
+        app.manage(crate::DbState::new(test_db()));
         app
     }
 
@@ -932,7 +927,7 @@ mod tests {
     fn no_filters_returns_everything() {
         let app = mock_app();
         let res = run(&app, json!({}), default_sort());
-        assert_eq!(res.total_count, 3);
+        assert_eq!(res.total_count, Some(3));
         assert_eq!(ids(&res), vec!["t1", "t2", "t3"]);
     }
 
@@ -946,14 +941,152 @@ mod tests {
         assert_eq!(ids(&by_recipient), vec!["t3"]);
 
         let no_match = run(&app, json!({ "search": "לא-קיים" }), default_sort());
-        assert_eq!(no_match.total_count, 0);
+        assert_eq!(no_match.total_count, Some(0));
+    }
+
+    #[test]
+    fn search_matches_word_prefixes_ignoring_niqqud_and_final_letters() {
+        let app = mock_app();
+        let prefix = run(&app, json!({ "search": "שכונ" }), default_sort());
+        assert_eq!(ids(&prefix), vec!["t2"]);
+
+        let pointed = run(&app, json!({ "search": "מִכּוֹלֶת" }), default_sort());
+        assert_eq!(ids(&pointed), vec!["t2"]);
+
+        // A prefix typed with a final letter still matches the longer word
+        let final_letter = run(&app, json!({ "search": "שכון" }), default_sort());
+        assert_eq!(ids(&final_letter), vec!["t2"]);
+    }
+
+    #[test]
+    fn search_requires_every_word_in_any_column() {
+        let app = mock_app();
+        let across_columns = run(&app, json!({ "search": "תרומה cash" }), default_sort());
+        assert_eq!(ids(&across_columns), vec!["t3"]);
+        let one_word_missing = run(&app, json!({ "search": "תרומה credit" }), default_sort());
+        assert_eq!(one_word_missing.total_count, Some(0));
+    }
+
+    #[test]
+    fn search_follows_updates_and_deletes_and_ranks_by_relevance() {
+        let app = mock_app();
+        update_transaction_handler(
+            app.state::<crate::DbState>(),
+            "t1".into(),
+            serde_json::from_value(json!({ "description": "תרומה" })).unwrap(),
+        )
+        .unwrap();
+        let relevance = json!({ "field": "relevance", "direction": "asc" });
+        let res = run(&app, json!({ "search": "תרומה" }), relevance.clone());
+        assert_eq!(ids(&res), vec!["t1", "t3"]);
+        let res = run(&app, json!({ "search": "משכורת" }), relevance);
+        assert_eq!(res.total_count, Some(0));
+
+        delete_transaction_handler(app.state::<crate::DbState>(), "t1".into()).unwrap();
+        let res = run(&app, json!({ "search": "תרומה" }), default_sort());
+        assert_eq!(ids(&res), vec!["t3"]);
+    }
+
+    #[test]
+    fn search_survives_renumbered_rowids() {
+        let app = mock_app();
+        {
+            let conn = app.state::<crate::DbState>().inner().0.lock().unwrap();
+            // The rowid of a table with a TEXT primary key is not stable: VACUUM may renumber it.
+            conn.execute_batch(
+                "UPDATE transactions SET rowid = rowid + 100;
+                 UPDATE transactions SET rowid = CASE id WHEN 't2' THEN 1 WHEN 't3' THEN 2 ELSE 3 END;",
+            )
+            .unwrap();
+        }
+        let res = run(&app, json!({ "search": "ישיבה" }), default_sort());
+        assert_eq!(ids(&res), vec!["t3"]);
+        let res = run(&app, json!({ "search": "מכולת" }), default_sort());
+        assert_eq!(ids(&res), vec!["t2"]);
+    }
+
+    /// Adds converted donations to the fixture: 600 and 400 USD to the yeshiva,
+    /// 900 USD to someone else.
+    fn add_usd_donations(app: &tauri::App<tauri::test::MockRuntime>) {
+        let conn = app.state::<crate::DbState>().inner().0.lock().unwrap();
+        conn.execute_batch(
+            "INSERT INTO recipients (id, name, created_at, updated_at) VALUES
+                ('r-yeshiva', 'ישיבה', '0', '0'), ('r-other', 'גמח', '0', '0');
+             UPDATE transactions SET recipient_id = 'r-yeshiva' WHERE id = 't3';
+             INSERT INTO transactions
+                (id, date, amount, currency, type, category, is_chomesh, recipient, recipient_id,
+                 original_amount, original_currency, created_at, updated_at)
+             VALUES
+                ('u1', '2024-04-01', 222000, 'ILS', 'donation', 'torah', 1, 'ישיבה', 'r-yeshiva',
+                 60000, 'USD', '2024-04-01T09:00:00+00:00', '2024-05-01T09:00:00+00:00'),
+                ('u2', '2024-04-02', 148000, 'ILS', 'donation', 'torah', 0, 'ישיבה', 'r-yeshiva',
+                 40000, 'USD', '2024-04-02T09:00:00+00:00', '2024-04-02T09:00:00+00:00'),
+                ('u3', '2024-04-03', 333000, 'ILS', 'donation', 'gemach', NULL, 'גמח', 'r-other',
+                 90000, 'USD', '2024-04-03T23:59:00+00:00', '2024-04-03T23:59:00+00:00');
+             INSERT INTO categories (key, parent_key, created_at, updated_at) VALUES
+                ('charity', NULL, '0', '0'), ('torah', 'charity', '0', '0');",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn structured_filters_combine() {
+        let app = mock_app();
+        add_usd_donations(&app);
+        let usd_over_500_to_yeshiva = run(
+            &app,
+            json!({ "types": ["donation"], "currencies": ["USD"], "amountMin": 500, "recipientIds": ["r-yeshiva"] }),
+            default_sort(),
+        );
+        assert_eq!(ids(&usd_over_500_to_yeshiva), vec!["u1"]);
+
+        // Amounts are compared as paid, inclusively: 100 ILS for t1, 400 USD for u2
+        let range = run(&app, json!({ "amountMin": 100, "amountMax": 400 }), default_sort());
+        assert_eq!(ids(&range), vec!["t1", "u2"]);
+
+        let chomesh = run(&app, json!({ "isChomesh": true }), default_sort());
+        assert_eq!(ids(&chomesh), vec!["t1", "u1"]);
+        let not_chomesh = run(&app, json!({ "isChomesh": false, "types": ["donation"] }), default_sort());
+        assert_eq!(ids(&not_chomesh), vec!["t3", "u2", "u3"]);
+
+        let converted = run(&app, json!({ "hasConversion": true }), default_sort());
+        assert_eq!(converted.total_count, Some(3));
+        let not_converted = run(&app, json!({ "hasConversion": false }), default_sort());
+        assert_eq!(ids(&not_converted), vec!["t1", "t2", "t3"]);
+    }
+
+    #[test]
+    fn category_filter_includes_subcategories_and_timestamp_ranges_are_inclusive() {
+        let app = mock_app();
+        add_usd_donations(&app);
+        let charity = run(&app, json!({ "categories": ["charity", "food"] }), default_sort());
+        assert_eq!(ids(&charity), vec!["t2", "u1", "u2"]);
+
+        let created = run(&app, json!({ "createdFrom": "2024-04-02", "createdTo": "2024-04-03" }), default_sort());
+        assert_eq!(ids(&created), vec!["u2", "u3"]);
+        let updated = run(&app, json!({ "updatedFrom": "2024-05-01" }), default_sort());
+        assert_eq!(ids(&updated), vec!["u1"]);
+    }
+
+    #[test]
+    fn export_and_count_apply_the_same_filters_as_the_table() {
+        let app = mock_app();
+        add_usd_donations(&app);
+        let filters = json!({ "search": "ישיבה", "currencies": ["USD"], "amountMin": 450 });
+        let table = run(&app, filters.clone(), default_sort());
+        assert_eq!(table.total_count, Some(1));
+
+        let exported =
+            export_transactions_handler(app.state::<crate::DbState>(), serde_json::from_value(filters).unwrap()).unwrap();
+        let exported_ids: Vec<_> = exported.iter().map(|t| t.transaction.id.clone()).collect();
+        assert_eq!(exported_ids, ids(&table));
     }
 
     #[test]
     fn empty_search_is_ignored() {
         let app = mock_app();
         let res = run(&app, json!({ "search": "" }), default_sort());
-        assert_eq!(res.total_count, 3);
+        assert_eq!(res.total_count, Some(3));
     }
 
     #[test]
@@ -1011,7 +1144,7 @@ mod tests {
         assert_eq!(ids(&regular), vec!["t1", "t2"]);
 
         let all = run(&app, json!({ "showOnly": "all" }), default_sort());
-        assert_eq!(all.total_count, 3);
+        assert_eq!(all.total_count, Some(3));
     }
 
     #[test]
@@ -1024,7 +1157,7 @@ mod tests {
         assert_eq!(ids(&monthly), vec!["t3"]);
 
         let paused = run(&app, json!({ "recurringStatuses": ["paused"] }), default_sort());
-        assert_eq!(paused.total_count, 0);
+        assert_eq!(paused.total_count, Some(0));
     }
 
     #[test]
@@ -1047,7 +1180,7 @@ mod tests {
             json!({}),
             json!({ "field": "amount", "direction": "asc; DROP TABLE transactions; --" }),
         );
-        assert_eq!(res.total_count, 3);
+        assert_eq!(res.total_count, Some(3));
         assert_eq!(ids(&res), vec!["t3", "t2", "t1"]); // ASC by amount
 
         let upper = run(&app, json!({}), json!({ "field": "amount", "direction": "DeSc" }));
@@ -1058,10 +1191,49 @@ mod tests {
     fn unknown_sort_field_falls_back_to_created_at() {
         let app = mock_app();
         let res = run(&app, json!({}), json!({ "field": "bogus", "direction": "asc" }));
-        assert_eq!(res.total_count, 3);
+        assert_eq!(res.total_count, Some(3));
         assert_eq!(ids(&res), vec!["t1", "t2", "t3"]); // created_at order
     }
 
+    #[test]
+    fn currency_change_without_amount_keeps_the_value() {
+        let app = mock_app();
+        let update = |payload: serde_json::Value| {
+            update_transaction_handler(app.state::<crate::DbState>(), "t1".into(), serde_json::from_value(payload).unwrap())
+                .unwrap()
+        };
+        let stored = || {
+            let conn = app.state::<crate::DbState>().inner().0.lock().unwrap();
+            conn.query_row("SELECT amount, currency FROM transactions WHERE id = 't1'", [], |r| {
+                Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?))
+            })
+            .unwrap()
+        };
+        // 100.00 ILS is 100 JPY (exponent 0) and 100.000 KWD (exponent 3).
+        update(json!({ "currency": "JPY" }));
+        assert_eq!(stored(), (100, "JPY".to_string()));
+        update(json!({ "currency": "KWD" }));
+        assert_eq!(stored(), (100000, "KWD".to_string()));
+        update(json!({ "currency": "USD", "amount": 7.5 }));
+        assert_eq!(stored(), (750, "USD".to_string()));
+    }
+
+    #[test]
+    fn update_and_delete_report_missing_id_as_not_found() {
+        let app = mock_app();
+        let payload: TransactionUpdatePayload = serde_json::from_value(json!({ "amount": 1.0 })).unwrap();
+        let err = update_transaction_handler(app.state::<crate::DbState>(), "missing".into(), payload)
+            .unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+        assert_eq!(
+            serde_json::to_value(&err).unwrap()["details"],
+            json!({ "entity": "Transaction", "id": "missing" })
+        );
+
+        let err = delete_transaction_handler(app.state::<crate::DbState>(), "missing".into()).unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+
     #[test]
     fn paginates_with_correct_total_count() {
         let app = mock_app();
@@ -1075,7 +1247,7 @@ mod tests {
             .unwrap(),
         )
         .unwrap();
-        assert_eq!(page1.total_count, 3);
+        assert_eq!(page1.total_count, Some(3));
         assert_eq!(ids(&page1), vec!["t1", "t2"]);
 
         let page2: PaginatedTransactionsResponse = get_filtered_transactions_handler(
@@ -1088,7 +1260,91 @@ mod tests {
             .unwrap(),
         )
         .unwrap();
-        assert_eq!(page2.total_count, 3);
+        assert_eq!(page2.total_count, Some(3));
         assert_eq!(ids(&page2), vec!["t3"]);
     }
+
+    fn page(
+        app: &tauri::App<tauri::test::MockRuntime>,
+        filters: &serde_json::Value,
+        sorting: &serde_json::Value,
+        cursor: Option<&str>,
+    ) -> PaginatedTransactionsResponse {
+        get_filtered_transactions_handler(
+            app.state::<crate::DbState>(),
+            serde_json::from_value(json!({
+                "filters": filters,
+                "pagination": { "limit": 2, "cursor": cursor, "includeTotal": false },
+                "sorting": sorting,
+            }))
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn cursors_walk_every_sort_forwards_and_back() {
+        let app = mock_app();
+        add_usd_donations(&app);
+        let fields = [
+            "date", "amount", "description", "currency", "type", "category", "recipient",
+            "payment_method", "is_chomesh", "created_at", "updated_at", "relevance",
+        ];
+        for field in fields {
+            // Relevance needs a search: three donations to the yeshiva, two pages
+            let filters = if field == "relevance" { json!({ "search": "ישיבה" }) } else { json!({}) };
+            for direction in ["asc", "desc"] {
+                let sorting = json!({ "field": field, "direction": direction });
+                let expected = ids(&run(&app, filters.clone(), sorting.clone()));
+
+                let mut pages = vec![page(&app, &filters, &sorting, None)];
+                while let Some(next) = pages.last().unwrap().next_cursor.clone() {
+                    pages.push(page(&app, &filters, &sorting, Some(&next)));
+                }
+                let walked: Vec<String> = pages.iter().flat_map(ids).collect();
+                assert_eq!(walked, expected, "{} {}", field, direction);
+                assert!(pages.iter().all(|p| p.total_count.is_none()));
+                assert!(!pages.last().unwrap().has_more);
+                assert!(pages[0].prev_cursor.is_none());
+
+                let back = page(&app, &filters, &sorting, pages.last().unwrap().prev_cursor.as_deref());
+                assert_eq!(ids(&back), ids(&pages[pages.len() - 2]), "{} {}", field, direction);
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_pages_do_not_shift_when_rows_are_added() {
+        let app = mock_app();
+        let sorting = json!({ "field": "date", "direction": "desc" });
+        let first = page(&app, &json!({}), &sorting, None);
+        assert_eq!(ids(&first), vec!["t3", "t2"]);
+        assert!(first.has_more);
+
+        app.state::<crate::DbState>()
+            .inner()
+            .0
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO transactions (id, date, amount, currency, type, created_at)
+                 VALUES ('t4', '2024-04-01', 100, 'ILS', 'expense', '2024-04-01T10:00:00Z')",
+            )
+            .unwrap();
+        let second = page(&app, &json!({}), &sorting, first.next_cursor.as_deref());
+        assert_eq!(ids(&second), vec!["t1"]);
+        assert!(!second.has_more && second.next_cursor.is_none());
+
+        let other_sort = json!({ "field": "amount", "direction": "desc" });
+        let mismatched = get_filtered_transactions_handler(
+            app.state::<crate::DbState>(),
+            serde_json::from_value(json!({
+                "filters": {},
+                "pagination": { "limit": 2, "cursor": first.next_cursor },
+                "sorting": other_sort,
+            }))
+            .unwrap(),
+        );
+        assert!(matches!(mismatched, Err(Ten10Error::Validation(_))));
+    }
 }
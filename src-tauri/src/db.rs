@@ -0,0 +1,188 @@
+// src-tauri/src/db.rs
+// Database handle shared by all commands.
+//
+// The database runs in WAL mode: one writer connection (`DbState.0`, behind a
+// Mutex exactly as before) plus a small pool of read-only connections. Readers
+// see the last committed snapshot and never wait for the writer, so analytics
+// and table queries keep answering while a bulk import or a recurring run holds
+// the write lock.
+//
+//   let conn = db_state.0.lock()?;   // INSERT / UPDATE / DELETE / migrations
+//   let conn = db_state.read()?;     // SELECT only
+
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use rusqlite::Connection;
+
+use crate::error::Ten10Result;
+use crate::search::register_search_functions;
+
+/// Read connections kept open; the analytics page fires 6–8 queries at once.
+const READ_POOL_SIZE: usize = 4;
+/// How long a connection waits on a lock held by another connection before SQLITE_BUSY.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct DbState(pub Mutex<Connection>, Option<ReadPool>);
+
+impl DbState {
+    /// Opens the database file in WAL mode with a writer and a pool of readers.
+    pub fn open(path: &Path) -> Ten10Result<Self> {
+        let writer = Connection::open(path)?;
+        writer.busy_timeout(BUSY_TIMEOUT)?;
+        writer.pragma_update(None, "journal_mode", "WAL")?;
+        writer.pragma_update(None, "synchronous", "NORMAL")?;
+        // Before anything writes: the full-text search triggers call into it.
+        register_search_functions(&writer)?;
+
+        let mut readers = Vec::with_capacity(READ_POOL_SIZE);
+        for _ in 0..READ_POOL_SIZE {
+            let reader = Connection::open(path)?;
+            reader.busy_timeout(BUSY_TIMEOUT)?;
+            reader.pragma_update(None, "query_only", true)?;
+            readers.push(reader);
+        }
+
+        Ok(DbState(
+            Mutex::new(writer),
+            Some(ReadPool { idle: Mutex::new(readers), available: Condvar::new() }),
+        ))
+    }
+
+    /// Single-connection state (reads share the writer) for in-memory test databases.
+    #[cfg(test)]
+    pub fn new(conn: Connection) -> Self {
+        DbState(Mutex::new(conn), None)
+    }
+
+    /// A connection for read-only queries. Blocks only while every reader is busy.
+    pub fn read(&self) -> Ten10Result<ReadConn<'_>> {
+        match &self.1 {
+            Some(pool) => pool.get().map(ReadConn::Pooled),
+            None => Ok(ReadConn::Writer(self.0.lock()?)),
+        }
+    }
+}
+
+pub struct ReadPool {
+    idle: Mutex<Vec<Connection>>,
+    available: Condvar,
+}
+
+impl ReadPool {
+    fn get(&self) -> Ten10Result<PooledConn<'_>> {
+        let mut idle = self.idle.lock()?;
+        loop {
+            if let Some(conn) = idle.pop() {
+                return Ok(PooledConn { pool: self, conn: Some(conn) });
+            }
+            idle = self.available.wait(idle)?;
+        }
+    }
+}
+
+/// A reader borrowed from the pool; goes back to it on drop.
+pub struct PooledConn<'a> {
+    pool: &'a ReadPool,
+    conn: Option<Connection>,
+}
+
+impl Drop for PooledConn<'_> {
+    fn drop(&mut self) {
+        if let (Some(conn), Ok(mut idle)) = (self.conn.take(), self.pool.idle.lock()) {
+            idle.push(conn);
+            self.pool.available.notify_one();
+        }
+    }
+}
+
+pub enum ReadConn<'a> {
+    Pooled(PooledConn<'a>),
+    Writer(MutexGuard<'a, Connection>),
+}
+
+impl Deref for ReadConn<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        match self {
+            ReadConn::Pooled(pooled) => pooled.conn.as_ref().expect("pooled connection taken"),
+            ReadConn::Writer(guard) => guard,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    struct TempDb(std::path::PathBuf);
+
+    impl TempDb {
+        fn new() -> Self {
+            TempDb(std::env::temp_dir().join(format!("ten10-test-{}.db", uuid::Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.0.display(), suffix));
+            }
+        }
+    }
+
+    fn count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM t", [], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn readers_see_committed_snapshot_while_writer_is_busy() {
+        let file = TempDb::new();
+        let db = DbState::open(&file.0).unwrap();
+        db.0.lock().unwrap().execute_batch("CREATE TABLE t (x); INSERT INTO t VALUES (1);").unwrap();
+
+        let mut writer = db.0.lock().unwrap();
+        let tx = writer.transaction().unwrap();
+        tx.execute("INSERT INTO t VALUES (2)", []).unwrap();
+
+        // The write lock and an open write transaction do not block readers.
+        assert_eq!(count(&db.read().unwrap()), 1);
+
+        tx.commit().unwrap();
+        drop(writer);
+        assert_eq!(count(&db.read().unwrap()), 2);
+    }
+
+    #[test]
+    fn readers_are_read_only_and_returned_to_the_pool() {
+        let file = TempDb::new();
+        let db = Arc::new(DbState::open(&file.0).unwrap());
+        db.0.lock().unwrap().execute_batch("CREATE TABLE t (x);").unwrap();
+
+        assert!(db.read().unwrap().execute("INSERT INTO t VALUES (1)", []).is_err());
+
+        // More concurrent readers than pooled connections: extra ones wait, none fail.
+        let handles: Vec<_> = (0..READ_POOL_SIZE * 3)
+            .map(|_| {
+                let db = Arc::clone(&db);
+                thread::spawn(move || count(&db.read().unwrap()))
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 0);
+        }
+        assert_eq!(db.1.as_ref().unwrap().idle.lock().unwrap().len(), READ_POOL_SIZE);
+    }
+
+    #[test]
+    fn single_connection_state_reads_through_the_writer() {
+        let db = DbState::new(Connection::open_in_memory().unwrap());
+        db.0.lock().unwrap().execute_batch("CREATE TABLE t (x); INSERT INTO t VALUES (1);").unwrap();
+        assert_eq!(count(&db.read().unwrap()), 1);
+    }
+}
@@ -0,0 +1,155 @@
+// src-tauri/src/error.rs
+// Error type returned by every Tauri command.
+//
+// Serializes to `{ code, message, details }`: `code` is a stable SCREAMING_SNAKE
+// identifier the frontend branches on and localizes, `message` is an English
+// fallback for logs, and `details` carries structured context (or null).
+
+use std::fmt;
+use std::sync::PoisonError;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::{json, Value};
+
+#[derive(Debug)]
+pub enum Ten10Error {
+    /// No row of `entity` has this id.
+    NotFound { entity: &'static str, id: String },
+    /// The caller sent something we refuse to act on.
+    Validation(String),
+    /// A thread panicked while holding the database lock.
+    LockPoisoned(String),
+    Database(rusqlite::Error),
+    /// A schema migration failed and was rolled back.
+    Migration { version: i64, name: &'static str, cause: String },
+    Io(std::io::Error),
+}
+
+pub type Ten10Result<T> = Result<T, Ten10Error>;
+
+impl Ten10Error {
+    pub fn not_found(entity: &'static str, id: impl Into<String>) -> Self {
+        Ten10Error::NotFound { entity, id: id.into() }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Ten10Error::Validation(message.into())
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Ten10Error::NotFound { .. } => "NOT_FOUND",
+            Ten10Error::Validation(_) => "VALIDATION",
+            Ten10Error::LockPoisoned(_) => "LOCK_POISONED",
+            Ten10Error::Database(_) => "DATABASE",
+            Ten10Error::Migration { .. } => "MIGRATION",
+            Ten10Error::Io(_) => "IO",
+        }
+    }
+
+    fn details(&self) -> Value {
+        match self {
+            Ten10Error::NotFound { entity, id } => json!({ "entity": entity, "id": id }),
+            Ten10Error::Migration { version, name, cause } => {
+                json!({ "version": version, "name": name, "cause": cause })
+            }
+            Ten10Error::Database(e) => json!({ "cause": e.to_string() }),
+            Ten10Error::Io(e) => json!({ "cause": e.to_string(), "kind": format!("{:?}", e.kind()) }),
+            Ten10Error::Validation(_) | Ten10Error::LockPoisoned(_) => Value::Null,
+        }
+    }
+}
+
+impl fmt::Display for Ten10Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ten10Error::NotFound { entity, id } => write!(f, "{} with ID {} not found", entity, id),
+            Ten10Error::Validation(message) => write!(f, "{}", message),
+            Ten10Error::LockPoisoned(message) => write!(f, "Database lock poisoned: {}", message),
+            Ten10Error::Database(e) => write!(f, "Database error: {}", e),
+            Ten10Error::Migration { version, name, cause } => {
+                write!(f, "Migration {:04}_{} failed: {}", version, name, cause)
+            }
+            Ten10Error::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Ten10Error {}
+
+impl Serialize for Ten10Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Ten10Error", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.serialize_field("details", &self.details())?;
+        s.end()
+    }
+}
+
+impl From<rusqlite::Error> for Ten10Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Ten10Error::Database(e)
+    }
+}
+
+impl From<std::io::Error> for Ten10Error {
+    fn from(e: std::io::Error) -> Self {
+        Ten10Error::Io(e)
+    }
+}
+
+/// Workbook errors are almost always the file write failing; the rest (a bad
+/// sheet name, too many rows) surface as IO with the library's message.
+impl From<rust_xlsxwriter::XlsxError> for Ten10Error {
+    fn from(e: rust_xlsxwriter::XlsxError) -> Self {
+        match e {
+            rust_xlsxwriter::XlsxError::IoError(io) => Ten10Error::Io(io),
+            other => Ten10Error::Io(std::io::Error::other(other)),
+        }
+    }
+}
+
+impl<T> From<PoisonError<T>> for Ten10Error {
+    fn from(e: PoisonError<T>) -> Self {
+        Ten10Error::LockPoisoned(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_code_message_and_details() {
+        let value = serde_json::to_value(Ten10Error::not_found("Transaction", "t1")).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "code": "NOT_FOUND",
+                "message": "Transaction with ID t1 not found",
+                "details": { "entity": "Transaction", "id": "t1" },
+            })
+        );
+
+        let value = serde_json::to_value(Ten10Error::validation("Invalid granularity: hour")).unwrap();
+        assert_eq!(value["code"], "VALIDATION");
+        assert_eq!(value["details"], Value::Null);
+    }
+
+    #[test]
+    fn converts_sqlite_and_lock_errors() {
+        let db: Ten10Error = rusqlite::Error::QueryReturnedNoRows.into();
+        assert_eq!(db.code(), "DATABASE");
+
+        let mutex = std::sync::Arc::new(std::sync::Mutex::new(()));
+        let m = mutex.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = m.lock().unwrap();
+            panic!("poison");
+        })
+        .join();
+        let err: Ten10Error = mutex.lock().unwrap_err().into();
+        assert_eq!(err.code(), "LOCK_POISONED");
+    }
+}
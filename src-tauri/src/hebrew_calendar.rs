@@ -0,0 +1,332 @@
+// src-tauri/src/hebrew_calendar.rs
+// Hebrew calendar arithmetic (after Dershowitz & Reingold, "Calendrical
+// Calculations"). Dates are handled as fixed day numbers (R.D.), which is exactly
+// chrono's `num_days_from_ce()`. The Hebrew year starts on 1 Tishrei.
+//
+// Months are numbered biblically: Nisan = 1 … Elul = 6, Tishrei = 7 … Adar = 12,
+// and in leap years Adar I = 12, Adar II = 13. Hebrew dates travel over IPC as
+// "YYYY-MM-DD" in that numbering (e.g. "5784-13-14" is Purim 5784).
+//
+// Years outside MIN_YEAR..=MAX_YEAR are rejected wherever a year comes from the
+// caller, which keeps the `year ± 1` arithmetic below far from overflowing.
+
+use chrono::{Datelike, NaiveDate};
+use crate::error::Ten10Error;
+
+/// R.D. of 1 Tishrei, year 1 AM.
+const HEBREW_EPOCH: i64 = -1_373_427;
+
+pub const NISAN: u32 = 1;
+pub const TISHREI: u32 = 7;
+pub const ADAR: u32 = 12;
+pub const ADAR_II: u32 = 13;
+
+/// First supported year; 1 Tishrei 3762 is in 1 CE.
+pub const MIN_YEAR: i32 = 3762;
+pub const MAX_YEAR: i32 = 9999;
+
+pub fn is_supported_year(year: i32) -> bool {
+    (MIN_YEAR..=MAX_YEAR).contains(&year)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HebrewDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+pub fn is_leap_year(year: i32) -> bool {
+    (7 * i64::from(year) + 1).rem_euclid(19) < 7
+}
+
+pub fn last_month_of_year(year: i32) -> u32 {
+    if is_leap_year(year) {
+        ADAR_II
+    } else {
+        ADAR
+    }
+}
+
+fn elapsed_days(year: i32) -> i64 {
+    let months_elapsed = (235 * i64::from(year) - 234).div_euclid(19);
+    let parts_elapsed = 12_084 + 13_753 * months_elapsed;
+    let day = 29 * months_elapsed + parts_elapsed.div_euclid(25_920);
+    if (3 * (day + 1)).rem_euclid(7) < 3 {
+        day + 1
+    } else {
+        day
+    }
+}
+
+fn year_length_correction(year: i32) -> i64 {
+    let ny0 = elapsed_days(year - 1);
+    let ny1 = elapsed_days(year);
+    let ny2 = elapsed_days(year + 1);
+    if ny2 - ny1 == 356 {
+        2
+    } else if ny1 - ny0 == 382 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Fixed day number of Rosh Hashana (1 Tishrei) of `year`.
+fn new_year(year: i32) -> i64 {
+    HEBREW_EPOCH + elapsed_days(year) + year_length_correction(year)
+}
+
+pub fn days_in_year(year: i32) -> i64 {
+    new_year(year + 1) - new_year(year)
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    let year_days = days_in_year(year);
+    let long_marheshvan = year_days == 355 || year_days == 385;
+    let short_kislev = year_days == 353 || year_days == 383;
+    match month {
+        2 | 4 | 6 | 10 | 13 => 29,
+        12 if !is_leap_year(year) => 29,
+        8 if !long_marheshvan => 29,
+        9 if short_kislev => 29,
+        _ => 30,
+    }
+}
+
+fn to_fixed(date: HebrewDate) -> i64 {
+    let months_before: i64 = if date.month < TISHREI {
+        (TISHREI..=last_month_of_year(date.year))
+            .chain(NISAN..date.month)
+            .map(|m| i64::from(days_in_month(date.year, m)))
+            .sum()
+    } else {
+        (TISHREI..date.month)
+            .map(|m| i64::from(days_in_month(date.year, m)))
+            .sum()
+    };
+    new_year(date.year) + months_before + i64::from(date.day) - 1
+}
+
+fn fixed_of(date: NaiveDate) -> i64 {
+    i64::from(date.num_days_from_ce())
+}
+
+fn from_fixed_gregorian(fixed: i64) -> Option<NaiveDate> {
+    NaiveDate::from_num_days_from_ce_opt(i32::try_from(fixed).ok()?)
+}
+
+/// Hebrew date of a Gregorian date.
+pub fn from_gregorian(date: NaiveDate) -> HebrewDate {
+    let fixed = fixed_of(date);
+    // Mean year length 35975351/98496 days; start one year early and walk forward.
+    let approx = ((fixed - HEBREW_EPOCH) * 98_496).div_euclid(35_975_351) as i32 + 1;
+    let mut year = approx - 1;
+    while new_year(year + 1) <= fixed {
+        year += 1;
+    }
+
+    let start = if fixed < to_fixed(HebrewDate { year, month: NISAN, day: 1 }) {
+        TISHREI
+    } else {
+        NISAN
+    };
+    let mut month = start;
+    while fixed > to_fixed(HebrewDate { year, month, day: days_in_month(year, month) }) {
+        month = if month == last_month_of_year(year) { NISAN } else { month + 1 };
+    }
+    let day = (fixed - to_fixed(HebrewDate { year, month, day: 1 }) + 1) as u32;
+    HebrewDate { year, month, day }
+}
+
+/// Gregorian date of a Hebrew date. None if the day does not exist in that month
+/// or the year is not supported.
+pub fn to_gregorian(date: HebrewDate) -> Option<NaiveDate> {
+    if !is_supported_year(date.year) || date.month == 0 || date.month > last_month_of_year(date.year) {
+        return None;
+    }
+    if date.day == 0 || date.day > days_in_month(date.year, date.month) {
+        return None;
+    }
+    from_fixed_gregorian(to_fixed(date))
+}
+
+/// Gregorian date of Rosh Hashana (1 Tishrei) of the Hebrew `year`. None if the
+/// year is not supported.
+pub fn rosh_hashana(year: i32) -> Option<NaiveDate> {
+    if !is_supported_year(year) {
+        return None;
+    }
+    from_fixed_gregorian(new_year(year))
+}
+
+/// First and last Gregorian day of the Hebrew `year` (1 Tishrei .. 29 Elul).
+pub fn year_bounds(year: i32) -> Option<(NaiveDate, NaiveDate)> {
+    let start = rosh_hashana(year)?;
+    let end = from_fixed_gregorian(new_year(year + 1) - 1)?;
+    Some((start, end))
+}
+
+/// Month following `month` in calendar order (Elul rolls over to Tishrei of the next year).
+pub fn next_month(year: i32, month: u32) -> (i32, u32) {
+    if month == 6 {
+        (year + 1, TISHREI)
+    } else if month == last_month_of_year(year) {
+        (year, NISAN)
+    } else {
+        (year, month + 1)
+    }
+}
+
+/// Month preceding `month` in calendar order (Tishrei rolls back to Elul of the previous year).
+pub fn prev_month(year: i32, month: u32) -> (i32, u32) {
+    if month == TISHREI {
+        (year - 1, 6)
+    } else if month == NISAN {
+        (year, last_month_of_year(year))
+    } else {
+        (year, month - 1)
+    }
+}
+
+/// First and last Gregorian day of a Hebrew month.
+pub fn month_bounds(year: i32, month: u32) -> Option<(NaiveDate, NaiveDate)> {
+    let start = to_gregorian(HebrewDate { year, month, day: 1 })?;
+    let end = to_gregorian(HebrewDate { year, month, day: days_in_month(year, month) })?;
+    Some((start, end))
+}
+
+/// Transliterated month name; Adar is "Adar I" in leap years.
+pub fn month_name(year: i32, month: u32) -> &'static str {
+    match month {
+        1 => "Nisan",
+        2 => "Iyar",
+        3 => "Sivan",
+        4 => "Tammuz",
+        5 => "Av",
+        6 => "Elul",
+        7 => "Tishrei",
+        8 => "Cheshvan",
+        9 => "Kislev",
+        10 => "Tevet",
+        11 => "Shevat",
+        12 if is_leap_year(year) => "Adar I",
+        12 => "Adar",
+        13 => "Adar II",
+        _ => "",
+    }
+}
+
+/// Parses a Hebrew "YYYY-MM-DD" date (biblical month numbers).
+pub fn parse_date(s: &str) -> Option<HebrewDate> {
+    let mut parts = s.trim().splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    let date = HebrewDate { year, month, day };
+    to_gregorian(date).map(|_| date)
+}
+
+/// Range bounds given in `calendar` ("gregorian", the default, or "hebrew") as
+/// Gregorian "YYYY-MM-DD" strings, ready for `date >= ?1 AND date <= ?2`.
+pub fn gregorian_range(
+    calendar: Option<&str>,
+    start_date: &str,
+    end_date: &str,
+) -> Result<(String, String), Ten10Error> {
+    match calendar.unwrap_or("gregorian") {
+        "gregorian" => Ok((start_date.to_string(), end_date.to_string())),
+        "hebrew" => {
+            let convert = |s: &str| {
+                parse_date(s)
+                    .and_then(to_gregorian)
+                    .map(|d| d.format("%Y-%m-%d").to_string())
+                    .ok_or_else(|| Ten10Error::validation(format!("Invalid Hebrew date: {}", s)))
+            };
+            Ok((convert(start_date)?, convert(end_date)?))
+        }
+        other => Err(Ten10Error::validation(format!("Invalid calendar: {}", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn known_rosh_hashana_dates() {
+        assert_eq!(rosh_hashana(5784), Some(date("2023-09-16")));
+        assert_eq!(rosh_hashana(5785), Some(date("2024-10-03")));
+        assert_eq!(rosh_hashana(5786), Some(date("2025-09-23")));
+    }
+
+    #[test]
+    fn leap_years_have_adar_ii() {
+        assert!(is_leap_year(5784));
+        assert!(!is_leap_year(5785));
+        assert_eq!(last_month_of_year(5784), ADAR_II);
+        // Purim 5784 is 14 Adar II = 2024-03-24; 14 Adar I fell a month earlier.
+        assert_eq!(to_gregorian(HebrewDate { year: 5784, month: ADAR_II, day: 14 }), Some(date("2024-03-24")));
+        assert_eq!(to_gregorian(HebrewDate { year: 5784, month: ADAR, day: 14 }), Some(date("2024-02-23")));
+        assert_eq!(to_gregorian(HebrewDate { year: 5785, month: ADAR_II, day: 1 }), None);
+    }
+
+    #[test]
+    fn round_trips_every_day_over_several_years() {
+        let mut d = date("2019-01-01");
+        let end = date("2031-12-31");
+        while d <= end {
+            let h = from_gregorian(d);
+            assert_eq!(to_gregorian(h), Some(d), "{:?}", h);
+            d = d.succ_opt().unwrap();
+        }
+        assert_eq!(from_gregorian(date("2024-04-23")), HebrewDate { year: 5784, month: NISAN, day: 15 });
+    }
+
+    #[test]
+    fn year_bounds_cover_tishrei_to_elul() {
+        assert_eq!(year_bounds(5785), Some((date("2024-10-03"), date("2025-09-22"))));
+    }
+
+    #[test]
+    fn months_step_across_year_and_adar_boundaries() {
+        assert_eq!(next_month(5784, 12), (5784, ADAR_II));
+        assert_eq!(next_month(5784, ADAR_II), (5784, NISAN));
+        assert_eq!(next_month(5785, ADAR), (5785, NISAN));
+        assert_eq!(next_month(5784, 6), (5785, TISHREI));
+        assert_eq!(prev_month(5785, TISHREI), (5784, 6));
+        assert_eq!(prev_month(5784, NISAN), (5784, ADAR_II));
+        assert_eq!(month_name(5784, ADAR), "Adar I");
+        assert_eq!(month_name(5785, ADAR), "Adar");
+        assert_eq!(month_bounds(5784, ADAR_II), Some((date("2024-03-11"), date("2024-04-08"))));
+    }
+
+    #[test]
+    fn hebrew_ranges_convert_to_gregorian() {
+        assert_eq!(
+            gregorian_range(Some("hebrew"), "5785-07-01", "5785-06-29").unwrap(),
+            ("2024-10-03".to_string(), "2025-09-22".to_string())
+        );
+        assert_eq!(
+            gregorian_range(None, "2024-01-01", "2024-12-31").unwrap(),
+            ("2024-01-01".to_string(), "2024-12-31".to_string())
+        );
+        assert!(gregorian_range(Some("hebrew"), "5785-13-01", "5785-06-29").is_err());
+        assert!(gregorian_range(Some("julian"), "2024-01-01", "2024-12-31").is_err());
+    }
+
+    #[test]
+    fn years_outside_the_supported_range_are_rejected() {
+        assert_eq!(parse_date("2147483647-07-01"), None);
+        assert_eq!(parse_date("-2147483648-07-01"), None);
+        assert!(gregorian_range(Some("hebrew"), "5785-07-01", "2147483647-06-29").is_err());
+        assert_eq!(year_bounds(i32::MAX), None);
+        assert_eq!(year_bounds(MIN_YEAR - 1), None);
+        assert!(year_bounds(MIN_YEAR).is_some() && year_bounds(MAX_YEAR).is_some());
+        assert_eq!(month_bounds(MAX_YEAR + 1, TISHREI), None);
+    }
+}
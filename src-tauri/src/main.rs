@@ -1,16 +1,26 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // Prevent console window in release on Windows
 
 use env_logger;
-use rusqlite::Connection;
-use std::sync::Mutex;
 use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
 use tauri_plugin_clipboard_manager;
 
 mod commands;
+mod db;
+mod error;
+mod hebrew_calendar;
+mod migrations;
 mod models;
+mod money;
+#[cfg(test)]
+mod query_plans;
+mod scheduler;
+mod search;
+mod tithe_rules;
 mod transaction_types;
 
 use commands::chart_commands::get_desktop_monthly_financial_summary;
+use commands::calendar_commands::get_calendar_date_range;
+use commands::export_commands::{export_transactions_csv, export_transactions_xlsx};
 use commands::insights_commands::{
     get_desktop_category_breakdown,
     get_desktop_payment_method_breakdown, get_desktop_recurring_vs_onetime,
@@ -23,7 +33,8 @@ use commands::db_commands::{
     delete_app_setting,
 };
 use commands::donation_commands::{
-    get_desktop_overall_tithe_balance, get_desktop_total_donations_in_range,
+    get_desktop_overall_tithe_balance, get_desktop_tithe_balance_ledger, get_desktop_tithe_year_balances,
+    get_desktop_total_donations_in_range,
 };
 use commands::expense_commands::get_desktop_total_expenses_in_range;
 use commands::income_commands::get_desktop_total_income_in_range;
@@ -31,7 +42,8 @@ use commands::import_commands::import_desktop_data_bulk;
 use commands::recurring_transaction_commands::{
     add_recurring_transaction_handler, delete_recurring_transaction_handler,
     get_due_recurring_transactions_handler, get_recurring_transaction_by_id_handler,
-    get_recurring_transactions_handler, update_recurring_transaction_handler,
+    get_recurring_transactions_handler, process_due_recurring_transactions,
+    update_recurring_transaction_handler,
 };
 use commands::transaction_commands::{
     add_transaction, delete_transaction_handler, export_transactions_handler,
@@ -39,8 +51,24 @@ use commands::transaction_commands::{
     get_transactions_count, get_distinct_payment_methods, update_transaction_handler,
 };
 use commands::platform_commands::{get_platform_info, copy_to_clipboard};
+use commands::tax_report_commands::{get_desktop_donation_tax_report, set_recipient_tax_deductible};
+use commands::recipient_commands::{
+    add_recipient, delete_recipient, get_recipient_by_id, get_recipients, merge_recipients, update_recipient,
+};
+use commands::budget_commands::{add_budget, delete_budget, get_budget_progress, get_budgets, update_budget};
+use commands::pledge_commands::{
+    add_pledge, create_pledge_payment_plan, delete_pledge, get_overdue_pledges, get_pledge_progress, get_pledges,
+    link_pledge_payment, unlink_pledge_payment, update_pledge,
+};
+use commands::default_currency_commands::{change_default_currency, preview_default_currency_change};
+use commands::exchange_rate_commands::{
+    get_exchange_rates, import_exchange_rates, lookup_exchange_rate, upsert_exchange_rates,
+};
+use commands::category_commands::{delete_category, get_categories, save_category};
+use commands::label_commands::{apply_label_change, preview_label_change};
+use commands::tithe_rules_commands::{delete_tithe_rules, get_tithe_rules, save_tithe_rules};
 
-pub struct DbState(Mutex<Connection>);
+use db::DbState;
 
 fn main() {
     env_logger::init();
@@ -67,6 +95,8 @@ fn main() {
             get_app_version,
             get_default_currency,
             set_default_currency,
+            preview_default_currency_change,
+            change_default_currency,
             get_app_setting,
             set_app_setting,
             delete_app_setting,
@@ -75,16 +105,26 @@ fn main() {
             get_desktop_total_expenses_in_range,
             get_desktop_total_donations_in_range,
             get_desktop_overall_tithe_balance,
+            get_desktop_tithe_balance_ledger,
+            get_desktop_tithe_year_balances,
             delete_transaction_handler,
             export_transactions_handler,
+            export_transactions_csv,
+            export_transactions_xlsx,
             get_filtered_transactions_handler,
             update_transaction_handler,
             get_last_known_rate,
+            upsert_exchange_rates,
+            import_exchange_rates,
+            lookup_exchange_rate,
+            get_exchange_rates,
             get_transactions_count,
             get_distinct_categories,
             get_distinct_payment_methods,
             get_desktop_monthly_financial_summary,
+            get_calendar_date_range,
             get_due_recurring_transactions_handler,
+            process_due_recurring_transactions,
             add_recurring_transaction_handler,
             get_recurring_transactions_handler,
             update_recurring_transaction_handler,
@@ -99,6 +139,36 @@ fn main() {
             get_desktop_recurring_vs_onetime,
             get_desktop_donation_recipients_breakdown,
             get_desktop_daily_heatmap,
+            get_tithe_rules,
+            save_tithe_rules,
+            delete_tithe_rules,
+            get_desktop_donation_tax_report,
+            set_recipient_tax_deductible,
+            get_recipients,
+            get_recipient_by_id,
+            add_recipient,
+            update_recipient,
+            merge_recipients,
+            delete_recipient,
+            preview_label_change,
+            apply_label_change,
+            get_categories,
+            save_category,
+            delete_category,
+            get_budgets,
+            add_budget,
+            update_budget,
+            delete_budget,
+            get_budget_progress,
+            get_pledges,
+            get_pledge_progress,
+            get_overdue_pledges,
+            add_pledge,
+            update_pledge,
+            delete_pledge,
+            link_pledge_payment,
+            unlink_pledge_payment,
+            create_pledge_payment_plan,
         ])
         // create main window with cache-busting index.html?v=<version>
         .setup(|app| {
@@ -125,8 +195,12 @@ fn main() {
             }
             
             // Update the database connection to use the new path
-            let conn = Connection::open(&db_path).expect("Failed to open database");
-            app.manage(DbState(Mutex::new(conn)));
+            // WAL mode: one writer plus pooled readers (see db.rs)
+            let db_state = DbState::open(&db_path).expect("Failed to open database");
+            app.manage(db_state);
+
+            // Keep recurring transactions up to date while the app sits in the tray
+            scheduler::start(app.handle().clone());
 
             let version = app.package_info().version.to_string();
 
@@ -0,0 +1,1016 @@
+// src-tauri/src/migrations.rs
+// Versioned schema migrations for the desktop SQLite database.
+//
+// Every migration has a unique, increasing version number and is applied at most
+// once. Each one runs inside its own SQLite transaction together with the row that
+// records it in `schema_migrations`, so a failing step rolls back completely and
+// the database is never left half-migrated.
+//
+// To change the schema, append a new `Migration` to `MIGRATIONS` — never edit or
+// reorder one that has already shipped.
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use uuid::Uuid;
+
+use crate::error::{Ten10Error, Ten10Result};
+use crate::money::minor_unit_scale_sql;
+use crate::search::register_search_functions;
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: fn(&rusqlite::Transaction) -> Result<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_core_tables",
+        up: create_core_tables,
+    },
+    Migration {
+        version: 2,
+        name: "add_legacy_missing_columns",
+        up: add_legacy_missing_columns,
+    },
+    Migration {
+        version: 3,
+        name: "normalize_category_labels",
+        up: normalize_category_labels,
+    },
+    Migration {
+        version: 4,
+        name: "drop_deprecated_recurring_columns",
+        up: drop_deprecated_recurring_columns,
+    },
+    Migration {
+        version: 5,
+        name: "create_tithe_rules",
+        up: create_tithe_rules,
+    },
+    Migration {
+        version: 6,
+        name: "store_amounts_as_minor_units",
+        up: store_amounts_as_minor_units,
+    },
+    Migration {
+        version: 7,
+        name: "create_recipients",
+        up: create_recipients,
+    },
+    Migration {
+        version: 8,
+        name: "link_transactions_to_recipients",
+        up: link_transactions_to_recipients,
+    },
+    Migration {
+        version: 9,
+        name: "create_categories",
+        up: create_categories,
+    },
+    Migration {
+        version: 10,
+        name: "create_budgets",
+        up: create_budgets,
+    },
+    Migration {
+        version: 11,
+        name: "create_pledges",
+        up: create_pledges,
+    },
+    Migration {
+        version: 12,
+        name: "create_transactions_fts",
+        up: create_transactions_fts,
+    },
+    Migration {
+        version: 13,
+        name: "create_transaction_indexes",
+        up: create_transaction_indexes,
+    },
+    Migration {
+        version: 14,
+        name: "create_exchange_rates",
+        up: create_exchange_rates,
+    },
+    Migration {
+        version: 15,
+        name: "key_transactions_fts_on_id",
+        up: key_transactions_fts_on_id,
+    },
+    Migration {
+        version: 16,
+        name: "create_conversion_history",
+        up: create_conversion_history,
+    },
+];
+
+/// Brings the database up to the latest schema version.
+/// Returns the versions that were applied by this call (empty when already up to date).
+pub fn run_migrations(conn: &mut Connection) -> Ten10Result<Vec<i64>> {
+    // The schema's triggers call into these.
+    register_search_functions(conn)?;
+    apply_migrations(conn, MIGRATIONS)
+}
+
+/// Applies every migration in `migrations` newer than the current schema version,
+/// in order, each in its own transaction. Stops at the first failure.
+pub fn apply_migrations(
+    conn: &mut Connection,
+    migrations: &[Migration],
+) -> Ten10Result<Vec<i64>> {
+    ensure_migrations_table(conn)?;
+    let current = current_version(conn)?;
+
+    let mut applied = Vec::new();
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx)
+            .and_then(|_| {
+                tx.execute(
+                    "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?1, ?2, ?3)",
+                    params![migration.version, migration.name, Utc::now().to_rfc3339()],
+                )
+            })
+            .map_err(|e| Ten10Error::Migration {
+                version: migration.version,
+                name: migration.name,
+                cause: e.to_string(),
+            })?;
+        tx.commit()?;
+
+        println!(
+            "[DB Migration] Applied {:04}_{}",
+            migration.version, migration.name
+        );
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+/// Highest applied migration version, or 0 for a database that has never been migrated.
+pub fn current_version(conn: &Connection) -> Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )
+}
+
+fn ensure_migrations_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// Helper function to check if a column exists in a table
+fn column_exists(conn: &Connection, table_name: &str, column_name: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table_name))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let name: String = row.get(1)?;
+        if name.eq_ignore_ascii_case(column_name) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, sql_type: &str) -> Result<()> {
+    if !column_exists(conn, table, column)? {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, sql_type),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+// ─── 0001: core tables ────────────────────────────────────────────────────────
+// Fresh installs get the full schema here. Databases created before versioned
+// migrations already have these tables, so every statement is IF NOT EXISTS.
+
+fn create_core_tables(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS transactions (
+            id TEXT PRIMARY KEY,
+            user_id TEXT,
+            date TEXT NOT NULL,
+            amount REAL NOT NULL,
+            currency TEXT NOT NULL,
+            description TEXT,
+            type TEXT NOT NULL,
+            category TEXT,
+            is_chomesh INTEGER,
+            recipient TEXT,
+            payment_method TEXT,
+            created_at TEXT,
+            updated_at TEXT,
+            source_recurring_id TEXT,
+            occurrence_number INTEGER,
+            original_amount REAL,
+            original_currency TEXT,
+            conversion_rate REAL,
+            conversion_date TEXT,
+            rate_source TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS recurring_transactions (
+            id TEXT PRIMARY KEY,
+            user_id TEXT,
+            status TEXT NOT NULL DEFAULT 'active',
+            start_date TEXT NOT NULL,
+            next_due_date TEXT NOT NULL,
+            frequency TEXT NOT NULL DEFAULT 'monthly',
+            day_of_month INTEGER NOT NULL,
+            total_occurrences INTEGER,
+            execution_count INTEGER NOT NULL DEFAULT 0,
+            description TEXT,
+            amount REAL NOT NULL,
+            currency TEXT NOT NULL,
+            type TEXT NOT NULL,
+            category TEXT,
+            is_chomesh INTEGER,
+            recipient TEXT,
+            payment_method TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            original_amount REAL,
+            original_currency TEXT,
+            conversion_rate REAL,
+            conversion_date TEXT,
+            rate_source TEXT
+        );
+
+        -- App settings table (for default_currency etc.) - survives WebView cache wipe on update
+        CREATE TABLE IF NOT EXISTS app_settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    )
+}
+
+// ─── 0002: columns added before versioned migrations ─────────────────────────
+// Older databases were upgraded ad hoc on every start; this catches up any of
+// those historical shapes in one step.
+
+fn add_legacy_missing_columns(tx: &rusqlite::Transaction) -> Result<()> {
+    for (column, sql_type) in [
+        ("original_amount", "REAL"),
+        ("original_currency", "TEXT"),
+        ("conversion_rate", "REAL"),
+        ("conversion_date", "TEXT"),
+        ("rate_source", "TEXT"),
+        ("payment_method", "TEXT"),
+    ] {
+        add_column_if_missing(tx, "recurring_transactions", column, sql_type)?;
+    }
+
+    for (column, sql_type) in [
+        ("source_recurring_id", "TEXT"),
+        ("occurrence_number", "INTEGER"),
+        ("original_amount", "REAL"),
+        ("original_currency", "TEXT"),
+        ("conversion_rate", "REAL"),
+        ("conversion_date", "TEXT"),
+        ("rate_source", "TEXT"),
+        ("payment_method", "TEXT"),
+    ] {
+        add_column_if_missing(tx, "transactions", column, sql_type)?;
+    }
+
+    Ok(())
+}
+
+// ─── 0003: normalize predefined category labels to stable keys ───────────────
+// Maps known Hebrew and English localized labels to canonical keys
+// (e.g. "מזון" or "Food" → "food"). Unknown values are left unchanged
+// so custom categories survive untouched.
+// Donation transactions do not support categories (they use recipient).
+
+fn normalize_category_labels(tx: &rusqlite::Transaction) -> Result<()> {
+    let normalize_category_sql =
+        "UPDATE {} SET category = CASE \
+          WHEN category IN ('Salary', 'משכורת')          THEN 'salary' \
+          WHEN category IN ('Business', 'עסק')             THEN 'business' \
+          WHEN category IN ('Freelance', 'עבודה עצמאית')  THEN 'freelance' \
+          WHEN category IN ('Investment', 'השקעות')        THEN 'investment' \
+          WHEN category IN ('Allowance', 'קצבאות')         THEN 'allowance' \
+          WHEN category IN ('Gift', 'מתנה')               THEN 'gift' \
+          WHEN category IN ('Food', 'מזון')               THEN 'food' \
+          WHEN category IN ('Transportation', 'תחבורה')   THEN 'transportation' \
+          WHEN category IN ('Housing', 'דיור')            THEN 'housing' \
+          WHEN category IN ('Utilities', 'שירותים')       THEN 'utilities' \
+          WHEN category IN ('Healthcare', 'בריאות')       THEN 'healthcare' \
+          WHEN category IN ('Education', 'חינוך')         THEN 'education' \
+          WHEN category IN ('Leisure', 'פנאי')            THEN 'leisure' \
+          WHEN category IN ('Shopping', 'קניות')          THEN 'shopping' \
+          WHEN category IN ('Other', 'אחר')               THEN 'other' \
+          ELSE category \
+        END \
+        WHERE category IN ( \
+          'Salary', 'משכורת', 'Business', 'עסק', 'Freelance', 'עבודה עצמאית', \
+          'Investment', 'השקעות', 'Allowance', 'קצבאות', 'Gift', 'מתנה', \
+          'Food', 'מזון', 'Transportation', 'תחבורה', 'Housing', 'דיור', \
+          'Utilities', 'שירותים', 'Healthcare', 'בריאות', 'Education', 'חינוך', \
+          'Leisure', 'פנאי', 'Shopping', 'קניות', 'Other', 'אחר' \
+        )";
+    tx.execute(&normalize_category_sql.replace("{}", "transactions"), [])?;
+    tx.execute(&normalize_category_sql.replace("{}", "recurring_transactions"), [])?;
+    Ok(())
+}
+
+// ─── 0004: drop the pre-`recurring_transactions` recurring columns ────────────
+
+fn drop_deprecated_recurring_columns(tx: &rusqlite::Transaction) -> Result<()> {
+    for col in ["is_recurring", "recurring_day_of_month", "recurring_total_count"] {
+        if column_exists(tx, "transactions", col)? {
+            tx.execute(&format!("ALTER TABLE transactions DROP COLUMN {}", col), [])?;
+            println!("[DB Migration] Dropped deprecated column: {}", col);
+        }
+    }
+    Ok(())
+}
+
+// ─── 0005: versioned tithe rules ──────────────────────────────────────────────
+// Seeds a base version that reproduces the original hard-coded split and covers
+// every date, so existing balances are unchanged.
+
+fn create_tithe_rules(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS tithe_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            effective_from TEXT NOT NULL UNIQUE,
+            maaser_percentage REAL NOT NULL DEFAULT 10,
+            chomesh_percentage REAL NOT NULL DEFAULT 10,
+            include_exempt_income INTEGER NOT NULL DEFAULT 0,
+            deduct_recognized_expenses INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL
+        );
+
+        INSERT OR IGNORE INTO tithe_rules (effective_from, created_at)
+            VALUES ('0000-01-01', strftime('%Y-%m-%dT%H:%M:%SZ', 'now'));",
+    )
+}
+
+// ─── 0006: amounts as INTEGER minor units ─────────────────────────────────────
+// SQLite cannot change a column type, so both tables are rebuilt. `amount` is
+// converted with the exponent of `currency`, `original_amount` with that of
+// `original_currency` (see money.rs).
+
+fn store_amounts_as_minor_units(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE transactions_new (
+            id TEXT PRIMARY KEY,
+            user_id TEXT,
+            date TEXT NOT NULL,
+            amount INTEGER NOT NULL,
+            currency TEXT NOT NULL,
+            description TEXT,
+            type TEXT NOT NULL,
+            category TEXT,
+            is_chomesh INTEGER,
+            recipient TEXT,
+            payment_method TEXT,
+            created_at TEXT,
+            updated_at TEXT,
+            source_recurring_id TEXT,
+            occurrence_number INTEGER,
+            original_amount INTEGER,
+            original_currency TEXT,
+            conversion_rate REAL,
+            conversion_date TEXT,
+            rate_source TEXT
+        );
+
+        CREATE TABLE recurring_transactions_new (
+            id TEXT PRIMARY KEY,
+            user_id TEXT,
+            status TEXT NOT NULL DEFAULT 'active',
+            start_date TEXT NOT NULL,
+            next_due_date TEXT NOT NULL,
+            frequency TEXT NOT NULL DEFAULT 'monthly',
+            day_of_month INTEGER NOT NULL,
+            total_occurrences INTEGER,
+            execution_count INTEGER NOT NULL DEFAULT 0,
+            description TEXT,
+            amount INTEGER NOT NULL,
+            currency TEXT NOT NULL,
+            type TEXT NOT NULL,
+            category TEXT,
+            is_chomesh INTEGER,
+            recipient TEXT,
+            payment_method TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            original_amount INTEGER,
+            original_currency TEXT,
+            conversion_rate REAL,
+            conversion_date TEXT,
+            rate_source TEXT
+        );",
+    )?;
+
+    tx.execute_batch(&format!(
+        "INSERT INTO transactions_new
+            SELECT id, user_id, date, CAST(ROUND(amount * {amount_scale}) AS INTEGER), currency,
+                   description, type, category, is_chomesh, recipient, payment_method, created_at,
+                   updated_at, source_recurring_id, occurrence_number,
+                   CAST(ROUND(original_amount * {original_scale}) AS INTEGER), original_currency,
+                   conversion_rate, conversion_date, rate_source
+            FROM transactions;
+
+        INSERT INTO recurring_transactions_new
+            SELECT id, user_id, status, start_date, next_due_date, frequency, day_of_month,
+                   total_occurrences, execution_count, description,
+                   CAST(ROUND(amount * {amount_scale}) AS INTEGER), currency, type, category,
+                   is_chomesh, recipient, payment_method, created_at, updated_at,
+                   CAST(ROUND(original_amount * {original_scale}) AS INTEGER), original_currency,
+                   conversion_rate, conversion_date, rate_source
+            FROM recurring_transactions;
+
+        DROP TABLE transactions;
+        ALTER TABLE transactions_new RENAME TO transactions;
+        DROP TABLE recurring_transactions;
+        ALTER TABLE recurring_transactions_new RENAME TO recurring_transactions;",
+        amount_scale = minor_unit_scale_sql("currency"),
+        original_scale = minor_unit_scale_sql("original_currency"),
+    ))?;
+    Ok(())
+}
+
+// ─── 0007: recipients ─────────────────────────────────────────────────────────
+// Attributes of a donation recipient, matched to `transactions.recipient` by
+// name (case-insensitive). At this version only recipients the user had marked
+// got a row; 0008 creates one for every recipient in use.
+
+fn create_recipients(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS recipients (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            tax_deductible INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );",
+    )
+}
+
+// ─── 0008: recipients as an entity ────────────────────────────────────────────
+// Adds the recipient metadata and aliases, and a `recipient_id` link on both
+// transaction tables. Every distinct recipient string becomes a recipient
+// (spellings that differ only in case or surrounding spaces share one) and the
+// existing rows are linked to it. `recipient` keeps the text as typed.
+
+fn link_transactions_to_recipients(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE recipients ADD COLUMN registration_number TEXT;
+        ALTER TABLE recipients ADD COLUMN notes TEXT;
+        ALTER TABLE recipients ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
+
+        CREATE TABLE IF NOT EXISTS recipient_aliases (
+            alias TEXT PRIMARY KEY COLLATE NOCASE,
+            recipient_id TEXT NOT NULL REFERENCES recipients(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_recipient_aliases_recipient_id ON recipient_aliases(recipient_id);
+
+        ALTER TABLE transactions ADD COLUMN recipient_id TEXT REFERENCES recipients(id);
+        ALTER TABLE recurring_transactions ADD COLUMN recipient_id TEXT REFERENCES recipients(id);
+        CREATE INDEX IF NOT EXISTS idx_transactions_recipient_id ON transactions(recipient_id);",
+    )?;
+
+    // Frozen copy of the linking as it shipped with this version, so later changes
+    // to recipient resolution never change what this migration does.
+    let now = Utc::now().to_rfc3339();
+    for table in ["transactions", "recurring_transactions"] {
+        let names: Vec<String> = tx
+            .prepare(&format!(
+                "SELECT DISTINCT TRIM(recipient) FROM {} WHERE recipient_id IS NULL AND TRIM(COALESCE(recipient, '')) <> ''",
+                table
+            ))?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_>>()?;
+        for name in names {
+            let existing: Option<String> = tx
+                .query_row("SELECT id FROM recipients WHERE name = ?1", params![name], |row| row.get(0))
+                .optional()?;
+            let id = match existing {
+                Some(id) => id,
+                None => {
+                    let id = Uuid::new_v4().to_string();
+                    tx.execute(
+                        "INSERT INTO recipients (id, name, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)",
+                        params![id, name, now],
+                    )?;
+                    id
+                }
+            };
+            tx.execute(
+                &format!(
+                    "UPDATE {} SET recipient_id = ?1 WHERE recipient_id IS NULL AND TRIM(recipient) = ?2 COLLATE NOCASE",
+                    table
+                ),
+                params![id, name],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// ─── 0009: category tree ──────────────────────────────────────────────────────
+// A row per category value (`key` is what `transactions.category` stores), with
+// an optional parent, the base types it applies to (comma-separated, empty for
+// all), display attributes and ordering. Seeded with the predefined keys and
+// every custom category already in use, all at the top level.
+
+/// Predefined category keys per base type, in display order (as in the
+/// frontend's category-registry.ts).
+const PREDEFINED_CATEGORIES: &[(&str, &str)] = &[
+    ("salary", "income"),
+    ("business", "income"),
+    ("freelance", "income"),
+    ("investment", "income"),
+    ("allowance", "income"),
+    ("gift", "income"),
+    ("food", "expense"),
+    ("transportation", "expense"),
+    ("housing", "expense"),
+    ("utilities", "expense"),
+    ("healthcare", "expense"),
+    ("education", "expense"),
+    ("leisure", "expense"),
+    ("shopping", "expense"),
+    ("other", "income,expense"),
+];
+
+fn create_categories(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS categories (
+            key TEXT PRIMARY KEY,
+            parent_key TEXT REFERENCES categories(key) ON UPDATE CASCADE,
+            transaction_types TEXT NOT NULL DEFAULT '',
+            icon TEXT,
+            color TEXT,
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_categories_parent_key ON categories(parent_key);",
+    )?;
+
+    let now = Utc::now().to_rfc3339();
+    for (order, (key, types)) in PREDEFINED_CATEGORIES.iter().enumerate() {
+        tx.execute(
+            "INSERT OR IGNORE INTO categories (key, transaction_types, sort_order, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?4)",
+            params![key, types, order as i64, now],
+        )?;
+    }
+    tx.execute(
+        "INSERT OR IGNORE INTO categories (key, transaction_types, sort_order, created_at, updated_at)
+         SELECT category, GROUP_CONCAT(DISTINCT base_type), ?1 + ROW_NUMBER() OVER (ORDER BY category), ?2, ?2
+         FROM (
+             SELECT category,
+                    CASE type WHEN 'exempt-income' THEN 'income'
+                              WHEN 'recognized-expense' THEN 'expense'
+                              WHEN 'non_tithe_donation' THEN 'donation'
+                              ELSE type END AS base_type
+             FROM (
+                 SELECT category, type FROM transactions
+                 UNION ALL
+                 SELECT category, type FROM recurring_transactions
+             )
+             WHERE category IS NOT NULL AND category != ''
+         )
+         GROUP BY category",
+        params![PREDEFINED_CATEGORIES.len() as i64, now],
+    )?;
+    Ok(())
+}
+
+// ─── 0010: budgets ────────────────────────────────────────────────────────────
+// A spending limit per period for one category (with its subcategories) or one
+// payment method. `amount` is in minor units of `currency`, the default currency.
+
+fn create_budgets(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS budgets (
+            id TEXT PRIMARY KEY,
+            category TEXT,
+            payment_method TEXT,
+            period TEXT NOT NULL CHECK (period IN ('monthly', 'yearly')),
+            amount INTEGER NOT NULL,
+            currency TEXT NOT NULL,
+            rollover INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            CHECK ((category IS NULL) <> (payment_method IS NULL))
+        );",
+    )
+}
+
+// ─── 0011: pledges ────────────────────────────────────────────────────────────
+// A promise to donate `amount` (minor units of `currency`) to a recipient by a
+// deadline. Payments are donations linked in `pledge_payments`, plus the ones
+// generated by the pledge's payment plan (`recurring_id`).
+
+fn create_pledges(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS pledges (
+            id TEXT PRIMARY KEY,
+            recipient_id TEXT NOT NULL REFERENCES recipients(id),
+            amount INTEGER NOT NULL,
+            currency TEXT NOT NULL,
+            start_date TEXT NOT NULL,
+            deadline TEXT NOT NULL,
+            notes TEXT,
+            cancelled INTEGER NOT NULL DEFAULT 0,
+            recurring_id TEXT REFERENCES recurring_transactions(id) ON DELETE SET NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_pledges_recipient_id ON pledges(recipient_id);
+
+        CREATE TABLE IF NOT EXISTS pledge_payments (
+            pledge_id TEXT NOT NULL REFERENCES pledges(id) ON DELETE CASCADE,
+            transaction_id TEXT NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+            PRIMARY KEY (pledge_id, transaction_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_pledge_payments_transaction_id ON pledge_payments(transaction_id);",
+    )
+}
+
+// ─── 0012: full-text search ───────────────────────────────────────────────────
+// `transactions_fts` indexes the searchable columns of each transaction under its
+// rowid, normalized by `search_text` (see `search::register_search_functions`).
+// Triggers keep it in sync; a migration rebuilding `transactions` must rebuild it too.
+// 0015 replaces the rowid key with the transaction id.
+
+const FTS_COLUMNS: [&str; 4] = ["description", "category", "recipient", "payment_method"];
+
+fn create_transactions_fts(tx: &rusqlite::Transaction) -> Result<()> {
+    let normalized = |row: &str| {
+        FTS_COLUMNS
+            .iter()
+            .map(|c| format!("search_text({}.{})", row, c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let columns = FTS_COLUMNS.join(", ");
+    tx.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS transactions_fts USING fts5(
+            {columns},
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS transactions_fts_insert AFTER INSERT ON transactions BEGIN
+            INSERT OR REPLACE INTO transactions_fts (rowid, {columns}) VALUES (new.rowid, {new});
+        END;
+        CREATE TRIGGER IF NOT EXISTS transactions_fts_update AFTER UPDATE OF {columns} ON transactions BEGIN
+            DELETE FROM transactions_fts WHERE rowid = old.rowid;
+            INSERT OR REPLACE INTO transactions_fts (rowid, {columns}) VALUES (new.rowid, {new});
+        END;
+        CREATE TRIGGER IF NOT EXISTS transactions_fts_delete AFTER DELETE ON transactions BEGIN
+            DELETE FROM transactions_fts WHERE rowid = old.rowid;
+        END;
+
+        DELETE FROM transactions_fts;
+        INSERT INTO transactions_fts (rowid, {columns}) SELECT rowid, {t} FROM transactions t;",
+        new = normalized("new"),
+        t = normalized("t"),
+    ))
+}
+
+// ─── 0013: indexes for the hot transaction queries ────────────────────────────
+// Period queries filter on `date` with or without a type group, the table joins
+// the recurring definition, the last known rate looks up a currency pair by
+// date, donations are grouped by recipient and the payment methods are listed
+// distinct. `query_plans.rs` pins their use.
+
+fn create_transaction_indexes(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_transactions_type_date ON transactions(type, date);
+        CREATE INDEX IF NOT EXISTS idx_transactions_date ON transactions(date);
+        CREATE INDEX IF NOT EXISTS idx_transactions_source_recurring_id ON transactions(source_recurring_id);
+        CREATE INDEX IF NOT EXISTS idx_transactions_conversion ON transactions(original_currency, currency, date);
+        CREATE INDEX IF NOT EXISTS idx_transactions_recipient ON transactions(recipient);
+        CREATE INDEX IF NOT EXISTS idx_transactions_payment_method ON transactions(payment_method);",
+    )
+}
+
+// ─── 0014: exchange rates ─────────────────────────────────────────────────────
+// One rate per currency pair and day: 1 `base` = `rate` `quote`, with where it
+// came from (a provider, an imported Bank of Israel or ECB file, or "manual").
+
+fn create_exchange_rates(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS exchange_rates (
+            date TEXT NOT NULL,
+            base TEXT NOT NULL,
+            quote TEXT NOT NULL,
+            rate REAL NOT NULL CHECK (rate > 0),
+            source TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (base, quote, date)
+        );",
+    )
+}
+
+// ─── 0015: full-text search keyed on the transaction id ──────────────────────
+// `transactions` has a TEXT primary key, so its rowid is implicit and VACUUM may
+// renumber it, leaving the index pointing at the wrong rows. The index is rebuilt
+// with the transaction `id` as an unindexed column, which searches join on.
+
+fn key_transactions_fts_on_id(tx: &rusqlite::Transaction) -> Result<()> {
+    let normalized = |row: &str| {
+        FTS_COLUMNS
+            .iter()
+            .map(|c| format!("search_text({}.{})", row, c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let columns = FTS_COLUMNS.join(", ");
+    tx.execute_batch(&format!(
+        "DROP TRIGGER IF EXISTS transactions_fts_insert;
+        DROP TRIGGER IF EXISTS transactions_fts_update;
+        DROP TRIGGER IF EXISTS transactions_fts_delete;
+        DROP TABLE IF EXISTS transactions_fts;
+
+        CREATE VIRTUAL TABLE transactions_fts USING fts5(
+            id UNINDEXED,
+            {columns},
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        CREATE TRIGGER transactions_fts_insert AFTER INSERT ON transactions BEGIN
+            INSERT INTO transactions_fts (id, {columns}) VALUES (new.id, {new});
+        END;
+        CREATE TRIGGER transactions_fts_update AFTER UPDATE OF id, {columns} ON transactions BEGIN
+            DELETE FROM transactions_fts WHERE id = old.id;
+            INSERT INTO transactions_fts (id, {columns}) VALUES (new.id, {new});
+        END;
+        CREATE TRIGGER transactions_fts_delete AFTER DELETE ON transactions BEGIN
+            DELETE FROM transactions_fts WHERE id = old.id;
+        END;
+
+        INSERT INTO transactions_fts (id, {columns}) SELECT id, {t} FROM transactions t;",
+        new = normalized("new"),
+        t = normalized("t"),
+    ))
+}
+
+// ─── 0016: conversion history ─────────────────────────────────────────────────
+// Changing the default currency overwrites the amount and conversion of every
+// transaction, recurring template and budget; the values they had are kept here,
+// one row per replaced version (`source_table` + `row_id` name the row).
+
+fn create_conversion_history(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS conversion_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_table TEXT NOT NULL,
+            row_id TEXT NOT NULL,
+            amount INTEGER NOT NULL,
+            currency TEXT NOT NULL,
+            original_amount INTEGER,
+            original_currency TEXT,
+            conversion_rate REAL,
+            conversion_date TEXT,
+            rate_source TEXT,
+            replaced_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_conversion_history_row ON conversion_history(source_table, row_id);",
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Upgrade harness: every historical database shape must migrate to exactly the
+// schema a fresh install gets, keep its data, and be a no-op on the next start.
+// ---------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(table, column, type)` for every user table, sorted — used to compare shapes.
+    fn schema_of(conn: &Connection) -> Vec<(String, String, String)> {
+        let mut tables: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        tables.sort();
+
+        let mut columns = Vec::new();
+        for table in tables {
+            let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).unwrap();
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+                .unwrap();
+            for row in rows {
+                let (name, ty) = row.unwrap();
+                columns.push((table.clone(), name, ty));
+            }
+        }
+        columns.sort();
+        columns
+    }
+
+    fn head_schema() -> Vec<(String, String, String)> {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        schema_of(&conn)
+    }
+
+    fn latest_version() -> i64 {
+        MIGRATIONS.last().unwrap().version
+    }
+
+    /// Earliest desktop shape: recurring info lived on `transactions` itself,
+    /// no currency conversion and no `recurring_transactions` payment method.
+    const FIXTURE_V0_5: &str = "
+        CREATE TABLE transactions (
+            id TEXT PRIMARY KEY, user_id TEXT, date TEXT NOT NULL, amount REAL NOT NULL,
+            currency TEXT NOT NULL, description TEXT, type TEXT NOT NULL, category TEXT,
+            is_chomesh INTEGER, recipient TEXT, created_at TEXT, updated_at TEXT,
+            is_recurring INTEGER, recurring_day_of_month INTEGER, recurring_total_count INTEGER
+        );
+        INSERT INTO transactions (id, date, amount, currency, type, category, is_recurring)
+            VALUES ('t1', '2024-01-01', 1000, 'ILS', 'income', 'משכורת', 0);
+    ";
+
+    /// Recurring definitions split out, but before currency conversion columns.
+    const FIXTURE_V0_6: &str = "
+        CREATE TABLE transactions (
+            id TEXT PRIMARY KEY, user_id TEXT, date TEXT NOT NULL, amount REAL NOT NULL,
+            currency TEXT NOT NULL, description TEXT, type TEXT NOT NULL, category TEXT,
+            is_chomesh INTEGER, recipient TEXT, payment_method TEXT, created_at TEXT,
+            updated_at TEXT, source_recurring_id TEXT, occurrence_number INTEGER
+        );
+        CREATE TABLE recurring_transactions (
+            id TEXT PRIMARY KEY, user_id TEXT, status TEXT NOT NULL DEFAULT 'active',
+            start_date TEXT NOT NULL, next_due_date TEXT NOT NULL,
+            frequency TEXT NOT NULL DEFAULT 'monthly', day_of_month INTEGER NOT NULL,
+            total_occurrences INTEGER, execution_count INTEGER NOT NULL DEFAULT 0,
+            description TEXT, amount REAL NOT NULL, currency TEXT NOT NULL, type TEXT NOT NULL,
+            category TEXT, is_chomesh INTEGER, recipient TEXT,
+            created_at TEXT NOT NULL, updated_at TEXT NOT NULL
+        );
+        INSERT INTO recurring_transactions
+            (id, start_date, next_due_date, day_of_month, amount, currency, type, category, created_at, updated_at)
+            VALUES ('r1', '2024-01-01', '2024-02-01', 1, 50, 'ILS', 'expense', 'Food', '2024-01-01', '2024-01-01');
+        INSERT INTO transactions (id, date, amount, currency, type, category, source_recurring_id, occurrence_number)
+            VALUES ('t1', '2024-01-01', 50, 'ILS', 'expense', 'Food', 'r1', 1);
+    ";
+
+    /// Last shape produced by the ad-hoc `init_db`: complete tables, no migration tracking.
+    const FIXTURE_V0_7: &str = "
+        CREATE TABLE transactions (
+            id TEXT PRIMARY KEY, user_id TEXT, date TEXT NOT NULL, amount REAL NOT NULL,
+            currency TEXT NOT NULL, description TEXT, type TEXT NOT NULL, category TEXT,
+            is_chomesh INTEGER, recipient TEXT, payment_method TEXT, created_at TEXT,
+            updated_at TEXT, original_amount REAL, original_currency TEXT, conversion_rate REAL,
+            conversion_date TEXT, rate_source TEXT, source_recurring_id TEXT, occurrence_number INTEGER
+        );
+        CREATE TABLE recurring_transactions (
+            id TEXT PRIMARY KEY, user_id TEXT, status TEXT NOT NULL DEFAULT 'active',
+            start_date TEXT NOT NULL, next_due_date TEXT NOT NULL,
+            frequency TEXT NOT NULL DEFAULT 'monthly', day_of_month INTEGER NOT NULL,
+            total_occurrences INTEGER, execution_count INTEGER NOT NULL DEFAULT 0,
+            description TEXT, amount REAL NOT NULL, currency TEXT NOT NULL, type TEXT NOT NULL,
+            category TEXT, is_chomesh INTEGER, recipient TEXT, payment_method TEXT,
+            created_at TEXT NOT NULL, updated_at TEXT NOT NULL, original_amount REAL,
+            original_currency TEXT, conversion_rate REAL, conversion_date TEXT, rate_source TEXT
+        );
+        CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+        INSERT INTO app_settings (key, value) VALUES ('default_currency', 'USD');
+        INSERT INTO transactions (id, date, amount, currency, type, category, original_amount, original_currency, conversion_rate)
+            VALUES ('t1', '2024-01-01', 370, 'ILS', 'income', 'salary', 100, 'USD', 3.7);
+    ";
+
+    fn upgrade(fixture: &str) -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(fixture).unwrap();
+        let applied = run_migrations(&mut conn).expect("migrations apply");
+        assert_eq!(applied, MIGRATIONS.iter().map(|m| m.version).collect::<Vec<_>>());
+        conn
+    }
+
+    #[test]
+    fn versions_are_strictly_increasing() {
+        for pair in MIGRATIONS.windows(2) {
+            assert!(pair[0].version < pair[1].version, "{} !< {}", pair[0].name, pair[1].name);
+        }
+    }
+
+    #[test]
+    fn fresh_database_migrates_to_head() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let applied = run_migrations(&mut conn).unwrap();
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn every_historical_shape_upgrades_to_head_schema() {
+        let head = head_schema();
+        for fixture in [FIXTURE_V0_5, FIXTURE_V0_6, FIXTURE_V0_7] {
+            let conn = upgrade(fixture);
+            assert_eq!(schema_of(&conn), head);
+            assert_eq!(current_version(&conn).unwrap(), latest_version());
+        }
+    }
+
+    #[test]
+    fn upgrade_keeps_data_and_normalizes_categories() {
+        let conn = upgrade(FIXTURE_V0_5);
+        let category: String = conn
+            .query_row("SELECT category FROM transactions WHERE id = 't1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(category, "salary");
+
+        let conn = upgrade(FIXTURE_V0_6);
+        let (tx_cat, rec_cat): (String, String) = conn
+            .query_row(
+                "SELECT t.category, rt.category FROM transactions t
+                 JOIN recurring_transactions rt ON t.source_recurring_id = rt.id",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((tx_cat.as_str(), rec_cat.as_str()), ("food", "food"));
+
+        let conn = upgrade(FIXTURE_V0_7);
+        let amounts: (i64, i64) = conn
+            .query_row("SELECT amount, original_amount FROM transactions WHERE id = 't1'", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(amounts, (37000, 10000));
+        let (rate, currency): (f64, String) = conn
+            .query_row(
+                "SELECT t.conversion_rate, s.value FROM transactions t, app_settings s
+                 WHERE t.id = 't1' AND s.key = 'default_currency'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((rate, currency.as_str()), (3.7, "USD"));
+    }
+
+    #[test]
+    fn second_run_is_a_noop() {
+        let mut conn = upgrade(FIXTURE_V0_6);
+        assert!(run_migrations(&mut conn).unwrap().is_empty());
+        let recorded: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_migrations", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(recorded, MIGRATIONS.len() as i64);
+    }
+
+    fn insert_marker(tx: &rusqlite::Transaction) -> Result<()> {
+        tx.execute("INSERT INTO app_settings (key, value) VALUES ('marker', 'x')", [])?;
+        Ok(())
+    }
+
+    fn broken(tx: &rusqlite::Transaction) -> Result<()> {
+        tx.execute("INSERT INTO app_settings (key, value) VALUES ('half', 'done')", [])?;
+        tx.execute("ALTER TABLE no_such_table ADD COLUMN x TEXT", [])?;
+        Ok(())
+    }
+
+    #[test]
+    fn failed_migration_rolls_back_and_stops() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        let head = latest_version();
+
+        let extra = [
+            Migration { version: head + 1, name: "marker", up: insert_marker },
+            Migration { version: head + 2, name: "broken", up: broken },
+            Migration { version: head + 3, name: "never_reached", up: insert_marker },
+        ];
+        let err = apply_migrations(&mut conn, &extra).unwrap_err();
+        assert!(
+            matches!(err, Ten10Error::Migration { version, name: "broken", .. } if version == head + 2),
+            "{}",
+            err
+        );
+
+        // The step before the failure is kept, the failing step left no trace.
+        assert_eq!(current_version(&conn).unwrap(), head + 1);
+        let half: i64 = conn
+            .query_row("SELECT COUNT(*) FROM app_settings WHERE key = 'half'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(half, 0);
+    }
+}
@@ -2,13 +2,15 @@
 use rusqlite::Result as RusqliteResult;
 use serde::{Deserialize, Serialize};
 
+use crate::money::Money;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Transaction {
     pub id: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub user_id: Option<String>,
     pub date: String,
-    pub amount: f64,
+    pub amount: Money,
     pub currency: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
@@ -31,7 +33,7 @@ pub struct Transaction {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub occurrence_number: Option<i32>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub original_amount: Option<f64>,
+    pub original_amount: Option<Money>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub original_currency: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -44,12 +46,15 @@ pub struct Transaction {
 
 impl Transaction {
     pub fn from_row(row: &rusqlite::Row<'_>) -> RusqliteResult<Self> {
+        let currency: String = row.get("currency")?;
+        let original_currency: Option<String> = row.get("original_currency")?;
+
         Ok(Transaction {
             id: row.get("id")?,
             user_id: row.get("user_id")?,
             date: row.get("date")?,
-            amount: row.get("amount")?,
-            currency: row.get("currency")?,
+            amount: Money::from_minor(row.get("amount")?, &currency),
+            currency,
             description: row.get("description")?,
             transaction_type: row.get("type")?,
             category: row.get("category")?,
@@ -60,8 +65,8 @@ impl Transaction {
             updated_at: row.get("updated_at")?,
             source_recurring_id: row.get("source_recurring_id")?,
             occurrence_number: row.get("occurrence_number")?,
-            original_amount: row.get("original_amount")?,
-            original_currency: row.get("original_currency")?,
+            original_amount: minor_amount(row, "original_amount", original_currency.as_deref())?,
+            original_currency,
             conversion_rate: row.get("conversion_rate")?,
             conversion_date: row.get("conversion_date")?,
             rate_source: row.get("rate_source")?,
@@ -69,6 +74,12 @@ impl Transaction {
     }
 }
 
+/// Nullable minor-unit column; an amount without a currency falls back to the common exponent.
+fn minor_amount(row: &rusqlite::Row<'_>, column: &str, currency: Option<&str>) -> RusqliteResult<Option<Money>> {
+    let minor: Option<i64> = row.get(column)?;
+    Ok(minor.map(|m| Money::from_minor(m, currency.unwrap_or_default())))
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RecurringTransaction {
     pub id: String,
@@ -84,7 +95,7 @@ pub struct RecurringTransaction {
     pub execution_count: i32,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
-    pub amount: f64,
+    pub amount: Money,
     pub currency: String,
     #[serde(rename = "type")]
     pub transaction_type: String,
@@ -99,7 +110,7 @@ pub struct RecurringTransaction {
     pub created_at: String,
     pub updated_at: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub original_amount: Option<f64>,
+    pub original_amount: Option<Money>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub original_currency: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -112,8 +123,9 @@ pub struct RecurringTransaction {
 
 impl RecurringTransaction {
     pub fn from_row(row: &rusqlite::Row<'_>) -> RusqliteResult<Self> {
-        let original_amount: Option<f64> = row.get("original_amount")?;
+        let currency: String = row.get("currency")?;
         let original_currency: Option<String> = row.get("original_currency")?;
+        let original_amount = minor_amount(row, "original_amount", original_currency.as_deref())?;
         let conversion_rate: Option<f64> = row.get("conversion_rate")?;
         let conversion_date: Option<String> = row.get("conversion_date")?;
         let rate_source: Option<String> = row.get("rate_source")?;
@@ -129,8 +141,8 @@ impl RecurringTransaction {
             total_occurrences: row.get("total_occurrences")?,
             execution_count: row.get("execution_count")?,
             description: row.get("description")?,
-            amount: row.get("amount")?,
-            currency: row.get("currency")?,
+            amount: Money::from_minor(row.get("amount")?, &currency),
+            currency,
             transaction_type: row.get("type")?,
             category: row.get("category")?,
             is_chomesh: row.get::<_, Option<i32>>("is_chomesh")?.map(|v| v != 0),
@@ -171,3 +183,206 @@ pub struct TitheBalanceBreakdown {
     pub maaser_balance: f64,
     pub chomesh_balance: f64,
 }
+
+/// One version of the tithe calculation rules, in force from `effective_from`
+/// until the next version. Percentages are whole numbers (10.0 = 10%).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TitheRules {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub effective_from: String,
+    pub maaser_percentage: f64,
+    pub chomesh_percentage: f64,
+    pub include_exempt_income: bool,
+    pub deduct_recognized_expenses: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+impl TitheRules {
+    pub fn from_row(row: &rusqlite::Row<'_>) -> RusqliteResult<Self> {
+        Ok(TitheRules {
+            id: row.get("id")?,
+            effective_from: row.get("effective_from")?,
+            maaser_percentage: row.get("maaser_percentage")?,
+            chomesh_percentage: row.get("chomesh_percentage")?,
+            include_exempt_income: row.get::<_, i64>("include_exempt_income")? != 0,
+            deduct_recognized_expenses: row.get::<_, i64>("deduct_recognized_expenses")? != 0,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+/// A donation recipient. Transactions link to it through `recipient_id`; their
+/// `recipient` text (the name or any alias, as typed) is resolved on save.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Recipient {
+    pub id: String,
+    pub name: String,
+    /// Other spellings that resolve to this recipient
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub tax_deductible: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registration_number: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    pub archived: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Recipient {
+    /// Reads a `recipients` row; `aliases` are loaded separately.
+    pub fn from_row(row: &rusqlite::Row<'_>) -> RusqliteResult<Self> {
+        Ok(Recipient {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            aliases: Vec::new(),
+            tax_deductible: row.get::<_, i64>("tax_deductible")? != 0,
+            registration_number: row.get("registration_number")?,
+            notes: row.get("notes")?,
+            archived: row.get::<_, i64>("archived")? != 0,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
+
+/// A node of the category tree. `key` is the value `transactions.category`
+/// stores: a predefined key ("food") or the custom text as typed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Category {
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_key: Option<String>,
+    /// Base types ("income", "expense", "donation") it applies to; empty for all
+    #[serde(default)]
+    pub transaction_types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    pub sort_order: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Category {
+    pub fn from_row(row: &rusqlite::Row<'_>) -> RusqliteResult<Self> {
+        let types: String = row.get("transaction_types")?;
+        Ok(Category {
+            key: row.get("key")?,
+            parent_key: row.get("parent_key")?,
+            transaction_types: types.split(',').filter(|t| !t.is_empty()).map(str::to_string).collect(),
+            icon: row.get("icon")?,
+            color: row.get("color")?,
+            sort_order: row.get("sort_order")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
+
+/// A spending limit per period for a category (including its subcategories) or
+/// a payment method; exactly one of the two is set.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Budget {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_method: Option<String>,
+    /// "monthly" or "yearly"
+    pub period: String,
+    pub amount: Money,
+    pub currency: String,
+    /// Whether what is left of (or overspent in) a period carries over to the next
+    pub rollover: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Budget {
+    pub fn from_row(row: &rusqlite::Row<'_>) -> RusqliteResult<Self> {
+        let currency: String = row.get("currency")?;
+        Ok(Budget {
+            id: row.get("id")?,
+            category: row.get("category")?,
+            payment_method: row.get("payment_method")?,
+            period: row.get("period")?,
+            amount: Money::from_minor(row.get("amount")?, &currency),
+            currency,
+            rollover: row.get::<_, i64>("rollover")? != 0,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
+
+/// A promise to donate `amount` to a recipient by `deadline`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Pledge {
+    pub id: String,
+    pub recipient_id: String,
+    /// The recipient's name
+    pub recipient: String,
+    pub amount: Money,
+    pub currency: String,
+    pub start_date: String,
+    pub deadline: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    pub cancelled: bool,
+    /// The recurring donation that pays it off, if one was generated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurring_id: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Pledge {
+    /// Reads a `pledges` row joined with its recipient's name as `recipient`.
+    pub fn from_row(row: &rusqlite::Row<'_>) -> RusqliteResult<Self> {
+        let currency: String = row.get("currency")?;
+        Ok(Pledge {
+            id: row.get("id")?,
+            recipient_id: row.get("recipient_id")?,
+            recipient: row.get("recipient")?,
+            amount: Money::from_minor(row.get("amount")?, &currency),
+            currency,
+            start_date: row.get("start_date")?,
+            deadline: row.get("deadline")?,
+            notes: row.get("notes")?,
+            cancelled: row.get::<_, i64>("cancelled")? != 0,
+            recurring_id: row.get("recurring_id")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
+
+/// 1 `base` = `rate` `quote` on `date`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExchangeRate {
+    pub date: String,
+    pub base: String,
+    pub quote: String,
+    pub rate: f64,
+    /// "manual", "boi", "ecb" or the provider the frontend fetched it from
+    pub source: String,
+    pub updated_at: String,
+}
+
+impl ExchangeRate {
+    pub fn from_row(row: &rusqlite::Row<'_>) -> RusqliteResult<Self> {
+        Ok(ExchangeRate {
+            date: row.get("date")?,
+            base: row.get("base")?,
+            quote: row.get("quote")?,
+            rate: row.get("rate")?,
+            source: row.get("source")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
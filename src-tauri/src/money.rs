@@ -0,0 +1,287 @@
+// src-tauri/src/money.rs
+// Amounts are stored as INTEGER minor units (agorot, cents) of the row's currency,
+// using the ISO-4217 exponent of that currency (2 for ILS/USD/EUR, 0 for JPY, 3 for KWD).
+//
+// Over IPC an amount is still a plain JSON number in major units, so the frontend
+// is unaffected. `Money` keeps the decimal value it was given exactly; it is only
+// rounded to a currency's minor unit when written to the database.
+//
+// Aggregates scale each row to micro-units (10^-6) of its own currency, which is an
+// exact integer multiplication, sum those and divide once at the end
+// (`major_sum_sql`), so a total never picks up floating-point drift from thousands
+// of additions and does not depend on which currency is the default.
+
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Ten10Error, Ten10Result};
+
+/// Fraction digits kept for an amount that is not (yet) tied to a currency.
+const MAX_EXPONENT: u32 = 6;
+/// `10^MAX_EXPONENT`: the common unit SQL aggregates sum in.
+const MICRO_SCALE: i64 = 10i64.pow(MAX_EXPONENT);
+
+const ZERO_DECIMAL_CURRENCIES: &[&str] = &[
+    "BIF", "CLP", "DJF", "GNF", "ISK", "JPY", "KMF", "KRW", "PYG", "RWF", "UGX", "UYI", "VND",
+    "VUV", "XAF", "XOF", "XPF",
+];
+const THREE_DECIMAL_CURRENCIES: &[&str] = &["BHD", "IQD", "JOD", "KWD", "LYD", "OMR", "TND"];
+const FOUR_DECIMAL_CURRENCIES: &[&str] = &["CLF", "UYW"];
+
+/// ISO-4217 minor unit exponent; unknown codes get the common 2.
+pub fn minor_unit_exponent(currency: &str) -> u32 {
+    let code = currency.trim().to_ascii_uppercase();
+    if ZERO_DECIMAL_CURRENCIES.contains(&code.as_str()) {
+        0
+    } else if THREE_DECIMAL_CURRENCIES.contains(&code.as_str()) {
+        3
+    } else if FOUR_DECIMAL_CURRENCIES.contains(&code.as_str()) {
+        4
+    } else {
+        2
+    }
+}
+
+/// An exact decimal amount: `minor / 10^exponent`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Money {
+    minor: i64,
+    exponent: u32,
+}
+
+impl Money {
+    /// Amount read from the database, in minor units of `currency`.
+    pub fn from_minor(minor: i64, currency: &str) -> Self {
+        Money { minor, exponent: minor_unit_exponent(currency) }
+    }
+
+    /// Amount in micro-units (10^-6), as produced by summing `to_micro_sql` in SQL.
+    pub fn from_micro(micro: i64) -> Self {
+        Money { minor: micro, exponent: MAX_EXPONENT }
+    }
+
+    /// Major-unit value rounded (half away from zero) to the minor unit of `currency`.
+    /// Fails for a non-finite value or one that does not fit in i64 minor units.
+    pub fn from_major(value: f64, currency: &str) -> Ten10Result<Self> {
+        let exponent = minor_unit_exponent(currency);
+        let minor = parse_decimal(value, exponent)
+            .ok_or_else(|| Ten10Error::validation(format!("Amount out of range: {} {}", value, currency)))?;
+        Ok(Money { minor, exponent })
+    }
+
+    /// Minor units of `currency`, rounding half away from zero when it has fewer fraction digits.
+    pub fn to_minor(self, currency: &str) -> i64 {
+        rescale(self.minor, self.exponent, minor_unit_exponent(currency))
+    }
+
+    pub fn to_major(self) -> f64 {
+        // Dividing two exact values gives the closest f64 to the decimal, and 0 is never -0.
+        self.minor as f64 / 10f64.powi(self.exponent as i32)
+    }
+
+    /// Converts at `rate` into `currency`, rounded to its minor unit.
+    pub fn convert(self, rate: f64, currency: &str) -> Ten10Result<Self> {
+        Money::from_major(self.to_major() * rate, currency)
+    }
+}
+
+fn rescale(minor: i64, from: u32, to: u32) -> i64 {
+    if to >= from {
+        minor.saturating_mul(10i64.pow(to - from))
+    } else {
+        let divisor = 10i64.pow(from - to);
+        let (quotient, remainder) = (minor / divisor, minor % divisor);
+        if remainder.abs() * 2 >= divisor {
+            quotient + minor.signum()
+        } else {
+            quotient
+        }
+    }
+}
+
+/// Parses the shortest decimal form of `value` (what the user typed) into units of
+/// `10^-exponent`, rounding half away from zero. None when out of range.
+fn parse_decimal(value: f64, exponent: u32) -> Option<i64> {
+    if !value.is_finite() {
+        return None;
+    }
+    let text = format!("{}", value.abs());
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    let kept: String = fraction.chars().chain(std::iter::repeat('0')).take(exponent as usize).collect();
+    let mut units: i128 = format!("{}{}", whole, kept).parse().ok()?;
+    if fraction.as_bytes().get(exponent as usize).is_some_and(|d| *d >= b'5') {
+        units += 1;
+    }
+    let units = i64::try_from(units).ok()?;
+    Some(if value < 0.0 { -units } else { units })
+}
+
+/// Plain decimal with exactly `exponent` fraction digits ("1234.50", "-3.00", "500"), for files.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let scale = 10u64.pow(self.exponent);
+        let (whole, fraction) = (self.minor.unsigned_abs() / scale, self.minor.unsigned_abs() % scale);
+        if self.exponent == 0 {
+            write!(f, "{}{}", sign, whole)
+        } else {
+            write!(f, "{}{}.{:0width$}", sign, whole, fraction, width = self.exponent as usize)
+        }
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_major())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = f64::deserialize(deserializer)?;
+        let minor = parse_decimal(value, MAX_EXPONENT)
+            .ok_or_else(|| serde::de::Error::custom(format!("amount out of range: {}", value)))?;
+        Ok(Money { minor, exponent: MAX_EXPONENT })
+    }
+}
+
+// ─── SQL ──────────────────────────────────────────────────────────────────────
+
+fn sql_list(codes: &[&str]) -> String {
+    codes.iter().map(|c| format!("'{}'", c)).collect::<Vec<_>>().join(", ")
+}
+
+/// SQL for `10^exponent` of the currency code produced by `currency_expr`.
+pub fn minor_unit_scale_sql(currency_expr: &str) -> String {
+    format!(
+        "(CASE WHEN {c} IN ({zero}) THEN 1 WHEN {c} IN ({three}) THEN 1000 WHEN {c} IN ({four}) THEN 10000 ELSE 100 END)",
+        c = currency_expr,
+        zero = sql_list(ZERO_DECIMAL_CURRENCIES),
+        three = sql_list(THREE_DECIMAL_CURRENCIES),
+        four = sql_list(FOUR_DECIMAL_CURRENCIES),
+    )
+}
+
+/// SQL turning `minor_expr` (minor units of the currency produced by `currency_expr`)
+/// into major units, for a single row.
+pub fn to_major_sql(minor_expr: &str, currency_expr: &str) -> String {
+    format!("(({}) * 1.0 / {})", minor_expr, minor_unit_scale_sql(currency_expr))
+}
+
+/// SQL turning `minor_expr` (minor units of `currency_expr`) into micro-units.
+/// 10^6 is a multiple of every minor unit scale, so integers stay integers.
+pub fn to_micro_sql(minor_expr: &str, currency_expr: &str) -> String {
+    format!("(({}) * ({} / {}))", minor_expr, MICRO_SCALE, minor_unit_scale_sql(currency_expr))
+}
+
+/// SQL turning an aggregate of micro-units into major units.
+pub fn micro_to_major_sql(micro_expr: &str) -> String {
+    format!("(({}) * 1.0 / {})", micro_expr, MICRO_SCALE)
+}
+
+/// `SUM(expr)` in major units, where `expr` is in minor units of the row's
+/// `currency` column. Fractional units (e.g. 10% of 1 agora) are summed first and
+/// rounded once, so an empty or fully offset sum is exactly 0.
+pub fn major_sum_sql(expr: &str) -> String {
+    micro_to_major_sql(&format!("ROUND(COALESCE(SUM({}), 0))", to_micro_sql(expr, "currency")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn exponents_follow_iso_4217() {
+        assert_eq!(minor_unit_exponent("ILS"), 2);
+        assert_eq!(minor_unit_exponent("jpy"), 0);
+        assert_eq!(minor_unit_exponent("KWD"), 3);
+        assert_eq!(minor_unit_exponent("XYZ"), 2);
+    }
+
+    #[test]
+    fn json_numbers_round_trip_exactly() {
+        for text in ["0", "1.15", "0.1", "123456789.12", "-42.5", "1000"] {
+            let money: Money = serde_json::from_str(text).unwrap();
+            assert_eq!(serde_json::to_string(&money).unwrap(), serde_json::to_string(&text.parse::<f64>().unwrap()).unwrap());
+        }
+        assert_eq!(serde_json::from_str::<Money>("1.15").unwrap().to_minor("ILS"), 115);
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero_to_the_currency_minor_unit() {
+        let money: Money = serde_json::from_str("12.345").unwrap();
+        assert_eq!(money.to_minor("ILS"), 1235);
+        assert_eq!(money.to_minor("JPY"), 12);
+        assert_eq!(money.to_minor("KWD"), 12345);
+        let negative: Money = serde_json::from_str("-0.005").unwrap();
+        assert_eq!(negative.to_minor("USD"), -1);
+        assert_eq!(Money::from_major(2.675, "ILS").unwrap().to_minor("ILS"), 268);
+    }
+
+    #[test]
+    fn displays_with_the_currency_fraction_digits() {
+        assert_eq!(Money::from_minor(123450, "ILS").to_string(), "1234.50");
+        assert_eq!(Money::from_minor(-5, "USD").to_string(), "-0.05");
+        assert_eq!(Money::from_minor(500, "JPY").to_string(), "500");
+    }
+
+    #[test]
+    fn zero_never_serializes_as_negative() {
+        let zero = Money::from_minor(0, "ILS");
+        assert_eq!(serde_json::to_string(&zero).unwrap(), "0.0");
+        assert_eq!(Money::from_major(-0.001, "ILS").unwrap().to_major().to_string(), "0");
+    }
+
+    #[test]
+    fn convert_rounds_to_the_target_currency() {
+        let usd = Money::from_major(100.0, "USD").unwrap();
+        assert_eq!(usd.convert(3.7, "ILS").unwrap().to_minor("ILS"), 37000);
+        assert_eq!(usd.convert(151.337, "JPY").unwrap().to_minor("JPY"), 15134);
+    }
+
+    #[test]
+    fn out_of_range_amounts_are_rejected() {
+        assert!(matches!(Money::from_major(f64::NAN, "ILS"), Err(Ten10Error::Validation(_))));
+        assert!(matches!(Money::from_major(1e17, "ILS"), Err(Ten10Error::Validation(_))));
+        let large = Money::from_major(1e12, "USD").unwrap();
+        assert!(matches!(large.convert(1e8, "JPY"), Err(Ten10Error::Validation(_))));
+    }
+
+    #[test]
+    fn sums_of_many_tenths_do_not_drift() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (amount INTEGER, currency TEXT);
+             WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 10000)
+             INSERT INTO t SELECT 1001, 'ILS' FROM n;",
+        )
+        .unwrap();
+        // 10% of 10.01 ten thousand times is exactly 10010.00.
+        let sql = format!("SELECT {} FROM t", major_sum_sql("amount * 0.1"));
+        let total: f64 = conn.query_row(&sql, [], |r| r.get(0)).unwrap();
+        assert_eq!(total, 10010.0);
+
+        let empty: f64 = conn.query_row(&format!("{} WHERE 0", sql), [], |r| r.get(0)).unwrap();
+        assert_eq!(empty.to_string(), "0");
+    }
+
+    #[test]
+    fn sums_scale_each_row_by_its_own_currency() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (amount INTEGER, currency TEXT);
+             INSERT INTO t VALUES (1050, 'USD'), (300, 'JPY'), (1250, 'KWD');",
+        )
+        .unwrap();
+        let total: f64 = conn
+            .query_row(&format!("SELECT {} FROM t", major_sum_sql("amount")), [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(total, 10.5 + 300.0 + 1.25);
+        let row: f64 = conn
+            .query_row(&format!("SELECT {} FROM t WHERE currency = 'KWD'", to_major_sql("amount", "currency")), [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(row, 1.25);
+    }
+}
@@ -0,0 +1,175 @@
+// src-tauri/src/query_plans.rs
+// Query-plan regression tests: the hot commands of insights_commands.rs,
+// chart_commands.rs and transaction_commands.rs are run against a migrated
+// database while every statement they execute is recorded, and each recorded
+// statement reading `transactions` must reach it through an index (migration
+// 0013) rather than a full table scan.
+//
+// Test-only code: compiled exclusively under `cargo test`.
+
+use std::cell::RefCell;
+
+use rusqlite::Connection;
+use serde_json::json;
+use tauri::Manager;
+
+use crate::commands::{chart_commands, insights_commands, transaction_commands};
+use crate::DbState;
+
+thread_local! {
+    static STATEMENTS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record(sql: &str) {
+    STATEMENTS.with(|statements| statements.borrow_mut().push(sql.to_string()));
+}
+
+fn mock_app() -> tauri::App<tauri::test::MockRuntime> {
+    let mut conn = Connection::open_in_memory().unwrap();
+    crate::migrations::run_migrations(&mut conn).unwrap();
+    conn.execute_batch(
+        "INSERT INTO transactions (id, date, amount, currency, description, type, category, recipient, payment_method,
+                                   original_amount, original_currency, conversion_rate, created_at, updated_at) VALUES
+            ('t1', '2024-01-10', 10000, 'ILS', 'salary', 'income', 'salary', NULL, 'transfer', NULL, NULL, NULL, '1', '1'),
+            ('t2', '2024-02-15', 37000, 'ILS', 'books', 'donation', 'torah', 'Yeshiva', 'cash', 10000, 'USD', 3.7, '2', '2'),
+            ('t3', '2024-03-01', 5000, 'ILS', 'groceries', 'expense', 'food', NULL, 'credit', NULL, NULL, NULL, '3', '3');",
+    )
+    .unwrap();
+    conn.trace(Some(record));
+    let app = tauri::test::mock_app();
+    app.manage(DbState::new(conn));
+    app
+}
+
+/// Table names and aliases `transactions` is read under in `sql`.
+fn transaction_aliases(sql: &str) -> Vec<String> {
+    let words: Vec<&str> = sql.split(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == ',').collect();
+    let mut aliases = vec!["transactions".to_string()];
+    for pair in words.windows(2) {
+        if pair[0].eq_ignore_ascii_case("transactions") {
+            let alias = pair[1];
+            let keyword = ["WHERE", "GROUP", "ORDER", "LIMIT", "JOIN", "LEFT", "INNER", "ON", "SET", "AS", ""]
+                .iter()
+                .any(|k| k.eq_ignore_ascii_case(alias));
+            if !keyword && alias.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                aliases.push(alias.to_string());
+            }
+        }
+    }
+    aliases
+}
+
+/// Runs `command` and returns the plan of every statement it ran on
+/// `transactions` that scans the table without an index.
+fn full_scans(app: &tauri::App<tauri::test::MockRuntime>, command: impl FnOnce()) -> Vec<String> {
+    STATEMENTS.with(|statements| statements.borrow_mut().clear());
+    command();
+    // Trigger bodies are reported as comments
+    let statements: Vec<String> =
+        STATEMENTS.with(|statements| statements.take()).into_iter().filter(|sql| !sql.starts_with("--")).collect();
+    assert!(statements.iter().any(|sql| sql.contains("transactions")), "no statement on transactions recorded");
+
+    let conn = app.state::<DbState>().inner().0.lock().unwrap();
+    let mut scans = Vec::new();
+    for sql in &statements {
+        let aliases = transaction_aliases(sql);
+        let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql)).unwrap();
+        let details: Vec<String> =
+            stmt.query_map([], |row| row.get::<_, String>(3)).unwrap().collect::<Result<_, _>>().unwrap();
+        let full_scan = details.iter().any(|detail| {
+            let mut parts = detail.split_whitespace();
+            parts.next() == Some("SCAN")
+                && parts.next().is_some_and(|table| aliases.iter().any(|a| a == table))
+                && !detail.contains("INDEX")
+        });
+        if full_scan {
+            scans.push(format!("{}\n  {:?}", sql.split_whitespace().collect::<Vec<_>>().join(" "), details));
+        }
+    }
+    scans
+}
+
+fn s(value: &str) -> String {
+    value.to_string()
+}
+
+#[test]
+fn insights_commands_use_indexes() {
+    let app = mock_app();
+    let state = || app.state::<DbState>();
+    let scans = full_scans(&app, || {
+        let (start, end) = (|| s("2024-01-01"), || s("2024-12-31"));
+        insights_commands::get_desktop_category_breakdown(state(), start(), end(), s("expense"), None, None, None).unwrap();
+        insights_commands::get_desktop_payment_method_breakdown(state(), start(), end(), None).unwrap();
+        insights_commands::get_desktop_recurring_vs_onetime(state(), start(), end(), None).unwrap();
+        insights_commands::get_desktop_daily_heatmap(state(), start(), end(), None, None).unwrap();
+        insights_commands::get_desktop_daily_heatmap(state(), start(), end(), Some(s("donation")), None).unwrap();
+        insights_commands::get_desktop_analytics_breakdowns(state(), start(), end(), None).unwrap();
+        insights_commands::get_desktop_analytics_range_stats(state(), start(), end(), None).unwrap();
+        insights_commands::get_desktop_donation_recipients_breakdown(state(), start(), end(), None).unwrap();
+    });
+    assert!(scans.is_empty(), "full table scans:\n{}", scans.join("\n"));
+}
+
+#[test]
+fn chart_commands_use_indexes() {
+    let app = mock_app();
+    let scans = full_scans(&app, || {
+        chart_commands::get_desktop_monthly_financial_summary(app.state::<DbState>(), s("2024-03-31"), 3, None, None).unwrap();
+        chart_commands::get_desktop_monthly_financial_summary(
+            app.state::<DbState>(),
+            s("2024-03-31"),
+            3,
+            Some(s("hebrew")),
+            serde_json::from_value(json!({ "byType": true, "byCurrency": true, "byChomesh": true })).unwrap(),
+        )
+        .unwrap();
+    });
+    assert!(scans.is_empty(), "full table scans:\n{}", scans.join("\n"));
+}
+
+#[test]
+fn transaction_commands_use_indexes() {
+    let app = mock_app();
+    let state = || app.state::<DbState>();
+    // Without a selective filter only the default date order can follow an
+    // index; any other order has to read every row.
+    let table_filters = [
+        (json!({}), &["date"][..]),
+        (json!({ "showOnly": "recurring" }), &["date"][..]),
+        (json!({ "dateFrom": "2024-02-01", "dateTo": "2024-02-29" }), &["date", "amount", "relevance"][..]),
+        (json!({ "types": ["donation"] }), &["date", "amount", "relevance"][..]),
+        (json!({ "search": "books" }), &["date", "amount", "relevance"][..]),
+        (json!({ "recipientIds": ["missing"], "currencies": ["USD"] }), &["date", "amount"][..]),
+    ];
+    let scans = full_scans(&app, || {
+        for (filters, sorts) in &table_filters {
+            for sort in sorts.iter() {
+                transaction_commands::get_filtered_transactions_handler(
+                    state(),
+                    serde_json::from_value(json!({
+                        "filters": filters,
+                        "pagination": { "page": 1, "limit": 20 },
+                        "sorting": { "field": sort, "direction": "desc" },
+                    }))
+                    .unwrap(),
+                )
+                .unwrap();
+            }
+            transaction_commands::export_transactions_handler(state(), serde_json::from_value(filters.clone()).unwrap())
+                .unwrap();
+        }
+        transaction_commands::get_transactions_count(state()).unwrap();
+        transaction_commands::get_last_known_rate(state(), s("USD"), s("ILS")).unwrap();
+        transaction_commands::get_distinct_categories(state(), s("expense")).unwrap();
+        transaction_commands::get_distinct_payment_methods(state()).unwrap();
+        transaction_commands::update_transaction_handler(
+            state(),
+            s("t3"),
+            serde_json::from_value(json!({ "description": "vegetables" })).unwrap(),
+        )
+        .unwrap();
+        transaction_commands::delete_transaction_handler(state(), s("t3")).unwrap();
+    });
+    assert!(scans.is_empty(), "full table scans:\n{}", scans.join("\n"));
+}
@@ -0,0 +1,183 @@
+// src-tauri/src/scheduler.rs
+// Background runner for recurring transactions, started from main.rs `.setup`.
+//
+// A plain thread ticks once a minute (or at local midnight, whichever is sooner)
+// and processes due recurring transactions when the local date has changed since
+// the last run, or when the wall clock jumped ahead — which is what a system
+// resume looks like from inside a sleeping thread. The engine itself is
+// idempotent, so an extra run never duplicates occurrences.
+
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, TimeDelta};
+use rusqlite::OptionalExtension;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::commands::recurring_transaction_commands::{
+    process_due_recurring, RecurringExecutionReport,
+};
+use crate::migrations::run_migrations;
+use crate::DbState;
+use crate::error::Ten10Error;
+
+/// Gives the webview's own startup run (which can fetch fresh exchange rates) the first go.
+const STARTUP_DELAY: Duration = Duration::from_secs(120);
+/// Longest sleep between checks.
+const TICK: Duration = Duration::from_secs(60);
+/// A gap longer than this between two wake-ups means the machine was asleep.
+const RESUME_GAP: TimeDelta = TimeDelta::minutes(5);
+/// Event emitted to the webview after a background run recorded something.
+pub const RECURRING_PROCESSED_EVENT: &str = "recurring-transactions-processed";
+
+pub fn start<R: Runtime>(app: AppHandle<R>) {
+    thread::spawn(move || {
+        thread::sleep(STARTUP_DELAY);
+        let mut last_run: Option<NaiveDate> = None;
+        let mut last_wake = Local::now();
+
+        loop {
+            let now = Local::now();
+            if should_run(last_run, last_wake, now) {
+                match run_once(&app) {
+                    Ok(report) => {
+                        last_run = Some(now.date_naive());
+                        if !report.created.is_empty() {
+                            notify(&app, &report);
+                        }
+                    }
+                    Err(e) => eprintln!("[Scheduler] Recurring run failed: {}", e),
+                }
+            }
+            last_wake = now;
+            thread::sleep(sleep_duration(Local::now()));
+        }
+    });
+}
+
+/// Run on the first wake-up, after the local date changes, and after a resume.
+fn should_run(last_run: Option<NaiveDate>, last_wake: DateTime<Local>, now: DateTime<Local>) -> bool {
+    match last_run {
+        None => true,
+        Some(day) => day != now.date_naive() || now - last_wake > RESUME_GAP,
+    }
+}
+
+/// Sleep one tick, or less when local midnight comes first.
+fn sleep_duration(now: DateTime<Local>) -> Duration {
+    let next_midnight = now
+        .date_naive()
+        .succ_opt()
+        .and_then(|d| d.and_hms_opt(0, 0, 1))
+        .map(|dt| dt - now.naive_local());
+    match next_midnight.and_then(|d| d.to_std().ok()) {
+        Some(until_midnight) => until_midnight.min(TICK),
+        None => TICK,
+    }
+}
+
+fn run_once<R: Runtime>(app: &AppHandle<R>) -> Result<RecurringExecutionReport, Ten10Error> {
+    let db = app.state::<DbState>();
+    let mut conn = db.0.lock()?;
+
+    // The webview may not have called init_db yet (app started hidden in the tray).
+    run_migrations(&mut conn)?;
+
+    // No network here: templates keep their stored conversion rates.
+    let report = process_due_recurring(&mut conn, Local::now().date_naive(), &HashMap::new())?;
+    drop(conn);
+
+    if !report.created.is_empty() {
+        println!(
+            "[Scheduler] Recorded {} recurring transactions ({} schedules completed)",
+            report.created.len(),
+            report.completed_ids.len()
+        );
+        let _ = app.emit(RECURRING_PROCESSED_EVENT, report.clone());
+    }
+    Ok(report)
+}
+
+/// The UI language saved in app_settings, for text shown outside the webview.
+pub(crate) fn notification_language<R: Runtime>(app: &AppHandle<R>) -> String {
+    app.state::<DbState>()
+        .read()
+        .ok()
+        .and_then(|conn| {
+            conn.query_row(
+                "SELECT value FROM app_settings WHERE key = 'language'",
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .ok()
+            .flatten()
+        })
+        .unwrap_or_else(|| "he".to_string())
+}
+
+fn notify<R: Runtime>(app: &AppHandle<R>, report: &RecurringExecutionReport) {
+    let (title, body) = notification_text(report, &notification_language(app));
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("[Scheduler] Failed to show notification: {}", e);
+    }
+}
+
+fn notification_text(report: &RecurringExecutionReport, language: &str) -> (String, String) {
+    let count = report.created.len();
+    let completed = report.completed_ids.len();
+    if language == "en" {
+        let mut body = if count == 1 {
+            "1 recurring transaction was recorded.".to_string()
+        } else {
+            format!("{} recurring transactions were recorded.", count)
+        };
+        if completed > 0 {
+            body.push_str(&format!(" {} standing orders completed.", completed));
+        }
+        ("Ten10 - standing orders".to_string(), body)
+    } else {
+        let mut body = if count == 1 {
+            "נרשמה תנועה אחת מהוראת קבע.".to_string()
+        } else {
+            format!("נרשמו {} תנועות מהוראות קבע.", count)
+        };
+        if completed > 0 {
+            body.push_str(&format!(" {} הוראות קבע הסתיימו.", completed));
+        }
+        ("Ten10 - הוראות קבע".to_string(), body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn runs_first_time_on_new_day_and_after_resume() {
+        let now = at(2024, 5, 1, 10, 0);
+        assert!(should_run(None, now, now));
+
+        let today = now.date_naive();
+        // Regular tick on the same day.
+        assert!(!should_run(Some(today), at(2024, 5, 1, 9, 59), now));
+        // Local date changed.
+        assert!(should_run(Some(today), at(2024, 5, 1, 23, 59), at(2024, 5, 2, 0, 0)));
+        // Woke up after a long sleep on the same day.
+        assert!(should_run(Some(today), at(2024, 5, 1, 8, 0), now));
+    }
+
+    #[test]
+    fn sleeps_until_midnight_when_it_is_closer_than_a_tick() {
+        assert_eq!(sleep_duration(at(2024, 5, 1, 10, 0)), TICK);
+        let late = Local.with_ymd_and_hms(2024, 5, 1, 23, 59, 30).unwrap();
+        assert_eq!(sleep_duration(late), Duration::from_secs(31));
+    }
+}
@@ -0,0 +1,101 @@
+// src-tauri/src/search.rs
+// Full-text search over the transactions' description, category, recipient and
+// payment method, backed by the `transactions_fts` FTS5 table (migrations 0012
+// and 0015), which is keyed on the transaction id.
+//
+// Hebrew is matched without niqqud and cantillation marks and with final letters
+// folded into their regular forms (ך→כ, ם→מ, ן→נ, ף→פ, ץ→צ), so "שָׁלוֹם" and
+// "שלו" both find "שלום". The triggers keeping the table in sync normalize the
+// indexed text with the `search_text` SQL function, the query terms are
+// normalized here the same way.
+
+use rusqlite::functions::FunctionFlags;
+use rusqlite::Connection;
+
+/// Joins the index, as `fts`, to the transactions aliased `t`; filter with
+/// `fts_match_clause` and a query from `fts_match_query`.
+pub(crate) const FTS_JOIN: &str = " JOIN transactions_fts fts ON fts.id = t.id";
+
+/// `WHERE` condition matching the joined index against parameter `?param_idx`.
+pub(crate) fn fts_match_clause(param_idx: usize) -> String {
+    format!("fts.transactions_fts MATCH ?{}", param_idx)
+}
+
+/// Hebrew points and cantillation marks removed before indexing.
+const HEBREW_MARKS: &[(char, char)] = &[
+    ('\u{0591}', '\u{05BD}'),
+    ('\u{05BF}', '\u{05BF}'),
+    ('\u{05C1}', '\u{05C2}'),
+    ('\u{05C4}', '\u{05C5}'),
+    ('\u{05C7}', '\u{05C7}'),
+];
+
+/// Final letter forms and the letters they fold into.
+const FINAL_LETTERS: &[(char, char)] = &[('ך', 'כ'), ('ם', 'מ'), ('ן', 'נ'), ('ף', 'פ'), ('ץ', 'צ')];
+
+fn is_hebrew_mark(c: char) -> bool {
+    HEBREW_MARKS.iter().any(|&(from, to)| (from..=to).contains(&c))
+}
+
+/// `text` without Hebrew marks and with final letters folded. Case and Latin
+/// diacritics are left to the FTS tokenizer.
+pub fn normalize_search_text(text: &str) -> String {
+    text.chars()
+        .filter(|&c| !is_hebrew_mark(c))
+        .map(|c| FINAL_LETTERS.iter().find(|&&(f, _)| f == c).map_or(c, |&(_, regular)| regular))
+        .collect()
+}
+
+/// Registers `search_text(text)`, `normalize_search_text` for SQL. The
+/// `transactions_fts` triggers call it, so every connection that writes
+/// transactions needs it.
+pub fn register_search_functions(conn: &Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "search_text",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC | FunctionFlags::SQLITE_INNOCUOUS,
+        |ctx| Ok(ctx.get::<Option<String>>(0)?.map(|text| normalize_search_text(&text))),
+    )
+}
+
+/// FTS5 query for what the user typed: every word must appear, as a word or the
+/// start of one, in any of the indexed columns. None when `term` has no words.
+pub fn fts_match_query(term: &str) -> Option<String> {
+    let normalized = normalize_search_text(term);
+    let words: Vec<String> = normalized
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("\"{}\"*", w))
+        .collect();
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_in_rust_and_sql_alike() {
+        let text = "שָׁלוֹם עֲלֵיכֶם, Café";
+        assert_eq!(normalize_search_text(text), "שלומ עליכמ, Café");
+
+        let conn = Connection::open_in_memory().unwrap();
+        register_search_functions(&conn).unwrap();
+        let in_sql: (String, Option<String>) = conn
+            .query_row("SELECT search_text(?1), search_text(NULL)", [text], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap();
+        assert_eq!(in_sql, (normalize_search_text(text), None));
+    }
+
+    #[test]
+    fn builds_prefix_queries_per_word() {
+        assert_eq!(fts_match_query("  מכולת  שכונ "), Some("\"מכולת\"* \"שכונ\"*".to_string()));
+        assert_eq!(fts_match_query("לא-קיים"), Some("\"לא\"* \"קיימ\"*".to_string()));
+        assert_eq!(fts_match_query("\" OR *"), Some("\"OR\"*".to_string()));
+        assert_eq!(fts_match_query(" -- "), None);
+    }
+}
@@ -0,0 +1,166 @@
+// src-tauri/src/tithe_rules.rs
+// SQL building blocks that evaluate transactions through the versioned `tithe_rules`.
+//
+// Every transaction is judged by the rule version in force on its own date
+// (the row with the latest `effective_from` <= date), so editing the rules from
+// some date onward never changes balances computed for earlier transactions.
+// The defaults (10% maaser, extra 10% chomesh, exempt income ignored, recognized
+// expenses deducted) reproduce the original hard-coded split.
+//
+// All expressions use bare transaction column names (`type`, `amount`, `is_chomesh`)
+// and the alias `r` for the joined rule; add `rules_join(..)` after FROM.
+
+/// Maaser percentage used when no rule version covers a date.
+pub const DEFAULT_MAASER_PERCENTAGE: f64 = 10.0;
+/// Chomesh (extra) percentage used when no rule version covers a date.
+pub const DEFAULT_CHOMESH_PERCENTAGE: f64 = 10.0;
+
+/// LEFT JOIN attaching the rule version in force on `{table}.date` as `r`.
+pub fn rules_join(table: &str) -> String {
+    format!(
+        "LEFT JOIN tithe_rules r ON r.effective_from = (
+            SELECT MAX(tr.effective_from) FROM tithe_rules tr WHERE tr.effective_from <= {}.date
+        )",
+        table
+    )
+}
+
+fn maaser_rate() -> String {
+    format!("(COALESCE(r.maaser_percentage, {}) / 100.0)", DEFAULT_MAASER_PERCENTAGE)
+}
+
+fn chomesh_rate() -> String {
+    format!("(COALESCE(r.chomesh_percentage, {}) / 100.0)", DEFAULT_CHOMESH_PERCENTAGE)
+}
+
+fn exempt_counts() -> &'static str {
+    "COALESCE(r.include_exempt_income, 0) = 1"
+}
+
+fn expenses_deduct() -> &'static str {
+    "COALESCE(r.deduct_recognized_expenses, 1) = 1"
+}
+
+/// Income amount that tithe is computed on, signed: income (+ exempt income when
+/// counted) minus recognized expenses when they deduct.
+pub fn tithable_income_case() -> String {
+    format!(
+        "CASE
+            WHEN type = 'income' THEN amount
+            WHEN type = 'exempt-income' AND {exempt} THEN amount
+            WHEN type = 'recognized-expense' AND {deduct} THEN -amount
+            ELSE 0
+        END",
+        exempt = exempt_counts(),
+        deduct = expenses_deduct(),
+    )
+}
+
+/// Change to the maaser pot caused by one transaction.
+/// Maaser gets its percentage of ALL tithable income (base obligation);
+/// donations and initial balances go to the pot indicated by is_chomesh.
+pub fn maaser_delta_case() -> String {
+    format!(
+        "CASE
+            WHEN type IN ('income', 'exempt-income', 'recognized-expense') THEN ({income}) * {rate}
+            WHEN type = 'donation' AND COALESCE(is_chomesh, 0) = 0 THEN -amount
+            WHEN type = 'initial_balance' AND COALESCE(is_chomesh, 0) = 0 THEN amount
+            ELSE 0
+        END",
+        income = tithable_income_case(),
+        rate = maaser_rate(),
+    )
+}
+
+/// Change to the chomesh pot caused by one transaction.
+/// Only chomesh-marked income adds the EXTRA percentage (delta on top of maaser),
+/// so income of 1000 with chomesh at default rules: maaser +100, chomesh +100.
+pub fn chomesh_delta_case() -> String {
+    format!(
+        "CASE
+            WHEN type IN ('income', 'exempt-income', 'recognized-expense') AND COALESCE(is_chomesh, 0) = 1
+                THEN ({income}) * {rate}
+            WHEN type = 'donation' AND COALESCE(is_chomesh, 0) = 1 THEN -amount
+            WHEN type = 'initial_balance' AND COALESCE(is_chomesh, 0) = 1 THEN amount
+            ELSE 0
+        END",
+        income = tithable_income_case(),
+        rate = chomesh_rate(),
+    )
+}
+
+/// Maaser obligation created by income-side rows only (no donations or opening balances).
+pub fn maaser_obligation_case() -> String {
+    format!("({}) * {}", tithable_income_case(), maaser_rate())
+}
+
+/// Chomesh obligation created by chomesh-marked income-side rows only.
+pub fn chomesh_obligation_case() -> String {
+    format!(
+        "CASE WHEN COALESCE(is_chomesh, 0) = 1 THEN ({}) * {} ELSE 0 END",
+        tithable_income_case(),
+        chomesh_rate()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::major_sum_sql;
+    use rusqlite::{params, Connection};
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO transactions (id, date, amount, currency, type, is_chomesh) VALUES
+                ('i1', '2023-06-01', 100000, 'ILS', 'income', 0),
+                ('i2', '2024-06-01', 100000, 'ILS', 'income', 1),
+                ('x1', '2024-06-02', 50000, 'ILS', 'exempt-income', 0),
+                ('e1', '2024-06-03', 20000, 'ILS', 'recognized-expense', 0),
+                ('d1', '2024-06-04', 5000, 'ILS', 'donation', 0),
+                ('d2', '2024-06-05', 3000, 'ILS', 'donation', 1),
+                ('b1', '2023-01-01', 4000, 'ILS', 'initial_balance', 0),
+                ('n1', '2024-06-06', 99900, 'ILS', 'non_tithe_donation', 0),
+                ('p1', '2024-06-07', 99900, 'ILS', 'expense', 0);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn balances(conn: &Connection) -> (f64, f64) {
+        conn.query_row(
+            &format!(
+                "SELECT {}, {} FROM transactions {}",
+                major_sum_sql(&maaser_delta_case()),
+                major_sum_sql(&chomesh_delta_case()),
+                rules_join("transactions")
+            ),
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn default_rules_match_the_original_split() {
+        let conn = test_db();
+        // maaser: 40 + 100 + 100 - 20 - 50 = 170 ; chomesh: 100 - 30 = 70
+        assert_eq!(balances(&conn), (170.0, 70.0));
+    }
+
+    #[test]
+    fn new_rule_version_only_affects_later_transactions() {
+        let conn = test_db();
+        conn.execute(
+            "INSERT INTO tithe_rules
+                (effective_from, maaser_percentage, chomesh_percentage, include_exempt_income, deduct_recognized_expenses, created_at)
+             VALUES (?1, 20, 10, 1, 0, '2024-01-01')",
+            params!["2024-01-01"],
+        )
+        .unwrap();
+        // 2023 income stays at 10% (100); 2024: (1000 + 500) * 20% = 300, expense not deducted.
+        // maaser: 40 + 100 + 300 - 50 = 390 ; chomesh: only i2 is chomesh → 1000 * 10% - 30 = 70
+        assert_eq!(balances(&conn), (390.0, 70.0));
+    }
+}
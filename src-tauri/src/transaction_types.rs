@@ -19,15 +19,6 @@ pub fn income_types_condition() -> String {
     format!("({})", conditions.join(" OR "))
 }
 
-/// Returns SQL CASE condition for income types in SUM aggregation
-pub fn income_types_case_condition() -> String {
-    let conditions: Vec<String> = INCOME_TYPES
-        .iter()
-        .map(|t| format!("type = '{}'", t))
-        .collect();
-    format!("CASE WHEN {} THEN amount ELSE 0 END", conditions.join(" OR "))
-}
-
 /// Returns SQL WHERE condition for donation types
 pub fn donation_types_condition() -> String {
     let conditions: Vec<String> = DONATION_TYPES
@@ -55,13 +46,15 @@ pub fn expense_types_condition() -> String {
     format!("({})", conditions.join(" OR "))
 }
 
-/// Returns SQL CASE condition for expense types in SUM aggregation
-pub fn expense_types_case_condition() -> String {
-    let conditions: Vec<String> = EXPENSE_TYPES
-        .iter()
-        .map(|t| format!("type = '{}'", t))
-        .collect();
-    format!("CASE WHEN {} THEN amount ELSE 0 END", conditions.join(" OR "))
+/// The types that share categories with `transaction_type`: a base type
+/// ("income", "expense", "donation") covers its derived types too.
+pub fn category_type_group(transaction_type: &str) -> Vec<&str> {
+    match transaction_type {
+        "income" => INCOME_TYPES.to_vec(),
+        "expense" => EXPENSE_TYPES.to_vec(),
+        "donation" => DONATION_TYPES.to_vec(),
+        other => vec![other],
+    }
 }
 
 #[cfg(test)]
@@ -204,6 +204,25 @@ function App() {
 
   useDesktopUpdateCheck(platform, desktopInitComplete);
 
+  // Desktop: refresh data when the background scheduler records recurring transactions
+  useEffect(() => {
+    if (platform !== "desktop" || !desktopInitComplete) return;
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+    RecurringTransactionsService.listenForBackgroundRuns()
+      .then((fn) => {
+        if (cancelled) fn();
+        else unlisten = fn;
+      })
+      .catch((e) =>
+        logger.error("Failed to listen for background recurring runs", e)
+      );
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [platform, desktopInitComplete]);
+
   // Desktop: auto-lock after real user inactivity (mouse/keyboard/touch/etc.)
   useEffect(() => {
     if (platform !== "desktop") return;
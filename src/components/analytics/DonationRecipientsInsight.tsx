@@ -50,7 +50,9 @@ export function DonationRecipientsInsight({
       ...item,
       label: item.recipient === "other"
         ? t("analytics.recipients.other")
-        : (item.last_description || item.recipient || t("analytics.recipients.other")),
+        : item.recipient_id
+          ? item.recipient
+          : (item.last_description || item.recipient || t("analytics.recipients.other")),
       fill: `hsl(42 ${Math.max(0, Math.min(100, Math.round(90 - index * 8)))}% ${Math.max(0, Math.min(100, Math.round(45 + index * 5)))}%)`,
     })),
     [data, t]
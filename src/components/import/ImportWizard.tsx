@@ -48,6 +48,7 @@ import { ColumnMappingStep } from "./steps/ColumnMappingStep";
 import { ImportReviewStep } from "./steps/ImportReviewStep";
 import { ImportResultStep } from "./steps/ImportResultStep";
 import { PrepareStep } from "./steps/PrepareStep";
+import { getErrorMessage } from "@/lib/utils/error-message";
 
 const IMPORT_PREVIEW_FETCH_TIMEOUT_MS = 15000;
 
@@ -485,7 +486,7 @@ export function ImportWizard() {
           type: "SET_PROCESSING_ERROR",
           error: {
             code: "preview_failed",
-            detail: err instanceof Error ? err.message : String(err),
+            detail: getErrorMessage(err) ?? String(err),
           },
         });
       }
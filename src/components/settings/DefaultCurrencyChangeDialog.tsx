@@ -0,0 +1,82 @@
+import { useTranslation } from "react-i18next";
+import {
+  AlertDialog,
+  AlertDialogAction,
+  AlertDialogCancel,
+  AlertDialogContent,
+  AlertDialogDescription,
+  AlertDialogFooter,
+  AlertDialogHeader,
+  AlertDialogTitle,
+} from "@/components/ui/alert-dialog";
+import type { DefaultCurrencyChange } from "@/lib/services/desktop-settings.service";
+
+interface DefaultCurrencyChangeDialogProps {
+  /** Preview of the pending change; the dialog is open while it is set */
+  change: DefaultCurrencyChange | null;
+  isChanging: boolean;
+  onConfirm: () => void;
+  onCancel: () => void;
+}
+
+/** Desktop: confirms re-converting existing data into a new default currency. */
+export function DefaultCurrencyChangeDialog({
+  change,
+  isChanging,
+  onConfirm,
+  onCancel,
+}: DefaultCurrencyChangeDialogProps) {
+  const { t, i18n } = useTranslation("settings");
+  const { t: tCommon } = useTranslation("common");
+  const blocked = !!change && change.missing_rates.length > 0;
+
+  return (
+    <AlertDialog open={!!change} onOpenChange={(open) => !open && onCancel()}>
+      <AlertDialogContent dir={i18n.dir()}>
+        <AlertDialogHeader className="text-start">
+          <AlertDialogTitle className="text-start">
+            {t("financial.currencyChange.title", {
+              from: change?.from,
+              to: change?.to,
+            })}
+          </AlertDialogTitle>
+          <AlertDialogDescription className="text-start">
+            {t("financial.currencyChange.description", {
+              transactions: change?.transactions ?? 0,
+              recurring: change?.recurring_transactions ?? 0,
+              budgets: change?.budgets ?? 0,
+            })}
+          </AlertDialogDescription>
+        </AlertDialogHeader>
+        {blocked && change && (
+          <div className="text-sm text-destructive text-start">
+            <p>{t("financial.currencyChange.missingRates")}</p>
+            <ul className="mt-2 list-disc ps-5">
+              {change.missing_rates.map((rate) => (
+                <li key={`${rate.base}-${rate.date}`} dir="ltr" className="text-start">
+                  {rate.base} → {rate.quote}, {rate.date} ({rate.rows})
+                </li>
+              ))}
+            </ul>
+          </div>
+        )}
+        <AlertDialogFooter className="gap-2 sm:space-x-0">
+          <AlertDialogCancel disabled={isChanging}>
+            {tCommon("actions.cancel")}
+          </AlertDialogCancel>
+          <AlertDialogAction
+            onClick={(event) => {
+              event.preventDefault();
+              onConfirm();
+            }}
+            disabled={blocked || isChanging}
+          >
+            {isChanging
+              ? tCommon("labels.loading")
+              : t("financial.currencyChange.confirmButton")}
+          </AlertDialogAction>
+        </AlertDialogFooter>
+      </AlertDialogContent>
+    </AlertDialog>
+  );
+}
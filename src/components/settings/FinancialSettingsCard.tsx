@@ -33,6 +33,8 @@ interface FinancialSettingsCardProps {
   disableMinMaaserPercentage?: boolean;
   onOpenBalanceModal?: () => void;
   currencyLocked?: boolean;
+  /** Desktop: changing the currency re-converts existing data instead of being locked */
+  currencyConvertible?: boolean;
 }
 
 export function FinancialSettingsCard({
@@ -42,8 +44,14 @@ export function FinancialSettingsCard({
   disableMinMaaserPercentage = false,
   onOpenBalanceModal,
   currencyLocked = false,
+  currencyConvertible = false,
 }: FinancialSettingsCardProps) {
   const { t } = useTranslation("settings");
+  const currencyInfo = currencyLocked
+    ? "locked"
+    : currencyConvertible
+    ? "convertible"
+    : "unlocked";
 
   return (
     <Card>
@@ -92,14 +100,10 @@ export function FinancialSettingsCard({
               <Info className="h-4 w-4" />
             )}
             <AlertTitle>
-              {currencyLocked 
-                ? t("financial.currencyInfo.locked.title") 
-                : t("financial.currencyInfo.unlocked.title")}
+              {t(`financial.currencyInfo.${currencyInfo}.title`)}
             </AlertTitle>
             <AlertDescription>
-              {currencyLocked 
-                ? t("financial.currencyInfo.locked.description") 
-                : t("financial.currencyInfo.unlocked.description")}
+              {t(`financial.currencyInfo.${currencyInfo}.description`)}
             </AlertDescription>
           </Alert>
         </div>
@@ -17,6 +17,7 @@ import { RecurringTransaction } from "@/types/transaction";
 import { DateRangeObject } from "./useDateControls";
 import { Platform } from "@/contexts/PlatformContext";
 import { logger } from "@/lib/logger";
+import { getErrorMessage } from "@/lib/utils/error-message";
 
 // Re-export so existing callers don't break
 export { getPreviousPeriodRange } from "@/lib/utils/date-range";
@@ -120,7 +121,7 @@ export function useInsights(
       setCategoryData(data);
     } catch (err) {
       logger.error("useInsights: category breakdown error:", err);
-      setCategoryError(err instanceof Error ? err.message : String(err));
+      setCategoryError(getErrorMessage(err) ?? String(err));
     } finally {
       setIsLoadingCategory(false);
     }
@@ -142,7 +143,7 @@ export function useInsights(
       .then((data) => setActiveRecurring(data))
       .catch((err) => {
         logger.error("useInsights: active recurring error:", err);
-        setRecurringError(err instanceof Error ? err.message : String(err));
+        setRecurringError(getErrorMessage(err) ?? String(err));
       })
       .finally(() => setIsLoadingRecurring(false));
   }, [isReady, platform, lastDbFetchTimestamp]);
@@ -212,7 +213,7 @@ export function useInsights(
       setHeatmapData(data);
     } catch (err) {
       logger.error("useInsights: heatmap error:", err);
-      setHeatmapError(err instanceof Error ? err.message : String(err));
+      setHeatmapError(getErrorMessage(err) ?? String(err));
     } finally {
       setIsLoadingHeatmap(false);
     }
@@ -4,13 +4,28 @@ import { supabase } from "@/lib/supabaseClient";
 import { getPlatform } from "../platformManager";
 import { logger } from "@/lib/logger";
 
-export interface MonthlyDataPoint {
-  month_label: string; // "YYYY-MM"
+export interface MonthlyTotals {
   income: number;
   donations: number;
   expenses: number;
 }
 
+export interface MonthlyDataPoint extends MonthlyTotals {
+  month_label: string; // "YYYY-MM"
+  // Desktop only: running totals since the first month returned
+  cumulative?: MonthlyTotals;
+  // Desktop only, when requested through `breakdowns`
+  by_type?: Record<string, number>;
+  by_currency?: Record<string, MonthlyTotals>;
+  by_chomesh?: { chomesh: MonthlyTotals; regular: MonthlyTotals };
+}
+
+export interface MonthlySummaryBreakdowns {
+  byType?: boolean;
+  byCurrency?: boolean;
+  byChomesh?: boolean;
+}
+
 export type ServerMonthlyDataResponse = MonthlyDataPoint[];
 
 const SUPABASE_RPC_FUNCTION_NAME = "get_monthly_financial_summary";
@@ -19,7 +34,8 @@ const TAURI_COMMAND_NAME = "get_desktop_monthly_financial_summary";
 export async function fetchServerMonthlyChartData(
   userId: string | null,
   endDate: Date, // JavaScript Date object
-  numMonths: number
+  numMonths: number,
+  breakdowns?: MonthlySummaryBreakdowns
 ): Promise<ServerMonthlyDataResponse | null> {
   // Validate endDate
   if (!(endDate instanceof Date) || isNaN(endDate.getTime())) {
@@ -65,6 +81,7 @@ export async function fetchServerMonthlyChartData(
       const data = await invoke<ServerMonthlyDataResponse>(TAURI_COMMAND_NAME, {
         endDateStr: endDateStr,
         numMonths: numMonths,
+        breakdowns,
       });
       logger.log(
         "ChartService: Successfully fetched chart data (Desktop):",
@@ -134,6 +134,8 @@ export type DailyHeatmapResponse = DailyHeatmapItem[];
 export interface CategoryBreakdownItem {
   category: string;
   total_amount: number;
+  /** Desktop: the category has subcategories to drill down into */
+  has_children?: boolean;
 }
 export type CategoryBreakdownResponse = CategoryBreakdownItem[];
 
@@ -154,6 +156,8 @@ export interface DonationRecipientItem {
   recipient: string;
   total_amount: number;
   last_description?: string | null;
+  /** Set when the group is a recipient; otherwise donations grouped by description */
+  recipient_id?: string | null;
 }
 export type DonationRecipientsResponse = DonationRecipientItem[];
 
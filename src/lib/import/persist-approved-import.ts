@@ -5,6 +5,7 @@ import { supabase } from "@/lib/supabaseClient";
 import { logger } from "@/lib/logger";
 import { WEB_IMPORT_BATCH_SIZE } from "@/lib/data-layer/dataManagement/importPrepare";
 import { nanoid } from "nanoid";
+import { getErrorMessage } from "@/lib/utils/error-message";
 
 /** Fields needed for deduplication — minimal fetch. */
 const DEDUP_FIELDS = "date,amount,currency,type,description,category";
@@ -237,7 +238,7 @@ async function persistDesktop(rows: ImportPreviewRow[]): Promise<ImportResult> {
       skipped: rows.length - approved.length,
       errors: [{
         code: "save_desktop_failed",
-        detail: err instanceof Error ? err.message : String(err),
+        detail: getErrorMessage(err) ?? String(err),
       }],
     };
   }
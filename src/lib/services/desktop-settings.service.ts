@@ -168,3 +168,33 @@ export async function restoreDesktopSettings(): Promise<RestoredDesktopSettings>
 export async function restoreDesktopDefaultCurrency(): Promise<void> {
   await restoreDesktopSettings();
 }
+
+export interface DefaultCurrencyChange {
+  from: string;
+  to: string;
+  transactions: number;
+  recurring_transactions: number;
+  budgets: number;
+  paid_currencies: { currency: string; rows: number }[];
+  // Non-empty: the change is refused until these rates are stored
+  missing_rates: { base: string; quote: string; date: string; rows: number }[];
+}
+
+/**
+ * What switching the default currency would re-convert (desktop only).
+ * Existing amounts are re-based with the stored historical rates.
+ */
+export async function previewDefaultCurrencyChange(
+  currency: CurrencyCode
+): Promise<DefaultCurrencyChange> {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<DefaultCurrencyChange>("preview_default_currency_change", { currency });
+}
+
+/** Switches the default currency and re-converts all existing data into it. */
+export async function changeDefaultCurrency(
+  currency: CurrencyCode
+): Promise<DefaultCurrencyChange> {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<DefaultCurrencyChange>("change_default_currency", { currency });
+}
@@ -95,6 +95,7 @@ export const ExchangeRateService = {
         rateCache[reverseCacheKey] = { rate: reverseRate, timestamp: Date.now() };
 
         logger.log(`ExchangeRateService: Got rate from "${provider.name}" for ${from} -> ${to}: ${roundedRate}`);
+        void this.storeRate(from, to, roundedRate, provider.name);
         return roundedRate;
       } catch (error) {
         logger.warn(`ExchangeRateService: Provider "${provider.name}" failed:`, error);
@@ -107,6 +108,18 @@ export const ExchangeRateService = {
     return this.getLastKnownRate(from, to);
   },
 
+  // Desktop: keeps fetched rates in the local store so conversion works offline later.
+  async storeRate(from: CurrencyCode, to: CurrencyCode, rate: number, source: string): Promise<void> {
+    if (getPlatform() !== "desktop") return;
+    try {
+      const { invoke } = await import("@tauri-apps/api/core");
+      const date = new Date().toISOString().split("T")[0];
+      await invoke("upsert_exchange_rates", { rates: [{ date, base: from, quote: to, rate, source }] });
+    } catch (error) {
+      logger.warn("ExchangeRateService: Error storing rate in DB:", error);
+    }
+  },
+
   async getLastKnownRate(from: CurrencyCode, to: CurrencyCode): Promise<number | null> {
     const platform = getPlatform();
     
@@ -122,7 +135,7 @@ export const ExchangeRateService = {
       try {
         // Dynamic import to avoid bundling Tauri in web builds
         const { invoke } = await import("@tauri-apps/api/core");
-        // Invoke Rust command to get last known rate from DB
+        // Invoke Rust command to get the latest stored rate (or the last one used on a transaction) from DB
         const rate = await invoke<number | null>("get_last_known_rate", { fromCurrency: from, toCurrency: to });
         if (rate) {
           logger.log(`ExchangeRateService: Retrieved last known rate from DB for ${from} -> ${to}: ${rate}`);
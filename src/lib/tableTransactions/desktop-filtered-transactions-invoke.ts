@@ -1,8 +1,9 @@
 import type { Transaction } from "@/types/transaction";
 
 /**
- * Mirrors `GetFilteredTransactionsArgs` / `TableFiltersPayload` on the Tauri side
- * (`get_filtered_transactions_handler`). Single place for the desktop table query shape.
+ * Mirrors `GetFilteredTransactionsArgs` / `TransactionFiltersPayload` on the Tauri side
+ * (`get_filtered_transactions_handler`, also used by the exports). Single place for the
+ * desktop table query shape.
  */
 export type DesktopFilteredTransactionsFilters = {
   search: string | null;
@@ -10,6 +11,21 @@ export type DesktopFilteredTransactionsFilters = {
   dateTo: string | null;
   types: string[] | null;
   paymentMethods: string[] | null;
+  /** Amount as paid (original amount when converted), major units */
+  amountMin?: number | null;
+  amountMax?: number | null;
+  /** Includes subcategories */
+  categories?: string[] | null;
+  /** Currency paid in (original currency when converted) */
+  currencies?: string[] | null;
+  isChomesh?: boolean | null;
+  recipientIds?: string[] | null;
+  hasConversion?: boolean | null;
+  /** YYYY-MM-DD, inclusive */
+  createdFrom?: string | null;
+  createdTo?: string | null;
+  updatedFrom?: string | null;
+  updatedTo?: string | null;
   showOnly: string | null;
   recurringStatuses: string[] | null;
   recurringFrequencies: string[] | null;
@@ -17,13 +33,25 @@ export type DesktopFilteredTransactionsFilters = {
 
 export type DesktopFilteredTransactionsArgs = {
   filters: DesktopFilteredTransactionsFilters;
-  pagination: { page: number; limit: number };
+  /**
+   * `cursor` (a `nextCursor`/`prevCursor` from a previous response) replaces `page`;
+   * `includeTotal: false` skips counting, leaving `totalCount` null.
+   */
+  pagination: {
+    page: number;
+    limit: number;
+    cursor?: string | null;
+    includeTotal?: boolean;
+  };
   sorting: { field: string; direction: string };
 };
 
 export type DesktopFilteredTransactionsResponse = {
   transactions: Transaction[];
-  totalCount: number;
+  totalCount: number | null;
+  hasMore: boolean;
+  nextCursor: string | null;
+  prevCursor: string | null;
 };
 
 /** Default “no filters” — matches an empty table filter state. */
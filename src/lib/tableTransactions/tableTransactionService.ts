@@ -9,11 +9,17 @@ import {
   TransactionUpdatePayload, // Assuming TransactionUpdatePayload is exported from dataService or a shared types file
 } from "../data-layer"; // Adjusted path to dataService
 import { logger } from "@/lib/logger";
-import { invokeDesktopFilteredTransactions } from "@/lib/tableTransactions/desktop-filtered-transactions-invoke";
+import {
+  invokeDesktopFilteredTransactions,
+  type DesktopFilteredTransactionsFilters,
+} from "@/lib/tableTransactions/desktop-filtered-transactions-invoke";
+import { getErrorMessage } from "@/lib/utils/error-message";
 
 interface FetchTransactionsParams {
   offset: number;
   limit: number;
+  /** Desktop: continue after the previous page instead of using `offset` */
+  cursor?: string | null;
   filters: TableTransactionFilters;
   sorting: {
     field: string;
@@ -24,14 +30,42 @@ interface FetchTransactionsParams {
 
 interface FetchTransactionsResponse {
   data: Transaction[];
-  totalCount: number;
+  /** Null when a cursor page skipped counting */
+  totalCount: number | null;
+  hasMore?: boolean;
+  nextCursor?: string | null;
+}
+
+/** Table filter state → the desktop (Rust) filter payload used by the table and file exports. */
+export function toDesktopTableFilters(
+  filters: TableTransactionFilters
+): DesktopFilteredTransactionsFilters {
+  return {
+    search: filters.search || null,
+    dateFrom: filters.dateRange.from
+      ? new Date(filters.dateRange.from).toISOString().split("T")[0]
+      : null,
+    dateTo: filters.dateRange.to
+      ? new Date(filters.dateRange.to).toISOString().split("T")[0]
+      : null,
+    types: filters.types.length > 0 ? filters.types : null,
+    paymentMethods:
+      filters.paymentMethods.length > 0 ? filters.paymentMethods : null,
+    showOnly: filters.isRecurring === "all" ? null : filters.isRecurring,
+    recurringStatuses:
+      filters.recurringStatuses.length > 0 ? filters.recurringStatuses : null,
+    recurringFrequencies:
+      filters.recurringFrequencies.length > 0
+        ? filters.recurringFrequencies
+        : null,
+  };
 }
 
 export class TableTransactionsService {
   static async fetchTransactions(
     params: FetchTransactionsParams
   ): Promise<FetchTransactionsResponse> {
-    const { offset, limit, filters, sorting, platform } = params;
+    const { offset, limit, cursor, filters, sorting, platform } = params;
 
     logger.log(
       "TableTransactionsService: Fetching transactions. Platform:",
@@ -143,31 +177,13 @@ export class TableTransactionsService {
       // Desktop implementation
       try {
         const response = await invokeDesktopFilteredTransactions({
-          filters: {
-            search: filters.search || null,
-            dateFrom: filters.dateRange.from
-              ? new Date(filters.dateRange.from).toISOString().split("T")[0]
-              : null,
-            dateTo: filters.dateRange.to
-              ? new Date(filters.dateRange.to).toISOString().split("T")[0]
-              : null,
-            types: filters.types.length > 0 ? filters.types : null,
-            paymentMethods:
-              filters.paymentMethods.length > 0 ? filters.paymentMethods : null,
-            showOnly:
-              filters.isRecurring === "all" ? null : filters.isRecurring,
-            recurringStatuses:
-              filters.recurringStatuses.length > 0
-                ? filters.recurringStatuses
-                : null,
-            recurringFrequencies:
-              filters.recurringFrequencies.length > 0
-                ? filters.recurringFrequencies
-                : null,
-          },
+          filters: toDesktopTableFilters(filters),
           pagination: {
             page: offset / limit + 1, // Calculate page number for Rust
             limit: limit,
+            // Later pages continue from the cursor; the first one counted already
+            cursor: cursor ?? null,
+            includeTotal: !cursor,
           },
           sorting: {
             field: sorting.field as string,
@@ -180,7 +196,10 @@ export class TableTransactionsService {
         );
         return {
           data: response.transactions,
-          totalCount: Number(response.totalCount),
+          totalCount:
+            response.totalCount === null ? null : Number(response.totalCount),
+          hasMore: response.hasMore,
+          nextCursor: response.nextCursor,
         };
       } catch (error) {
         logger.error(
@@ -220,7 +239,7 @@ export class TableTransactionsService {
       );
       throw new Error(
         `Failed to update transaction via dataService: ${
-          error instanceof Error ? error.message : String(error)
+          getErrorMessage(error) ?? String(error)
         }`
       );
     }
@@ -245,7 +264,7 @@ export class TableTransactionsService {
       );
       throw new Error(
         `Failed to delete transaction via dataService: ${
-          error instanceof Error ? error.message : String(error)
+          getErrorMessage(error) ?? String(error)
         }`
       );
     }
@@ -8,11 +8,20 @@ import {
   initialTableSortConfig,
   initialTablePaginationState,
 } from "./tableTransactions.types"; // Updated path
-import { TableTransactionsService } from "./tableTransactionService"; // Updated path
+import {
+  TableTransactionsService,
+  toDesktopTableFilters,
+} from "./tableTransactionService"; // Updated path
 import { Platform } from "@/contexts/PlatformContext"; // Path should be relative to new location
 import { exportTransactionsToPDF } from "../utils/export-pdf"; // Updated path
-import { exportTransactionsToExcel } from "../utils/export-excel"; // Updated path
-import { exportTransactionsToCSV } from "../utils/export-csv"; // Updated path
+import {
+  exportTransactionsToExcel,
+  exportTransactionsToExcelDesktop,
+} from "../utils/export-excel"; // Updated path
+import {
+  exportTransactionsToCSV,
+  exportTransactionsToCSVDesktop,
+} from "../utils/export-csv"; // Updated path
 import { EXPORT_DESKTOP_SAVE_CANCELLED } from "../utils/save-export-file";
 import i18n from "../i18n"; // For current language
 import { logger } from "@/lib/logger";
@@ -102,6 +111,7 @@ export const useTableTransactionsStore = create<TableTransactionsState>()(
             currentPage: 1,
             totalCount: 0,
             hasMore: true,
+            nextCursor: null,
           },
           transactions: [],
         });
@@ -113,14 +123,21 @@ export const useTableTransactionsStore = create<TableTransactionsState>()(
         const offset = (currentPage - 1) * pagination.itemsPerPage;
         const limit = pagination.itemsPerPage;
 
-        const { data, totalCount: newTotalCount } =
-          await TableTransactionsService.fetchTransactions({
-            offset,
-            limit,
-            filters,
-            sorting,
-            platform,
-          });
+        const cursor = currentPage > 1 ? get().pagination.nextCursor : null;
+
+        const {
+          data,
+          totalCount: fetchedTotalCount,
+          hasMore: fetchedHasMore,
+          nextCursor,
+        } = await TableTransactionsService.fetchTransactions({
+          offset,
+          limit,
+          cursor,
+          filters,
+          sorting,
+          platform,
+        });
 
         logger.log(
           "TableTransactionsStore: Received from service - Data length:",
@@ -129,10 +146,12 @@ export const useTableTransactionsStore = create<TableTransactionsState>()(
           newTotalCount
         );
 
+        const newTotalCount = fetchedTotalCount ?? get().pagination.totalCount;
         const newTransactions = reset
           ? data
           : [...currentTransactions, ...data];
-        const hasMore = newTransactions.length < newTotalCount;
+        const hasMore =
+          fetchedHasMore ?? newTransactions.length < newTotalCount;
 
         logger.log(
           "TableTransactionsStore: New transactions length:",
@@ -148,6 +167,7 @@ export const useTableTransactionsStore = create<TableTransactionsState>()(
             currentPage,
             totalCount: newTotalCount,
             hasMore,
+            nextCursor: nextCursor ?? null,
           },
           loading: false,
         });
@@ -292,6 +312,29 @@ export const useTableTransactionsStore = create<TableTransactionsState>()(
 
       try {
         const { filters, sorting } = get();
+
+        // Desktop CSV/Excel are written by Rust straight from SQLite; nothing to fetch here.
+        if ((format === "csv" || format === "excel") && platform === "desktop") {
+          const saved =
+            format === "csv"
+              ? await exportTransactionsToCSVDesktop(
+                  toDesktopTableFilters(filters),
+                  "Ten10-transactions.csv",
+                  i18n.language
+                )
+              : await exportTransactionsToExcelDesktop(
+                  toDesktopTableFilters(filters),
+                  "Ten10-transactions.xlsx",
+                  i18n.language
+                );
+          if (!saved) {
+            set({ exportError: EXPORT_DESKTOP_SAVE_CANCELLED });
+            return;
+          }
+          trackProductEvent("transactions_exported", { format });
+          return;
+        }
+
         const { transactions: transactionsToExport, totalCount } =
           await TableTransactionsService.getDataForExport(filters, platform);
 
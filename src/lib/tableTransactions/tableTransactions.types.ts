@@ -25,6 +25,8 @@ export interface TablePaginationState {
   itemsPerPage: number;
   totalCount: number;
   hasMore: boolean;
+  /** Desktop: keyset cursor of the next page (null on the first page / web) */
+  nextCursor: string | null;
 }
 
 // Default initial values
@@ -48,4 +50,5 @@ export const initialTablePaginationState: TablePaginationState = {
   itemsPerPage: 20, // As per guide
   totalCount: 0,
   hasMore: true, // Assume more initially
+  nextCursor: null,
 };
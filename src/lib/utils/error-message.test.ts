@@ -1,5 +1,5 @@
 import { describe, it, expect } from "vitest";
-import { getErrorMessage } from "./error-message";
+import { getErrorCode, getErrorMessage } from "./error-message";
 
 // Regression test for a Copilot-flagged review comment: Supabase throws
 // PostgrestError, a plain object with `.message` (not an Error instance),
@@ -24,3 +24,21 @@ describe("getErrorMessage", () => {
     expect(getErrorMessage(undefined)).toBeUndefined();
   });
 });
+
+describe("getErrorCode", () => {
+  it("extracts the code from a desktop command error", () => {
+    const err = {
+      code: "NOT_FOUND",
+      message: "Transaction with ID t1 not found",
+      details: { entity: "Transaction", id: "t1" },
+    };
+    expect(getErrorCode(err)).toBe("NOT_FOUND");
+    expect(getErrorMessage(err)).toBe("Transaction with ID t1 not found");
+  });
+
+  it("returns undefined for errors without a string code", () => {
+    expect(getErrorCode(new Error("boom"))).toBeUndefined();
+    expect(getErrorCode("plain string")).toBeUndefined();
+    expect(getErrorCode({ code: 23505 })).toBeUndefined();
+  });
+});
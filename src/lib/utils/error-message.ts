@@ -12,3 +12,30 @@ export function getErrorMessage(err: unknown): string | undefined {
   }
   return undefined;
 }
+
+/** Stable codes of the `{ code, message, details }` errors returned by desktop (Tauri) commands. */
+export type DesktopErrorCode =
+  | "NOT_FOUND"
+  | "VALIDATION"
+  | "LOCK_POISONED"
+  | "DATABASE"
+  | "MIGRATION"
+  | "IO";
+
+export interface DesktopCommandError {
+  code: DesktopErrorCode;
+  message: string;
+  details: Record<string, unknown> | null;
+}
+
+/**
+ * Returns the error code of a desktop command error, so callers can branch on
+ * (and localize) the failure instead of matching message text.
+ */
+export function getErrorCode(err: unknown): DesktopErrorCode | undefined {
+  if (typeof err === "object" && err !== null && "code" in err) {
+    const code = (err as { code: unknown }).code;
+    if (typeof code === "string") return code as DesktopErrorCode;
+  }
+  return undefined;
+}
@@ -4,6 +4,10 @@ import { logger } from "@/lib/logger";
 import { saveOrDownloadExportedFile } from "@/lib/utils/save-export-file";
 import { formatPaymentMethod } from "@/lib/payment-methods";
 import { getRecurringExportInfo, getExportCategoryLabel } from "@/lib/utils/export-transaction-fields";
+import { PAYMENT_METHOD_KEYS } from "@/lib/payment-methods";
+import { CATEGORY_KEYS_BY_TYPE, formatCategory } from "@/lib/category-registry";
+import { transactionTypes } from "@/types/transaction";
+import type { DesktopFilteredTransactionsFilters } from "@/lib/tableTransactions/desktop-filtered-transactions-invoke";
 
 function escapeCsvCell(
   cellData: string | number | boolean | null | undefined
@@ -139,3 +143,89 @@ export async function exportTransactionsToCSV(
     mimeType: "text/csv;charset=utf-8",
   });
 }
+
+/** Column keys of the Rust CSV export (`EXPORT_COLUMNS` in export_commands.rs) → data-tables header keys. */
+const DESKTOP_CSV_HEADER_KEYS: Record<string, string> = {
+  date: "columns.date",
+  type: "columns.type",
+  description: "columns.description",
+  category: "columns.category",
+  recipient: "columns.recipient",
+  payment_method: "columns.paymentMethod",
+  amount: "columns.amount",
+  currency: "columns.currency",
+  is_chomesh: "columns.chomesh",
+  movement_type: "columns.movementType",
+  recurring_frequency: "columns.recurringStatus",
+  recurring_progress: "columns.recurringProgress",
+  id: "columns.id",
+  user_id: "columns.userId",
+  created_at: "columns.createdAt",
+  updated_at: "columns.updatedAt",
+  source_recurring_id: "columns.sourceRecurringId",
+  original_amount: "columns.originalAmount",
+  original_currency: "columns.originalCurrency",
+  conversion_rate: "columns.conversionRate",
+};
+
+/** Localized headers and value labels sent to `export_transactions_csv`. */
+export function buildDesktopCsvLocalization(currentLanguage: string): {
+  headers: Record<string, string>;
+  labels: Record<string, string>;
+} {
+  const t = (key: string, ns: string) => i18n.t(key, { lng: currentLanguage, ns });
+
+  const headers: Record<string, string> = {};
+  for (const [column, key] of Object.entries(DESKTOP_CSV_HEADER_KEYS)) {
+    headers[column] = t(key, "data-tables");
+  }
+
+  const labels: Record<string, string> = {
+    "boolean.yes": t("boolean.yes", "common"),
+    "boolean.no": t("boolean.no", "common"),
+    "movement.recurring": t("movementType.recurring", "data-tables"),
+    "movement.regular": t("movementType.regular", "data-tables"),
+  };
+  for (const type of transactionTypes) {
+    labels[`type.${type}`] = t(`export.transactionTypes.${type}`, "common") || type;
+  }
+  for (const frequency of ["daily", "weekly", "monthly", "yearly"]) {
+    labels[`frequency.${frequency}`] = t(`pdf.frequencies.${frequency}`, "common") || frequency;
+  }
+  for (const method of PAYMENT_METHOD_KEYS) {
+    labels[`payment_method.${method}`] = formatPaymentMethod(method, currentLanguage);
+  }
+  for (const baseType of ["income", "expense"] as const) {
+    for (const category of CATEGORY_KEYS_BY_TYPE[baseType]) {
+      labels[`category.${baseType}.${category}`] = formatCategory(baseType, category, currentLanguage);
+    }
+  }
+  return { headers, labels };
+}
+
+/**
+ * Desktop: the user picks the file, then Rust streams the matching rows straight
+ * to disk (no transactions cross IPC). Returns false if the save dialog was cancelled.
+ */
+export async function exportTransactionsToCSVDesktop(
+  filters: DesktopFilteredTransactionsFilters,
+  filename = "Ten10-transactions.csv",
+  currentLanguage: string = "he"
+): Promise<boolean> {
+  const { save } = await import("@tauri-apps/plugin-dialog");
+  const { invoke } = await import("@tauri-apps/api/core");
+
+  const path = await save({
+    defaultPath: `${filename}_${new Date().toISOString().split("T")[0]}.csv`,
+    filters: [{ name: "CSV", extensions: ["csv"] }],
+  });
+  if (!path) return false;
+
+  const rows = await invoke<number>("export_transactions_csv", {
+    filters,
+    path,
+    options: buildDesktopCsvLocalization(currentLanguage),
+  });
+  logger.log(`Desktop CSV export: ${rows} rows written to ${path}`);
+  return true;
+}
@@ -4,6 +4,9 @@ import i18n from "@/lib/i18n";
 import { formatPaymentMethod } from "@/lib/payment-methods";
 import { getRecurringExportInfo, getExportCategoryLabel } from "@/lib/utils/export-transaction-fields";
 import { saveOrDownloadExportedFile } from "@/lib/utils/save-export-file";
+import { buildDesktopCsvLocalization } from "@/lib/utils/export-csv";
+import type { DesktopFilteredTransactionsFilters } from "@/lib/tableTransactions/desktop-filtered-transactions-invoke";
+import { logger } from "@/lib/logger";
 
 export async function exportTransactionsToExcel(
   transactions: Transaction[],
@@ -218,3 +221,57 @@ export async function exportTransactionsToExcel(
       "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
   });
 }
+
+const DESKTOP_XLSX_CAPTION_KEYS: Record<string, string> = {
+  "sheet.summary": "export.excel.sheets.summary",
+  "sheet.income": "export.excel.sheets.income",
+  "sheet.expenses": "export.excel.sheets.expenses",
+  "sheet.donations": "export.excel.sheets.donations",
+  "sheet.other": "export.excel.sheets.other",
+  "summary.tithe_balance": "export.excel.summary.titheBalance",
+  "summary.maaser_balance": "export.excel.summary.maaserBalance",
+  "summary.chomesh_balance": "export.excel.summary.chomeshBalance",
+  "summary.month": "export.excel.summary.month",
+  "summary.income": "export.excel.summary.income",
+  "summary.donations": "export.excel.summary.donations",
+  "summary.expenses": "export.excel.summary.expenses",
+  "summary.total": "export.excel.summary.total",
+};
+
+/**
+ * Desktop: Rust builds the workbook from SQLite (summary sheet + a sheet per
+ * transaction group) and writes it to the file the user picks.
+ * Returns false if the save dialog was cancelled.
+ */
+export async function exportTransactionsToExcelDesktop(
+  filters: DesktopFilteredTransactionsFilters,
+  filename = "Ten10-transactions.xlsx",
+  currentLanguage: string = "he"
+): Promise<boolean> {
+  const { save } = await import("@tauri-apps/plugin-dialog");
+  const { invoke } = await import("@tauri-apps/api/core");
+
+  const baseName = filename.replace(/\.xlsx$/i, "");
+  const path = await save({
+    defaultPath: `${baseName}-${new Date().toISOString().split("T")[0]}.xlsx`,
+    filters: [{ name: "Excel", extensions: ["xlsx"] }],
+  });
+  if (!path) return false;
+
+  const { headers, labels } = buildDesktopCsvLocalization(currentLanguage);
+  for (const [key, i18nKey] of Object.entries(DESKTOP_XLSX_CAPTION_KEYS)) {
+    labels[key] = i18n.t(i18nKey, { lng: currentLanguage, ns: "common" });
+  }
+
+  const rows = await invoke<number>("export_transactions_xlsx", {
+    filters,
+    path,
+    options: {
+      headers,
+      labels,
+      rightToLeft: currentLanguage === "he",
+    },
+  });
+  logger.log(`Desktop Excel export: ${rows} rows written to ${path}`);
+  return true;
+}
@@ -22,11 +22,17 @@ import { ImportExportDataSection } from "@/components/settings/ImportExportDataS
 import { VersionInfoCard } from "@/components/settings/VersionInfoCard";
 import { AppLockSettingsCard } from "@/components/settings/AppLockSettingsCard";
 import { OpeningBalanceModal } from "@/components/settings/OpeningBalanceModal";
+import { DefaultCurrencyChangeDialog } from "@/components/settings/DefaultCurrencyChangeDialog";
 import { ImportConfirmModal } from "@/components/settings/ImportConfirmModal";
 import { ImportDuplicatesModal } from "@/components/settings/ImportDuplicatesModal";
 import { logger } from "@/lib/logger";
 import { Transaction } from "@/types/transaction";
 import { CurrencyCode } from "@/lib/currencies";
+import {
+  changeDefaultCurrency,
+  DefaultCurrencyChange,
+  previewDefaultCurrencyChange,
+} from "@/lib/services/desktop-settings.service";
 
 import { useIsCurrencyLocked } from "@/hooks/useIsCurrencyLocked";
 import { trackProductEvent } from "@/lib/analytics/productAnalytics";
@@ -96,6 +102,44 @@ export function SettingsPage() {
     }
   };
 
+  const [pendingCurrencyChange, setPendingCurrencyChange] =
+    useState<DefaultCurrencyChange | null>(null);
+  const [isChangingCurrency, setIsChangingCurrency] = useState(false);
+
+  // Desktop: existing data is re-converted into the new default currency, so the
+  // store only follows once the database has changed.
+  const applyDesktopCurrencyChange = async (currency: CurrencyCode) => {
+    setIsChangingCurrency(true);
+    try {
+      await changeDefaultCurrency(currency);
+      updateSettings({ defaultCurrency: currency });
+      useDonationStore.getState().setLastDbFetchTimestamp(Date.now());
+      trackProductEvent("settings_changed", { setting_key: "currency" });
+      setPendingCurrencyChange(null);
+    } catch (error) {
+      logger.error("Failed to change the default currency:", error);
+      toast.error(tCommon("toast.settings.updateError"));
+    } finally {
+      setIsChangingCurrency(false);
+    }
+  };
+
+  const requestDesktopCurrencyChange = async (currency: CurrencyCode) => {
+    try {
+      const preview = await previewDefaultCurrencyChange(currency);
+      const converted =
+        preview.transactions + preview.recurring_transactions + preview.budgets;
+      if (converted === 0) {
+        await applyDesktopCurrencyChange(currency);
+      } else {
+        setPendingCurrencyChange(preview);
+      }
+    } catch (error) {
+      logger.error("Failed to preview the default currency change:", error);
+      toast.error(tCommon("toast.settings.updateError"));
+    }
+  };
+
   const handleSetTheme = (newTheme: "light" | "dark" | "system") => {
     setTheme(newTheme);
     updateSettings({ theme: newTheme });
@@ -128,6 +172,15 @@ export function SettingsPage() {
         minMaaserPercentage: settings.minMaaserPercentage,
       }}
       updateSettings={(newFinancialSettings) => {
+        if (platform === "desktop" && newFinancialSettings.defaultCurrency) {
+          const { defaultCurrency, ...rest } = newFinancialSettings;
+          if (Object.keys(rest).length > 0) updateSettings(rest);
+          if (defaultCurrency !== settings.defaultCurrency) {
+            requestDesktopCurrencyChange(defaultCurrency);
+          }
+          return;
+        }
+
         updateSettings(newFinancialSettings);
 
         if (newFinancialSettings.defaultCurrency) {
@@ -157,7 +210,8 @@ export function SettingsPage() {
       }}
       disableMinMaaserPercentage={true}
       onOpenBalanceModal={handleOpenBalanceModal}
-      currencyLocked={isCurrencyLocked}
+      currencyLocked={isCurrencyLocked && platform !== "desktop"}
+      currencyConvertible={platform === "desktop"}
     />
   );
 
@@ -359,6 +413,16 @@ export function SettingsPage() {
           onClose={() => setIsOpeningBalanceModalOpen(false)}
           onUpdate={handleUpdateOpeningBalance}
         />
+
+        <DefaultCurrencyChangeDialog
+          change={pendingCurrencyChange}
+          isChanging={isChangingCurrency}
+          onConfirm={() =>
+            pendingCurrencyChange &&
+            applyDesktopCurrencyChange(pendingCurrencyChange.to as CurrencyCode)
+          }
+          onCancel={() => setPendingCurrencyChange(null)}
+        />
       </div>
     </>
   );